@@ -0,0 +1,149 @@
+//! Micro-benchmarks of the request handling of the [`HttpServer`], measuring the time from writing a request to reading its response over
+//! an in-memory connection. The transport costs next to nothing, so the results are dominated by parsing the request, running the router
+//! and serializing the response.
+//!
+//! ```text
+//! cargo bench --features esp --bench round_trip
+//! ```
+//!
+//! Every benchmark runs twice, once in the `round_trip` group, and once in the `pooled_round_trip` group with a
+//! [`response_buffer_pool`](HttpServer::response_buffer_pool), so the two can be compared.
+
+use std::io::{
+    self,
+    BufRead,
+    BufReader,
+    Read,
+    Write,
+};
+
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+    testing::{
+        MemoryListener,
+        MemoryStream,
+    },
+};
+use tokio::runtime::Runtime;
+
+/// The size of the body of the `/large` route.
+const LARGE_BODY: usize = 64 * 1024;
+
+/// The router answering the benchmarked requests.
+fn router() -> Router {
+    Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/large", get(|| async { "x".repeat(LARGE_BODY) }))
+}
+
+/// Read a single response with a `Content-Length` from the given connection, and return the size of its body.
+fn read_response(connection: &mut BufReader<MemoryStream>, line: &mut String) -> u64 {
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        assert_ne!(
+            connection.read_line(line).unwrap(),
+            0,
+            "the connection was closed"
+        );
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    io::copy(
+        &mut connection.by_ref().take(content_length),
+        &mut io::sink(),
+    )
+    .unwrap()
+}
+
+/// Benchmark every request of [`router`] against the given HttpServer, in a group with the given name.
+fn bench_http_server(c: &mut Criterion, group_name: &str, mut http_server: HttpServer) {
+    let runtime = Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let (listener, connector) = MemoryListener::new();
+    http_server.serve_on(listener, router()).unwrap();
+
+    let mut many_headers = b"GET / HTTP/1.1\r\nHost: 192.168.4.1\r\n".to_vec();
+    for index in 0..32 {
+        many_headers
+            .extend_from_slice(format!("X-Header-{index}: {}\r\n", "v".repeat(32)).as_bytes());
+    }
+    many_headers.extend_from_slice(b"\r\n");
+
+    let cases: [(&str, Vec<u8>, usize); 4] = [
+        (
+            "small",
+            b"GET / HTTP/1.1\r\nHost: 192.168.4.1\r\n\r\n".to_vec(),
+            1,
+        ),
+        ("many_headers", many_headers, 1),
+        (
+            "large_body",
+            b"GET /large HTTP/1.1\r\nHost: 192.168.4.1\r\n\r\n".to_vec(),
+            1,
+        ),
+        (
+            "pipelined",
+            b"GET / HTTP/1.1\r\nHost: 192.168.4.1\r\n\r\n".repeat(8),
+            8,
+        ),
+    ];
+
+    let mut group = c.benchmark_group(group_name);
+    for (name, request, responses) in cases {
+        let mut connection = BufReader::new(connector.connect().unwrap());
+        let mut line = String::new();
+        group.throughput(Throughput::Elements(responses as u64));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                connection.get_mut().write_all(&request).unwrap();
+                for _ in 0..responses {
+                    read_response(&mut connection, &mut line);
+                }
+            });
+        });
+    }
+    group.finish();
+
+    runtime.block_on(http_server.shutdown());
+}
+
+fn round_trip(c: &mut Criterion) {
+    bench_http_server(
+        c,
+        "round_trip",
+        HttpServer::bind("127.0.0.1:0", None, None).unwrap(),
+    );
+}
+
+/// The same requests as [`round_trip`], answered with a [`response_buffer_pool`](HttpServer::response_buffer_pool) large enough for
+/// every response.
+fn pooled_round_trip(c: &mut Criterion) {
+    bench_http_server(
+        c,
+        "pooled_round_trip",
+        HttpServer::bind("127.0.0.1:0", None, None)
+            .unwrap()
+            .response_buffer_pool(8, LARGE_BODY + 1024),
+    );
+}
+
+criterion_group!(benches, round_trip, pooled_round_trip);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+//! Generates load on an [`HttpServer`] and prints the throughput, the latency percentiles and the number of allocations per request.
+//!
+//! ```text
+//! cargo run --release --features esp --example loadgen -- [connections] [requests] [pipeline depth] [address]
+//! ```
+//!
+//! Every one of the `connections` (default `16`) keep-alive connections sends `requests` (default `1000`) requests to `/`, writing
+//! `pipeline depth` (default `1`) requests at once before reading their responses. \
+//! Without an address, an HttpServer serving a small test router gets started in this process, so the allocations of both the server
+//! and the client get counted. With an address, like the one of an HttpServer running on a device, only the client runs here and no
+//! allocations get reported. The client only uses the standard library.
+
+use std::{
+    alloc::{
+        GlobalAlloc,
+        Layout,
+        System,
+    },
+    env,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Read,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+};
+use tokio::runtime::Runtime;
+
+/// Counts the allocations of this process.
+struct CountingAllocator;
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// The number of allocations since this process was started.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Get the positional argument at the given index, or the given default if it is missing.
+fn argument<T: std::str::FromStr>(index: usize, default: T) -> T {
+    env::args()
+        .nth(index)
+        .map(|argument| {
+            argument
+                .parse()
+                .unwrap_or_else(|_| panic!("The argument `{argument}` is invalid."))
+        })
+        .unwrap_or(default)
+}
+
+/// Read a single response with a `Content-Length` from the given connection.
+fn read_response(connection: &mut BufReader<TcpStream>, line: &mut String) -> io::Result<()> {
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if connection.read_line(line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if line == "\r\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+            }
+        }
+    }
+    io::copy(
+        &mut connection.by_ref().take(content_length),
+        &mut io::sink(),
+    )?;
+    Ok(())
+}
+
+/// Send the given number of requests over a new connection to the given address, and return the latency of every one of them. The
+/// latency of a pipelined request is the time from writing its batch until its response was read.
+fn run_connection(addr: SocketAddr, requests: usize, depth: usize) -> io::Result<Vec<Duration>> {
+    let request = format!("GET / HTTP/1.1\r\nHost: {addr}\r\n\r\n");
+    let mut connection = BufReader::new(TcpStream::connect(addr)?);
+    connection.get_ref().set_nodelay(true)?;
+    let mut latencies = Vec::with_capacity(requests);
+    let mut line = String::new();
+
+    let mut sent = 0;
+    while sent < requests {
+        let batch = depth.min(requests - sent);
+        let started = Instant::now();
+        connection
+            .get_mut()
+            .write_all(request.repeat(batch).as_bytes())?;
+        for _ in 0..batch {
+            read_response(&mut connection, &mut line)?;
+            latencies.push(started.elapsed());
+        }
+        sent += batch;
+    }
+    Ok(latencies)
+}
+
+fn main() {
+    let connections: usize = argument(1, 16);
+    let requests: usize = argument(2, 1000);
+    let depth: usize = argument(3, 1).max(1);
+    let target: Option<SocketAddr> = env::args()
+        .nth(4)
+        .map(|addr| addr.parse().expect("The address is invalid."));
+
+    let runtime = Runtime::new().unwrap();
+    let mut http_server = None;
+    let addr = target.unwrap_or_else(|| {
+        let _guard = runtime.enter();
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut server = HttpServer::bind(addr, None, None).unwrap();
+        server
+            .serve(Router::new().route("/", get(|| async { "Hello, World!" })))
+            .unwrap();
+        http_server = Some(server);
+        addr
+    });
+    // give the HttpServer time to start accepting connections
+    thread::sleep(Duration::from_millis(100));
+
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    let started = Instant::now();
+    let clients: Vec<_> = (0..connections)
+        .map(|_| thread::spawn(move || run_connection(addr, requests, depth)))
+        .collect();
+    let mut latencies = vec![];
+    for client in clients {
+        match client.join().unwrap() {
+            Ok(client_latencies) => latencies.extend(client_latencies),
+            Err(error) => eprintln!("A connection failed: {error}"),
+        }
+    }
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations;
+
+    if latencies.is_empty() {
+        eprintln!("No request was answered.");
+        return;
+    }
+    latencies.sort_unstable();
+    let percentile = |percent: usize| latencies[(latencies.len() - 1) * percent / 100];
+
+    println!(
+        "{} requests over {connections} connections (pipeline depth {depth}) to {addr} in {elapsed:?}",
+        latencies.len()
+    );
+    println!(
+        "Throughput: {:.0} requests/s",
+        latencies.len() as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "Latency: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+        percentile(50),
+        percentile(90),
+        percentile(99),
+        percentile(100)
+    );
+    if let Some(mut http_server) = http_server {
+        println!(
+            "Allocations: {:.1} per request (server and client)",
+            allocations as f64 / latencies.len() as f64
+        );
+        runtime.block_on(http_server.shutdown());
+    }
+}
@@ -0,0 +1,63 @@
+//! This module provides helpers for build scripts of crates using the [`router`](crate::router) macro.
+//!
+//! The [`router`](crate::router) macro declares a module for every route and group, e.g. `mod say_hello;`. If the file behind one of them
+//! is missing, the compiler reports it at the macro call, without telling which file it looked for. Checking the files in the build
+//! script of the crate instead gives a message naming every missing file:
+//! ```no_run
+//! // build.rs
+//! fn main() {
+//!     goohttp::build::verify_route_modules(
+//!         env!("CARGO_MANIFEST_DIR"),
+//!         &[("src", "api"), ("src/api", "say_hello"), ("src/api", "update_settings")],
+//!     );
+//! }
+//! ```
+//! Add goohttp to the `[build-dependencies]` of the crate for this.
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+/// Check that the file of every given route module exists, and tell Cargo to run the build script again once one of them gets created or
+/// removed. \
+/// Every route definition consists of the directory the modules of a router are declared in, relative to the given manifest directory,
+/// and the name of a route or group of this router. For a router in `src/api/mod.rs` or `src/api.rs`, this directory is `src/api`, while
+/// it is `src` for a router in `src/main.rs` or `src/lib.rs`. Like the compiler, both `<directory>/<name>.rs` and `<directory>/<name>/mod.rs`
+/// are accepted.
+///
+/// # Panics
+///
+/// This function panics with a message naming the files it looked for if the file of any route module is missing. Each of them also gets
+/// reported as a Cargo warning.
+pub fn verify_route_modules(manifest_dir: &str, route_definitions: &[(&str, &str)]) {
+    let manifest_dir = Path::new(manifest_dir);
+    let mut missing = vec![];
+    for (directory, name) in route_definitions {
+        let candidates = module_files(&manifest_dir.join(directory), name);
+        for candidate in &candidates {
+            println!("cargo:rerun-if-changed={}", candidate.display());
+        }
+        if !candidates.iter().any(|candidate| candidate.is_file()) {
+            let message = format!(
+                "goohttp: the module of the route `{name}` is missing. Create either `{}` or `{}`.",
+                candidates[0].display(),
+                candidates[1].display()
+            );
+            println!("cargo:warning={message}");
+            missing.push(message);
+        }
+    }
+
+    if !missing.is_empty() {
+        panic!("{}", missing.join("\n"));
+    }
+}
+
+/// Get the files the module with the given name could be declared in.
+fn module_files(directory: &Path, name: &str) -> [PathBuf; 2] {
+    [
+        directory.join(format!("{name}.rs")),
+        directory.join(name).join("mod.rs"),
+    ]
+}
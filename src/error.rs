@@ -0,0 +1,262 @@
+//! This module provides the [`Error`] returned by the [`HttpServer`](crate::http_server::HttpServer).
+
+use std::{
+    fmt,
+    io::{
+        self,
+        ErrorKind,
+    },
+};
+
+use http::StatusCode;
+
+use crate::http_server::ConfigError;
+
+/// Everything that can go wrong while setting up or running an [`HttpServer`](crate::http_server::HttpServer). \
+/// It can be converted into an [`io::Error`] for code only dealing with those.
+#[derive(Debug)]
+pub enum Error {
+    /// The TcpListener could not be bound to the address of the HttpServer or could not be switched to non-blocking mode.
+    Bind(io::Error),
+    /// The address given to the HttpServer could not be resolved to a socket address.
+    Resolve,
+    /// An incoming connection could not be accepted.
+    Accept(io::Error),
+    /// A client sent a request which could not be parsed.
+    Parse(ParseError),
+    /// Reading from or writing to a connection failed.
+    Io(io::Error),
+    /// A response could not be turned into bytes.
+    Serialize(SerializeError),
+    /// The HttpServer is already serving.
+    AlreadyRunning,
+    /// The settings of the HttpServer contradict each other.
+    Config(ConfigError),
+    /// The task accepting connections panicked or got cancelled. The message of the panic is included if it had one.
+    Task(String),
+    /// The sockets passed by systemd could not be taken over by [`from_systemd`](crate::http_server::HttpServer::from_systemd).
+    Activation(ActivationError),
+    /// The [`dedicated_runtime`](crate::http_server::HttpServer::dedicated_runtime) could not be created.
+    Runtime(io::Error),
+    /// A request of the self-test of [`serve_with_selftest`](crate::http_server::HttpServer::serve_with_selftest) was answered with a
+    /// server error. The status is `None` if the route panicked or the request could not be built from the given method and path.
+    SelfTest {
+        /// The path of the failed request.
+        path: String,
+        /// The status of the response to the failed request.
+        status: Option<StatusCode>,
+    },
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bind(error) => write!(f, "Bind: The TcpListener could not be set up. {error}"),
+            Self::Resolve => write!(
+                f,
+                "Resolve: The address could not be resolved to a socket address."
+            ),
+            Self::Accept(error) => write!(f, "Accept: A connection could not be accepted. {error}"),
+            Self::Parse(error) => write!(f, "Parse: {error}"),
+            Self::Io(error) => write!(f, "Io: {error}"),
+            Self::Serialize(error) => write!(f, "Serialize: {error}"),
+            Self::AlreadyRunning => write!(f, "AlreadyRunning: The HttpServer is already serving."),
+            Self::Config(error) => write!(f, "Config: {error}"),
+            Self::Task(message) => write!(
+                f,
+                "Task: The task accepting connections did not stop cleanly. {message}"
+            ),
+            Self::Activation(error) => write!(f, "Activation: {error}"),
+            Self::Runtime(error) => write!(f, "Runtime: The runtime could not be created. {error}"),
+            Self::SelfTest {
+                path,
+                status: Some(status),
+            } => write!(
+                f,
+                "SelfTest: The request to `{path}` was answered with `{status}`."
+            ),
+            Self::SelfTest { path, status: None } => write!(
+                f,
+                "SelfTest: The request to `{path}` panicked or could not be built."
+            ),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bind(error) | Self::Accept(error) | Self::Io(error) | Self::Runtime(error) => {
+                Some(error)
+            }
+            Self::Parse(error) => Some(error),
+            Self::Serialize(error) => Some(error),
+            Self::Config(error) => Some(error),
+            Self::Activation(error) => Some(error),
+            Self::Resolve | Self::AlreadyRunning | Self::Task(_) | Self::SelfTest { .. } => None,
+        }
+    }
+}
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+impl From<SerializeError> for Error {
+    fn from(error: SerializeError) -> Self {
+        Self::Serialize(error)
+    }
+}
+impl From<ConfigError> for Error {
+    fn from(error: ConfigError) -> Self {
+        Self::Config(error)
+    }
+}
+impl From<ActivationError> for Error {
+    fn from(error: ActivationError) -> Self {
+        Self::Activation(error)
+    }
+}
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Bind(error)
+            | Error::Accept(error)
+            | Error::Io(error)
+            | Error::Runtime(error) => error,
+            Error::Resolve | Error::Config(_) | Error::Activation(_) => {
+                io::Error::new(ErrorKind::InvalidInput, error)
+            }
+            Error::Parse(_) => io::Error::new(ErrorKind::InvalidData, error),
+            Error::AlreadyRunning => io::Error::new(ErrorKind::AlreadyExists, error),
+            Error::Serialize(_) | Error::Task(_) | Error::SelfTest { .. } => {
+                io::Error::other(error)
+            }
+        }
+    }
+}
+
+/// Describes what is wrong with a request a client sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The request line does not consist of a method, a request-target, and a version.
+    RequestLine,
+    /// The method of the request is not a valid token.
+    Method,
+    /// The request-target of the request is not a valid URI.
+    Uri,
+    /// The request uses an HTTP version other than HTTP/1.0 or HTTP/1.1.
+    Version,
+    /// A header line is missing its colon, or its name or value contain invalid characters.
+    Header,
+    /// The `Content-Length` header is not a number.
+    ContentLength,
+    /// The request uses a `Transfer-Encoding` other than `chunked`, combines it with a `Content-Length`, or uses it with HTTP/1.0.
+    TransferEncoding,
+    /// A chunk of a request body sent with `Transfer-Encoding: chunked` is malformed.
+    Chunk,
+    /// A header line of the request is longer than [`max_header_value_bytes`](crate::http_server::HttpServer::max_header_value_bytes)
+    /// allows.
+    HeaderTooLarge,
+    /// The request line of the request is longer than [`max_header_value_bytes`](crate::http_server::HttpServer::max_header_value_bytes)
+    /// allows for a header line.
+    UriTooLong,
+    /// The request has more headers than [`max_request_headers`](crate::http_server::HttpServer::max_request_headers) allows.
+    TooManyHeaders,
+    /// The connection did not start with a valid PROXY protocol header, although
+    /// [`proxy_protocol`](crate::http_server::HttpServer::proxy_protocol) is enabled.
+    ProxyHeader,
+    /// The path of a request frame sent to an [`RpcServer`](crate::rpc_server::RpcServer) is too long, not valid UTF-8, or does not
+    /// start with a `/`.
+    RpcFrame,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RequestLine => write!(f, "The request line is malformed."),
+            Self::Method => write!(f, "The method of the request is invalid."),
+            Self::Uri => write!(f, "The request-target of the request is invalid."),
+            Self::Version => write!(f, "The HTTP version of the request is not supported."),
+            Self::Header => write!(f, "A header of the request is malformed."),
+            Self::ContentLength => write!(f, "The `Content-Length` of the request is invalid."),
+            Self::TransferEncoding => write!(
+                f,
+                "The request uses a `Transfer-Encoding`, which is not supported."
+            ),
+            Self::Chunk => write!(f, "A chunk of the request body is malformed."),
+            Self::HeaderTooLarge => write!(f, "A header of the request is too large."),
+            Self::UriTooLong => write!(f, "The request line of the request is too long."),
+            Self::TooManyHeaders => write!(f, "The request has too many headers."),
+            Self::ProxyHeader => write!(
+                f,
+                "The connection did not start with a valid PROXY protocol header."
+            ),
+            Self::RpcFrame => write!(f, "The path of the request frame is invalid."),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+/// Describes why a response could not be turned into bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SerializeError {
+    /// The body of the response returned an error while being read.
+    Body,
+    /// The status code of the response has no reason phrase to write into the status line.
+    Status(u16),
+    /// A critical header of the response, like `Location`, contains characters not allowed in a header value.
+    Header,
+    /// The body of the response is larger than the 4 GiB a response frame of an [`RpcServer`](crate::rpc_server::RpcServer) can hold.
+    FrameTooLarge,
+}
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body => write!(f, "The body of the response could not be read."),
+            Self::Status(status) => write!(f, "The status code {status} is unknown."),
+            Self::Header => write!(f, "A critical header of the response is invalid."),
+            Self::FrameTooLarge => write!(f, "The body of the response does not fit into a frame."),
+        }
+    }
+}
+impl std::error::Error for SerializeError {}
+
+/// Describes why the sockets passed by systemd could not be taken over by
+/// [`from_systemd`](crate::http_server::HttpServer::from_systemd).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActivationError {
+    /// The `LISTEN_PID` or `LISTEN_FDS` variable is not set, so the process was not started by socket activation.
+    NotActivated,
+    /// The given variable is not a number.
+    InvalidVariable(&'static str),
+    /// The sockets were passed to the process with the given id, e.g. to the parent of this process.
+    OtherProcess(u32),
+    /// `LISTEN_FDS` is `0`, so there is no socket to listen on.
+    NoSockets,
+    /// The file descriptor with the given number is not a TCP socket.
+    NotTcp(i32),
+    /// The sockets were already taken over by an earlier call.
+    AlreadyTaken,
+}
+impl fmt::Display for ActivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotActivated => write!(
+                f,
+                "`LISTEN_PID` and `LISTEN_FDS` are not set. The process was not started by socket activation."
+            ),
+            Self::InvalidVariable(variable) => write!(f, "`{variable}` is not a number."),
+            Self::OtherProcess(pid) => write!(
+                f,
+                "The sockets were passed to the process {pid} instead of this one."
+            ),
+            Self::NoSockets => write!(f, "`LISTEN_FDS` is 0. No socket was passed."),
+            Self::NotTcp(fd) => write!(f, "The file descriptor {fd} is not a TCP socket."),
+            Self::AlreadyTaken => write!(f, "The sockets were already taken over."),
+        }
+    }
+}
+impl std::error::Error for ActivationError {}
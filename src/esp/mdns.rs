@@ -0,0 +1,92 @@
+//! This module provides the [`MdnsAdvertiser`], which lets local clients find an [`HttpServer`](crate::http_server::HttpServer) under a
+//! name like `device.local`.
+
+/// The mDNS implementation an [`MdnsAdvertiser`] registers its service with. \
+/// With the `esp-mdns` feature, it is implemented for `esp_idf_svc::mdns::EspMdns`, which requires the mDNS component to be enabled in
+/// ESP-IDF:
+///
+/// ```no_run
+/// # #[cfg(all(feature = "esp-mdns", target_os = "espidf"))]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let http_server = goohttp::http_server::HttpServer::bind("0.0.0.0:80", None, None)?;
+/// use esp_idf_svc::mdns::EspMdns;
+///
+/// let advertiser = http_server.advertise_mdns(EspMdns::take()?, "device", "My device")?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "esp-mdns", target_os = "espidf")))]
+/// # fn main() {}
+/// ```
+pub trait MdnsBackend {
+    /// The error returned if the backend failed to apply a change.
+    type Error;
+
+    /// Set the hostname the device gets found under, without the `.local` suffix.
+    fn set_hostname(&mut self, hostname: &str) -> Result<(), Self::Error>;
+    /// Announce a service with the given instance name, type, and protocol on the given port.
+    fn add_service(
+        &mut self,
+        instance: &str,
+        service_type: &str,
+        protocol: &str,
+        port: u16,
+    ) -> Result<(), Self::Error>;
+    /// Stop announcing the service with the given type and protocol.
+    fn remove_service(&mut self, service_type: &str, protocol: &str) -> Result<(), Self::Error>;
+}
+
+#[cfg(all(feature = "esp-mdns", target_os = "espidf"))]
+impl MdnsBackend for esp_idf_svc::mdns::EspMdns {
+    type Error = esp_idf_svc::sys::EspError;
+
+    fn set_hostname(&mut self, hostname: &str) -> Result<(), Self::Error> {
+        Self::set_hostname(self, hostname)
+    }
+    fn add_service(
+        &mut self,
+        instance: &str,
+        service_type: &str,
+        protocol: &str,
+        port: u16,
+    ) -> Result<(), Self::Error> {
+        Self::add_service(self, Some(instance), service_type, protocol, port, &[])
+    }
+    fn remove_service(&mut self, service_type: &str, protocol: &str) -> Result<(), Self::Error> {
+        Self::remove_service(self, service_type, protocol)
+    }
+}
+
+/// The service type HTTP servers get announced with.
+const SERVICE_TYPE: &str = "_http";
+/// The protocol HTTP servers get announced with.
+const PROTOCOL: &str = "_tcp";
+
+/// Announces an [`HttpServer`](crate::http_server::HttpServer) as an `_http._tcp` service until it gets dropped. \
+/// It is created with [`HttpServer::advertise_mdns`](crate::http_server::HttpServer::advertise_mdns).
+pub struct MdnsAdvertiser<B: MdnsBackend> {
+    /// The backend the service is registered with.
+    backend: B,
+}
+impl<B: MdnsBackend> MdnsAdvertiser<B> {
+    /// Set the given hostname and register an `_http._tcp` service with the given instance name and port at the given backend.
+    ///
+    /// # Errors
+    ///
+    /// The error of the backend is returned if it failed to set the hostname or to register the service.
+    pub fn new(
+        mut backend: B,
+        hostname: &str,
+        instance: &str,
+        port: u16,
+    ) -> Result<Self, B::Error> {
+        backend.set_hostname(hostname)?;
+        backend.add_service(instance, SERVICE_TYPE, PROTOCOL, port)?;
+        Ok(Self { backend })
+    }
+}
+impl<B: MdnsBackend> Drop for MdnsAdvertiser<B> {
+    fn drop(&mut self) {
+        // the service is gone either way once the backend gets dropped
+        let _ = self.backend.remove_service(SERVICE_TYPE, PROTOCOL);
+    }
+}
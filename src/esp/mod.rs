@@ -0,0 +1,3 @@
+//! This module provides helpers for running an [`HttpServer`](crate::http_server::HttpServer) on devices like the ESP32.
+
+pub mod mdns;
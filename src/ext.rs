@@ -0,0 +1,50 @@
+//! This module provides extension traits for types of [`axum`].
+
+use std::io;
+
+use axum::{
+    async_trait,
+    body::Body,
+    http::Request,
+    response::Response,
+    Router,
+};
+use tower_service::Service;
+
+/// Adds methods to the [`Router`] of axum.
+#[async_trait]
+pub trait RouterExt {
+    /// Answer the given request with this router. \
+    /// Routing itself cannot fail, since axum turns every error of its routes into a response, so this always returns the response. It
+    /// exists for callers preferring to handle an error explicitly over relying on the `Infallible` error type of
+    /// [`call`](Service::call):
+    /// ```
+    /// # async fn example() -> std::io::Result<()> {
+    /// use goohttp::{
+    ///     axum::{
+    ///         body::Body,
+    ///         http::Request,
+    ///         routing::get,
+    ///         Router,
+    ///     },
+    ///     ext::RouterExt,
+    /// };
+    ///
+    /// let mut router = Router::new().route("/", get(|| async { "Hello!" }));
+    /// let response = router
+    ///     .try_call(Request::get("/").body(Body::empty()).unwrap())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn try_call(&mut self, request: Request<Body>) -> Result<Response, io::Error>;
+}
+#[async_trait]
+impl RouterExt for Router {
+    async fn try_call(&mut self, request: Request<Body>) -> Result<Response, io::Error> {
+        match self.call(request).await {
+            Ok(response) => Ok(response),
+            Err(infallible) => match infallible {},
+        }
+    }
+}
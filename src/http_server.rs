@@ -1,39 +1,154 @@
 //! This module provides an [`HttpServer`] that is compatible with embedded systems like the ESP32, but also supports many of the popular HttpServer features.
 
 use std::{
+    cmp,
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt,
+    future::{
+        poll_fn,
+        ready,
+        Future,
+        Ready,
+    },
     io::{
         self,
         BufRead,
         BufReader,
         ErrorKind,
+        Read,
         Write,
     },
     net::{
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+        Shutdown,
         SocketAddr,
         TcpListener,
         TcpStream,
         ToSocketAddrs,
     },
-    time::Duration,
+    pin::{
+        pin,
+        Pin,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            AtomicU32,
+            AtomicU64,
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+    },
+    task::{
+        Context,
+        Poll,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
-use axum::Router;
+use axum::{
+    async_trait,
+    extract::{
+        DefaultBodyLimit,
+        FromRequestParts,
+    },
+    response::IntoResponse,
+    Router,
+};
 use goolog::*;
 use http::{
+    header::{
+        ALLOW,
+        CONNECTION,
+        CONTENT_LENGTH,
+        CONTENT_TYPE,
+        EXPECT,
+        LOCATION,
+        SET_COOKIE,
+        TRANSFER_ENCODING,
+        UPGRADE,
+        WARNING,
+    },
+    request::Parts,
+    HeaderMap,
+    HeaderName,
+    HeaderValue,
     Method,
+    StatusCode,
     Uri,
+    Version,
 };
 use hyper::{
-    body::HttpBody,
+    body::{
+        Bytes,
+        HttpBody,
+    },
     service::Service,
     Body,
     Request,
     Response,
 };
 use tokio::{
+    runtime::{
+        self,
+        Handle,
+        Runtime,
+        RuntimeFlavor,
+    },
     spawn,
-    task::JoinHandle,
-    time::sleep,
+    sync::{
+        Notify,
+        Semaphore,
+    },
+    task::{
+        block_in_place,
+        yield_now,
+        JoinHandle,
+    },
+    time::{
+        sleep,
+        timeout,
+    },
+};
+
+use crate::{
+    error::{
+        Error,
+        ParseError,
+        SerializeError,
+    },
+    esp::mdns::{
+        MdnsAdvertiser,
+        MdnsBackend,
+    },
+    metrics::{
+        MetricsBackend,
+        NoopMetrics,
+        RouteTable,
+    },
+    middleware::throttle::{
+        PriorityThrottleConfig,
+        PriorityThrottleLayer,
+    },
+    pool::{
+        BufferPool,
+        ResponseBuffer,
+    },
+    response::Streamed,
+    rpc_server::{
+        read_request_frame,
+        write_response_frame,
+    },
 };
 
 /// When developing for embedded systems, you cannot, as of now, use asynchronous TcpListeners and thus
@@ -76,6 +191,9 @@ use tokio::{
 ///
 /// First, you will need a [`Router`]. You can use the macros from this library:
 /// ```
+/// # #[path = "../tests/router_macro/docs"]
+/// # mod docs {
+/// # use goohttp::router;
 /// // In this example, we will create a simple router with one route
 /// router! {
 ///     router {
@@ -84,24 +202,129 @@ use tokio::{
 ///                         // this macro documentation.
 ///     }
 /// }
+/// # }
+/// # fn main() {}
 /// ```
 /// After creating a router, we can bind and serve our HttpServer:
-/// ```
+/// ```no_run
+/// # use goohttp::{
+/// #     axum::Router,
+/// #     http_server::HttpServer,
+/// # };
+/// # fn router() -> Router {
+/// #     Router::new()
+/// # }
+/// # #[tokio::main]
+/// # async fn main() {
 /// let router = router(); // The macro above has only generated a function.
 ///                        // Only after calling it, we can get our router.
 ///
-/// let http_server = HttpServer::bind("0.0.0.0:80");
+/// let mut http_server = HttpServer::bind("0.0.0.0:80", None, None).unwrap();
 /// http_server.serve(router).unwrap();
+/// # }
 /// ```
+///
+/// # Keep-alive
+///
+/// Connections of HTTP/1.1 clients stay open for their next request, as do the ones of HTTP/1.0 clients sending
+/// `Connection: keep-alive`. Regardless of what the client asked for, the connection gets closed with a `Connection: close` header after
+/// a response if
+/// - the client sent `Connection: close`,
+/// - the [`max_requests_per_connection`](Self::max_requests_per_connection) were answered, or the
+///   [`keep_alive_timeout`](Self::keep_alive_timeout) is zero,
+/// - the route did not read the whole body of the request, and its rest could not be skipped, since it would be mistaken for the next
+///   request. Up to 64KB of a body get read and discarded after the route, unless the response is streamed or the client sent
+///   `Expect: 100-continue`,
+/// - the response is a [`StreamBody`](crate::response::StreamBody) without a `Content-Length` sent to an HTTP/1.0 client, whose end can
+///   only be told by the end of the connection,
+/// - the status of the response is `400 Bad Request` or `500 Internal Server Error`, after which the server cannot be sure where the
+///   next request starts, or
+/// - the route itself answered with `Connection: close`.
 pub struct HttpServer {
+    /// What happens if an incoming connection could not be accepted. See [`accept_error_policy`](Self::accept_error_policy).
+    accept_error_policy: AcceptErrorPolicy,
+    /// The sockets passed by systemd, which get served instead of binding a TcpListener. See [`from_systemd`](Self::from_systemd).
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    activated: Vec<TcpListener>,
     /// The address that the internal TcpListener will use.
     addr: SocketAddr,
+    /// The buffers responses get serialized into. See [`response_buffer_pool`](Self::response_buffer_pool).
+    buffer_pool: Option<Arc<BufferPool>>,
+    /// The open connections and the number of requests they sent.
+    connections: Arc<Connections>,
+    /// How often a connection checks whether its client disconnected. See [`cancel_on_disconnect`](Self::cancel_on_disconnect).
+    disconnect_check: Option<Duration>,
+    /// Builds the error responses sent without the router. See [`error_responder`](Self::error_responder).
+    error_responder: Option<ErrorResponder>,
+    /// The paths answered without the router. See [`fast_path`](Self::fast_path).
+    fast_paths: BTreeMap<String, RequestHandler>,
+    /// Whether connections get handled by the main task instead of their own tasks. See
+    /// [`handle_connections_inline`](Self::handle_connections_inline).
+    inline_connections: bool,
+    /// The handle to the current serving session, which gets used by [`shutdown`](Self::shutdown).
+    handle: Option<ServeHandle>,
+    /// How long an open connection may wait for its next request. See [`keep_alive_timeout`](Self::keep_alive_timeout).
+    keep_alive_timeout: Duration,
+    /// How long a connection may wait for its next request before it gets closed. See [`idle_eviction`](Self::idle_eviction).
+    idle_eviction: Option<Duration>,
+    /// How long closing a connection waits for unsent data. See [`linger`](Self::linger).
+    linger: Option<Duration>,
+    /// Whether requests no route matched get logged. See [`log_unmatched_routes`](Self::log_unmatched_routes).
+    log_unmatched_routes: bool,
     /// The main task of this HttpServer.
-    main_task: Option<JoinHandle<()>>,
+    main_task: Option<JoinHandle<Result<(), Error>>>,
+    /// The limit for the size of a single header value. See [`max_header_value_bytes`](Self::max_header_value_bytes).
+    max_header_value_bytes: usize,
+    /// The limit for the number of request headers. See [`max_request_headers`](Self::max_request_headers).
+    max_request_headers: usize,
+    /// The default limit for the size of request bodies. See [`max_request_body`](Self::max_request_body).
+    max_request_body: Option<usize>,
+    /// The number of requests answered over a single connection. See [`max_requests_per_connection`](Self::max_requests_per_connection).
+    max_requests_per_connection: Option<u64>,
+    /// The limit for the size of response bodies. See [`max_response_body`](Self::max_response_body).
+    max_response_body: Option<usize>,
+    /// Receives the events needed for metrics. See [`metrics_backend`](Self::metrics_backend).
+    metrics: Arc<dyn MetricsBackend>,
     /// The name of this HttpServer, which gets used in log messages.
     name: String,
-    /// The time this HttpServer sleeps between two [accept()](TcpListener::accept) calls.
-    refresh_rate: Duration,
+    /// The body and content type sent with `404 Not Found` responses without a body.
+    not_found_page: Option<(Vec<u8>, HeaderValue)>,
+    /// Gets called with the result of every connection once it is closed. See [`on_connection_result`](Self::on_connection_result).
+    on_connection_result: Option<ConnectionResultHook>,
+    /// Gets called with the result of the task accepting connections once it ends. See [`on_stop`](Self::on_stop).
+    on_stop: Option<StopHook>,
+    /// The time in nanoseconds this HttpServer sleeps between two [accept()](TcpListener::accept) calls. It is shared with the main task,
+    /// so it can be changed while serving.
+    refresh_rate: Arc<AtomicU64>,
+    /// Limits the rate of requests by their priority. See [`priority_throttle`](Self::priority_throttle).
+    priority_throttle: Option<PriorityThrottleLayer>,
+    /// Whether every connection starts with a PROXY protocol header. See [`proxy_protocol`](Self::proxy_protocol).
+    proxy_protocol: bool,
+    /// The number of requests answered at once. See [`queue_depth`](Self::queue_depth).
+    queue_depth: Option<usize>,
+    /// The places of the requests waiting for a permit, shared by every connection. See [`queue_depth`](Self::queue_depth).
+    queue_slots: Option<Arc<Semaphore>>,
+    /// The permits of the requests being answered, shared by every connection. See [`queue_depth`](Self::queue_depth).
+    request_permits: Option<Arc<Semaphore>>,
+    /// How the listener gets replaced after a fault. See [`auto_restart`](Self::auto_restart).
+    restart_policy: Option<RestartPolicy>,
+    /// The routes requests get matched against for metrics. See [`route_table`](Self::route_table).
+    route_table: Option<RouteTable>,
+    /// Whether connections speak the binary protocol of the [`RpcServer`](crate::rpc_server::RpcServer) instead of HTTP.
+    rpc_frames: bool,
+    /// Whether other sockets may bind the same port. See [`reuse_port`](Self::reuse_port).
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    reuse_port: bool,
+    /// The runtime this HttpServer runs on, once it was created. See [`dedicated_runtime`](Self::dedicated_runtime).
+    runtime: Option<Runtime>,
+    /// The number of worker threads and their stack size. See [`dedicated_runtime`](Self::dedicated_runtime).
+    runtime_threads: Option<(usize, usize)>,
+    /// Whether the settings get logged when serving starts. See [`startup_banner`](Self::startup_banner).
+    startup_banner: bool,
+    /// Whether response bodies exceeding the limit get truncated. See [`truncate_large_responses`](Self::truncate_large_responses).
+    truncate_large_responses: bool,
+    /// Whether connections give way to other tasks after every request. See [`yield_between_requests`](Self::yield_between_requests).
+    yield_between_requests: bool,
 }
 impl HttpServer {
     /// Create and set an address for a new HttpServer.
@@ -112,11 +335,34 @@ impl HttpServer {
     /// |--------------|--------------|------------------------------------------------------------------------------------|
     /// | name         | "HttpServer" | The name of this HttpServer, which gets used in log messages.                      |
     /// | refresh_rate | 10ms         | The time this HttpServer sleeps between two [accept()](TcpListener::accept) calls. |
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Resolve`] is returned if the given address could not be resolved to a socket address.
     pub fn bind<A: ToSocketAddrs>(
         addr: A,
         name: Option<&str>,
         refresh_rate: Option<Duration>,
-    ) -> Self {
+    ) -> Result<Self, Error> {
+        let Some(addr) = addr
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+        else {
+            error!(
+                name.unwrap_or("HttpServer"),
+                "Could not create the HttpServer. Error: {}",
+                Error::Resolve
+            );
+            return Err(Error::Resolve);
+        };
+
+        Ok(Self::bind_addr(addr, name, refresh_rate))
+    }
+    /// Create a new HttpServer for the given socket address. \
+    /// Unlike [`bind`](Self::bind), the address does not get resolved, so this cannot fail, and no resolver gets involved on devices where
+    /// it is slow or unreliable. The default values are the same as for `bind`.
+    pub fn bind_addr(addr: SocketAddr, name: Option<&str>, refresh_rate: Option<Duration>) -> Self {
         let final_name;
         if let Some(name) = name {
             final_name = name.to_string();
@@ -127,205 +373,4025 @@ impl HttpServer {
         if let Some(refresh_rate) = refresh_rate {
             final_refresh_rate = refresh_rate;
         } else {
-            final_refresh_rate = Duration::from_millis(1);
+            final_refresh_rate = Duration::from_millis(10);
         }
 
         Self {
-            addr: addr
-                .to_socket_addrs()
-                .unwrap_or_else(|_| {
-                    fatal!(
-                        final_name,
-                        "The specified address could not be converted to `std::net::SocketAddr`."
-                    );
-                })
-                .next()
-                .unwrap_or_else(|| {
-                    fatal!(final_name, "Could not find an address.");
-                }),
+            accept_error_policy: AcceptErrorPolicy::default(),
+            #[cfg(all(unix, not(target_os = "espidf")))]
+            activated: vec![],
+            addr,
+            buffer_pool: None,
+            connections: Arc::new(Connections::default()),
+            disconnect_check: None,
+            error_responder: None,
+            fast_paths: BTreeMap::new(),
+            handle: None,
+            inline_connections: false,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            linger: None,
+            idle_eviction: None,
+            log_unmatched_routes: true,
             main_task: None,
+            max_header_value_bytes: DEFAULT_MAX_HEADER_VALUE_BYTES,
+            max_request_headers: DEFAULT_MAX_REQUEST_HEADERS,
+            max_request_body: None,
+            max_requests_per_connection: None,
+            max_response_body: None,
+            metrics: Arc::new(NoopMetrics),
             name: final_name,
-            refresh_rate: final_refresh_rate,
-        }
-    }
-    /// This method will close the internal TCPListener and all of its connections by killing the task they are running on. \
-    /// If this HttpServer was already offline, this method will do nothing.
-    pub async fn shutdown(&mut self) {
-        if let Some(main_task) = self.main_task.take() {
-            main_task.abort();
-
-            info!(self.name, "Stopped.");
+            not_found_page: None,
+            on_connection_result: None,
+            on_stop: None,
+            refresh_rate: Arc::new(AtomicU64::new(duration_to_nanos(final_refresh_rate))),
+            priority_throttle: None,
+            proxy_protocol: false,
+            queue_depth: None,
+            queue_slots: None,
+            request_permits: None,
+            restart_policy: None,
+            route_table: None,
+            rpc_frames: false,
+            #[cfg(all(unix, not(target_os = "espidf")))]
+            reuse_port: false,
+            runtime: None,
+            runtime_threads: None,
+            startup_banner: cfg!(debug_assertions),
+            truncate_large_responses: false,
+            yield_between_requests: false,
         }
     }
-
-    /// Serve the given [`HttpServer`] with the given [`Router`]. \
-    /// This function is non-blocking.
+    /// Create a new HttpServer serving the sockets passed by systemd socket activation, instead of binding its own TcpListener. \
+    /// The listen queue of these sockets is kept by systemd, so the service can be restarted or started on the first connection without
+    /// refusing any client. Like `sd_listen_fds`, this reads the `LISTEN_PID` and `LISTEN_FDS` variables, and takes over the file
+    /// descriptors starting at `3`. [`serve`](Self::serve) then accepts connections from all of them. The address of the first socket
+    /// is reported by [`local_addr`](Self::local_addr). The default values are the same as for [`bind`](Self::bind).
+    ///
+    /// ```ini
+    /// # goohttp.socket
+    /// [Socket]
+    /// ListenStream=80
+    ///
+    /// [Install]
+    /// WantedBy=sockets.target
+    /// ```
+    ///
+    /// The sockets cannot be bound again after a fault, so [`auto_restart`](Self::auto_restart) does not apply to them, and
+    /// [`reuse_port`](Self::reuse_port) has to be set in the socket unit with `ReusePort=` instead.
     ///
     /// # Errors
     ///
-    /// An error is returned if the TcpListener failed to bind to the given address.
-    pub fn serve(&mut self, router: Router) -> io::Result<()> {
-        info!(self.name, "Starting...");
-
-        let tcp_listener;
-        match TcpListener::bind(self.addr) {
-            Ok(listener) => tcp_listener = listener,
+    /// [`Error::Activation`] is returned if the variables are missing or malformed, if they were meant for another process, if one of
+    /// the file descriptors is not a TCP socket, or if the sockets were already taken over by an earlier call.
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    pub fn from_systemd(name: Option<&str>, refresh_rate: Option<Duration>) -> Result<Self, Error> {
+        let listeners = match activated_listeners() {
+            Ok(listeners) => listeners,
             Err(error) => {
+                let error = Error::Activation(error);
                 error!(
-                    self.name,
-                    "An error occurred while binding the TcpListener. Error: {error}"
+                    name.unwrap_or("HttpServer"),
+                    "Could not take over the sockets of systemd. Error: {error}"
                 );
                 return Err(error);
             }
-        }
+        };
+        let addrs = listeners
+            .iter()
+            .map(TcpListener::local_addr)
+            .collect::<io::Result<Vec<SocketAddr>>>()
+            .map_err(Error::Bind)?;
 
-        info!(self.name, "Started! Now listening for clients...");
+        let mut http_server = Self::bind_addr(addrs[0], name, refresh_rate);
+        http_server.activated = listeners;
+        debug!(
+            http_server.name,
+            "Took over {} sockets of systemd: {addrs:?}",
+            addrs.len()
+        );
+        Ok(http_server)
+    }
 
-        let name = self.name.clone();
-        let refresh_rate = self.refresh_rate.clone();
-        let main_task = spawn(async move {
-            loop {
-                match tcp_listener.accept() {
-                    Ok((client, client_addr)) => {
-                        trace!(
-                            name,
-                            "A new client with the address `{client_addr}` connected."
-                        );
+    /// Limit the size of request bodies to the given number of bytes, unless a route sets its own limit. By default, axum's limit of 2MB
+    /// is used.
+    ///
+    /// Bodies only get read from the connection once a route asks for them, so they do not take up any memory before the route got
+    /// matched. The limit gets applied as a [`DefaultBodyLimit`] layer around the whole router. A route can override it by adding its own
+    /// [`DefaultBodyLimit`] layer, or, when using the [`router`](crate::router) macro, the `limit = ...` option. Bodies exceeding the limit
+    /// get rejected with `413 Payload Too Large` by extractors like `String`, `Bytes`, or `Json`, while routes reading the raw [`Body`]
+    /// are not limited. The rejection gets sent as built by the [`error_responder`](Self::error_responder). \
+    /// If a route does not read the whole body, up to 64KB of its rest get read and discarded after the response, so the connection can
+    /// stay open for the next request. It gets closed instead if more than that is left, if the response is streamed, if the client sent
+    /// `Expect: 100-continue`, or if the body exceeded the limit, since the rest of the body would otherwise be mistaken for the next
+    /// request.
+    pub fn max_request_body(mut self, bytes: usize) -> Self {
+        self.max_request_body = Some(bytes);
+        self
+    }
 
-                        let router = router.clone();
-                        spawn(Self::handler(client, router));
-                    }
-                    Err(error) => {
-                        error!(name, "Could not accept an incoming connection. It will be ignored. Error: {error}");
-                        continue;
-                    }
-                }
-                // we need to sleep here to give the handlers a chance to execute
-                sleep(refresh_rate).await;
-            }
-        });
+    /// Limit the size of the value of a single request header to the given number of bytes. By default, values may be up to 8KB long. \
+    /// Header lines get read into memory as a whole, so without a limit, a single header with a value of several megabytes could use up
+    /// all of the memory of the device. Lines get only read up to the limit, plus up to 256 bytes for the name of the header. A request
+    /// exceeding it gets answered with `431 Request Header Fields Too Large`, and its connection gets closed with
+    /// [`ParseError::HeaderTooLarge`]. \
+    /// The request line is limited to the same length. A longer one gets answered with `414 URI Too Long`, and its connection gets closed
+    /// with [`ParseError::UriTooLong`].
+    pub fn max_header_value_bytes(mut self, bytes: usize) -> Self {
+        self.max_header_value_bytes = bytes;
+        self
+    }
 
-        self.main_task = Some(main_task);
+    /// Limit the number of headers of a single request. By default, a request may have up to 100 headers. \
+    /// A request exceeding the limit gets answered with `431 Request Header Fields Too Large`, and its connection gets closed with
+    /// [`ParseError::TooManyHeaders`].
+    ///
+    /// The header lines of a request get collected in a single buffer before they are parsed, so the [`HeaderMap`] of the request can be
+    /// allocated once with room for all of them, instead of growing and rehashing with every header. The map cannot be skipped for
+    /// routes not reading any header, since every axum [`Request`] carries one. Devices whose clients only send a handful of headers
+    /// can lower the limit to bound the memory a request may take, at the cost of rejecting clients sending more than that, like
+    /// browsers with many cookies or extensions.
+    pub fn max_request_headers(mut self, count: usize) -> Self {
+        self.max_request_headers = count;
+        self
+    }
 
-        Ok(())
+    /// Limit the size of response bodies to the given number of bytes. By default, response bodies are not limited. \
+    /// Since every response gets buffered before it is written, a route accidentally returning a huge file could otherwise use up all of
+    /// the memory of the device. Responses exceeding the limit get logged with their size and replaced with an empty
+    /// `500 Internal Server Error`, unless they get [truncated](Self::truncate_large_responses) instead. Their bodies only get read until
+    /// the limit is exceeded.
+    pub fn max_response_body(mut self, bytes: usize) -> Self {
+        self.max_response_body = Some(bytes);
+        self
     }
-    /// The handler of each client.
-    async fn handler(mut client: TcpStream, mut router: Router) -> io::Result<()> {
-        /// Get a [`Response`] from the given [`Router`] based on the given [`Request`].
-        async fn request_to_response(
-            req: Request<Body>,
-            router: &mut Router,
-        ) -> Result<Response<Vec<u8>>, axum::http::Error> {
-            Response::builder().body({
-                let result = router
-                    .call(req)
-                    .await
-                    .expect("This should not fail since the error is of kind `Infallible`.")
-                    .data()
-                    .await;
-
-                let mut data = vec![];
-                if let Some(Ok(val)) = result {
-                    data = val.to_vec();
-                }
 
-                data
-            })
-        }
-        /// Convert a [`Response`] to a vec of bytes.
-        fn response_to_bytes(response: Response<Vec<u8>>) -> Vec<u8> {
-            let (parts, mut body) = response.into_parts();
-            let mut http_response = vec![];
-
-            // status line
-            http_response.append(
-                &mut format!(
-                    "{:?} {} {}\r\n",
-                    parts.version,
-                    parts.status.as_u16(),
-                    parts
-                        .status
-                        .canonical_reason()
-                        .expect("Every status code should have a canonical_reason!")
-                )
-                .as_bytes()
-                .to_vec(),
+    /// Truncate response bodies exceeding the [`max_response_body`](Self::max_response_body) limit to its size, instead of replacing the
+    /// response with a `500 Internal Server Error`. This option is disabled by default. \
+    /// A truncated response keeps its status and headers, and gets a `Warning: 214 - "Transformation Applied"` header telling the client
+    /// that its body was changed. This suits routes like log viewers, for which the beginning of the body is more useful than an error.
+    pub fn truncate_large_responses(mut self, truncate: bool) -> Self {
+        self.truncate_large_responses = truncate;
+        self
+    }
+
+    /// Keep the given number of buffers with the given capacity in bytes, and serialize responses into them instead of allocating a new
+    /// buffer for every response. By default, no buffers are kept. \
+    /// On devices with a slow allocator, like the ESP32, allocating and freeing the buffer of every response takes a noticeable part of
+    /// the time spent on a request. A buffer holds the head and body of a response, so responses larger than the capacity still get a new
+    /// buffer, just like every response while all buffers are in use. The buffers get allocated right away and stay allocated until the
+    /// HttpServer is dropped, so this is rarely worth it on targets with plenty of memory and a fast allocator.
+    pub fn response_buffer_pool(mut self, count: usize, capacity: usize) -> Self {
+        self.buffer_pool = Some(Arc::new(BufferPool::new(count, capacity)));
+        self
+    }
+
+    /// Serialize responses into the buffers of the given [`BufferPool`], which can be shared with other HttpServers. See
+    /// [`response_buffer_pool`](Self::response_buffer_pool).
+    pub fn shared_response_buffer_pool(mut self, pool: Arc<BufferPool>) -> Self {
+        self.buffer_pool = Some(pool);
+        self
+    }
+
+    /// Close connections that did not send their next request within the given duration. By default, connections get closed after 5
+    /// seconds without a request, so idle ones do not tie up the few sockets of a microcontroller. \
+    /// The timeout also applies to the first request of a connection, and gets announced to clients with a `Keep-Alive: timeout=...`
+    /// header in whole seconds. Every read of the head of a request has to finish within it as well, otherwise the request gets answered
+    /// with `408 Request Timeout`. A timeout of zero closes every connection after its first response. The timeout relies on the
+    /// [`set_read_timeout`](Stream::set_read_timeout) of the [`Stream`].
+    ///
+    /// While a connection waits for its next request, it does not hold a worker thread of a multi-threaded runtime, but a thread of its
+    /// blocking pool, so idle connections do not keep the runtime from accepting and answering others.
+    pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.keep_alive_timeout = timeout;
+        self
+    }
+
+    /// Set the `SO_LINGER` option of every accepted connection to the given duration. By default, the option is left to the operating
+    /// system, which closes connections in the background and keeps their sockets in `TIME_WAIT` afterwards. On a microcontroller with a
+    /// handful of sockets, these can keep new clients from connecting. \
+    /// With a duration of zero, connections get closed right away with a reset, so their sockets are freed immediately. Any data not yet
+    /// sent to the client gets lost, including the end of the last response if the client was slow to read it. With a longer duration,
+    /// closing a connection blocks until its data was sent or the duration is over, after which the rest gets lost as well.
+    ///
+    /// Only the connections of TcpListeners on Unix-like systems, including ESP-IDF, support this option. Custom [`Stream`]s have to implement
+    /// [`set_linger`](Stream::set_linger).
+    pub fn linger(mut self, linger: Duration) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    /// Close connections that waited longer than the given duration for their next request, including clients sending a request so
+    /// slowly that it never completes. By default, idle connections are not evicted. \
+    /// Unlike the [`keep_alive_timeout`](Self::keep_alive_timeout), this does not rely on the read timeouts of the [`Stream`], but closes
+    /// the connection with its [`closer`](Stream::closer), so it also works for streams ignoring read timeouts. The connections get
+    /// checked by the task accepting connections every [`refresh_rate`](Self::refresh_rate), or, with
+    /// [`handle_connections_inline`](Self::handle_connections_inline), only between two connections.
+    pub fn idle_eviction(mut self, limit: Duration) -> Self {
+        self.idle_eviction = Some(limit);
+        self
+    }
+
+    /// Check every given interval whether the client closed its connection while a route is answering its request, and cancel the route
+    /// if it did. By default, routes always run to completion, even if nobody is left to receive their response. \
+    /// This frees the resources of slow routes, like one reading a sensor, as soon as their client gave up. The route gets dropped at its
+    /// next `.await`, and the cancellation gets logged.
+    ///
+    /// Whether the client closed the connection gets found out with [`Stream::peer_closed`], which is skipped while the route reads the
+    /// body of its request. A TcpStream cannot tell a client that closed the connection apart from one that only shut down its sending
+    /// side after the last request, so routes of clients doing the latter get cancelled as well.
+    pub fn cancel_on_disconnect(mut self, interval: Duration) -> Self {
+        self.disconnect_check = Some(interval);
+        self
+    }
+
+    /// Limit the rate of requests separately for each [`Priority`](crate::middleware::throttle::Priority), as described by the
+    /// [`PriorityThrottleLayer`]. By default, requests are not throttled. \
+    /// Like the [`max_request_body`](Self::max_request_body) limit, the layer gets added around the whole router. The limits are kept for
+    /// the lifetime of this HttpServer, so they apply across connections, and are not reset by serving again after a shutdown.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use goohttp::{
+    /// #     http_server::HttpServer,
+    /// #     middleware::throttle::{
+    /// #         Priority,
+    /// #         PriorityThrottleConfig,
+    /// #         Rate,
+    /// #     },
+    /// # };
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.priority_throttle(
+    ///     PriorityThrottleConfig::new(|request| match request.uri.path() {
+    ///         "/health" => Priority::High,
+    ///         _ => Priority::Normal,
+    ///     })
+    ///     .limit(
+    ///         Priority::Normal,
+    ///         Rate {
+    ///             requests: 10,
+    ///             period: Duration::from_secs(1),
+    ///         },
+    ///     ),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn priority_throttle(mut self, config: PriorityThrottleConfig) -> Self {
+        self.priority_throttle = Some(PriorityThrottleLayer::new(config));
+        self
+    }
+
+    /// Close connections after the given number of requests were answered over them. By default, a connection can be used for any number
+    /// of requests. \
+    /// The number of requests a client may still send gets announced with a `Keep-Alive: max=...` header.
+    pub fn max_requests_per_connection(mut self, requests: u64) -> Self {
+        self.max_requests_per_connection = Some(requests);
+        self
+    }
+
+    /// Answer at most the given number of requests at once. By default, every received request gets answered right away. \
+    /// Up to the same number of further requests wait until one of the others was answered, while idle connections do not count against
+    /// the limit. Requests finding this queue full get answered with `503 Service Unavailable`, see
+    /// [`error_responder`](Self::error_responder). Once the limit is reached, a newly accepted connection is held without a task of its own
+    /// until a request finished, and no other connection gets accepted in the meantime. Clients then queue up in the backlog of the
+    /// listener, instead of every one of them taking the memory of a task that could not make progress anyway.
+    pub fn queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = Some(depth);
+        self.queue_slots = Some(Arc::new(Semaphore::new(depth)));
+        self.request_permits = Some(Arc::new(Semaphore::new(depth)));
+        self
+    }
+
+    /// Run this HttpServer on a runtime of its own with the given number of worker threads, each with a stack of the given number of
+    /// bytes. By default, its tasks get spawned on the runtime it is started from.
+    ///
+    /// The task accepting connections and the tasks of the connections then do not compete with the other tasks of the application, which
+    /// keeps the load of the HttpServer predictable. On a dual-core ESP32-S3, a single worker thread leaves the other core to the rest of
+    /// the application:
+    /// ```no_run
+    /// # async fn example() {
+    /// use goohttp::{
+    ///     axum::Router,
+    ///     http_server::HttpServer,
+    /// };
+    ///
+    /// let mut http_server = HttpServer::bind("0.0.0.0:80", None, None)
+    ///     .unwrap()
+    ///     .dedicated_runtime(1, 16 * 1024);
+    /// http_server.serve(Router::new()).unwrap();
+    /// # }
+    /// ```
+    /// A single worker thread has its limits, though:
+    /// - A connection waiting for the head of its next request does not hold the worker, but a thread of the blocking pool of the
+    ///   runtime, which gets a stack of the given size as well. Every connection open at the same time can take a thread of its own,
+    ///   so their number should be limited with the [`keep_alive_timeout`](Self::keep_alive_timeout) and the sockets of the device.
+    /// - The body of a request gets read by the worker while its route runs, so a client sending its body slowly holds up every other
+    ///   connection until it is done.
+    ///
+    /// The runtime gets created by the first call to [`serve`](Self::serve), [`serve_on`](Self::serve_on) or
+    /// [`serve_dispatch`](Self::serve_dispatch), and gets reused by the following ones. Dropping this HttpServer shuts it down, which
+    /// closes the connections still open right away, instead of answering them like after a [`shutdown`](Self::shutdown). \
+    /// The futures returned by [`serve_connection`](Self::serve_connection) do not use the runtime, since they run wherever the caller
+    /// spawns them.
+    pub fn dedicated_runtime(mut self, worker_threads: usize, stack_size: usize) -> Self {
+        self.runtime_threads = Some((worker_threads, stack_size));
+        self
+    }
+
+    /// Answer every request to exactly the given path with the given handler, before it waits for the [`queue_depth`](Self::queue_depth)
+    /// and without passing it to the router. By default, every request goes through the router.
+    ///
+    /// This keeps health checks answering while the device is busy, since a load balancer marking the device unhealthy exactly when it
+    /// serves the most requests would only make matters worse:
+    /// ```
+    /// use goohttp::http_server::HttpServer;
+    /// use hyper::{
+    ///     Body,
+    ///     Request,
+    ///     Response,
+    /// };
+    ///
+    /// fn healthz(_: Request<Body>) -> Response<Body> {
+    ///     Response::new(Body::from("ok"))
+    /// }
+    ///
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)
+    ///     .unwrap()
+    ///     .queue_depth(4)
+    ///     .fast_path("/healthz", healthz);
+    /// ```
+    /// The request gets answered for every method, and still counts for the [`metrics_backend`](Self::metrics_backend). Since the router
+    /// is skipped, so are its layers, like the [`max_request_body`](Self::max_request_body) limit and the
+    /// [`priority_throttle`](Self::priority_throttle). \
+    /// A health check arriving on a new connection could not skip the queue if the connection was held until a permit is free, so with
+    /// fast paths, the `queue_depth` no longer holds newly accepted connections. Their requests still wait for a permit in their own task.
+    pub fn fast_path(mut self, path: &str, handler: RequestHandler) -> Self {
+        self.fast_paths.insert(path.to_string(), handler);
+        self
+    }
+
+    /// Send the given body with the given content type whenever the router answers with an empty `404 Not Found`, like it does if no
+    /// route matches the request. Bodies set by routes themselves are kept.
+    ///
+    /// ```
+    /// # use goohttp::http_server::HttpServer;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?
+    ///     .not_found_page("<h1>This page does not exist</h1>", "text/html; charset=utf-8");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the given content type is not a valid header value.
+    pub fn not_found_page(mut self, body: impl Into<Vec<u8>>, content_type: &str) -> Self {
+        let content_type = HeaderValue::from_str(content_type).unwrap_or_else(|_| {
+            fatal!(
+                self.name,
+                "The content type `{content_type}` of the 404 page is not a valid header value."
             );
+        });
+        self.not_found_page = Some((body.into(), content_type));
+        self
+    }
 
-            // headers
-            for (header_name, header_value) in parts.headers {
-                http_response.append(
-                    &mut format!(
-                        "{}: ",
-                        header_name.expect("Every header should have a name!")
-                    )
-                    .as_bytes()
-                    .to_vec(),
+    /// Build the error responses this HttpServer sends on its own, without asking the router, with the given function. By default, they
+    /// have an empty body. \
+    /// These are the responses to
+    /// - malformed requests: `400 Bad Request`
+    /// - requests whose head was not read within the [`keep_alive_timeout`](Self::keep_alive_timeout): `408 Request Timeout`
+    /// - requests rejected by the [`max_request_body`](Self::max_request_body) limit or the limit of their route:
+    ///   `413 Payload Too Large`
+    /// - requests with a request line longer than the [`max_header_value_bytes`](Self::max_header_value_bytes) allow: `414 URI Too Long`
+    /// - requests with too large or too many headers: `431 Request Header Fields Too Large`
+    /// - streamed responses whose head cannot be sent: `500 Internal Server Error`
+    /// - requests finding the queue of the [`queue_depth`](Self::queue_depth) full: `503 Service Unavailable`
+    ///
+    /// They get built and serialized once every time the HttpServer starts serving, so sending them later needs neither allocations nor
+    /// serialization, even on a device flooded with bad requests. Their `Content-Length` and `Connection: close` headers get set by the
+    /// HttpServer. The `413 Payload Too Large` response replaces the one of axum's extractors, whose body would not fit the others.
+    ///
+    /// ```
+    /// # use goohttp::{
+    /// #     axum::http::{
+    /// #         header::CONTENT_TYPE,
+    /// #         HeaderValue,
+    /// #         Response,
+    /// #     },
+    /// #     http_server::HttpServer,
+    /// # };
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.error_responder(|status| {
+    ///     let mut response = Response::new(format!("Error {}", status.as_u16()).into_bytes());
+    ///     response
+    ///         .headers_mut()
+    ///         .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    ///     response
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn error_responder(
+        mut self,
+        responder: impl Fn(StatusCode) -> Response<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        self.error_responder = Some(Arc::new(responder));
+        self
+    }
+
+    /// Log the method and path of every request answered with an empty `404 Not Found`, like the router does if no route matches the
+    /// request. This is enabled by default, and shows which routes clients look for that do not exist. \
+    /// The requests get logged at the debug level, so they only show up if the logger lets debug messages of this HttpServer through.
+    /// Unlike the other debug messages, they get logged in release builds as well.
+    /// Responses of routes answering with `404 Not Found` and a body of their own, like a missing entry of an API, do not get logged.
+    pub fn log_unmatched_routes(mut self, enabled: bool) -> Self {
+        self.log_unmatched_routes = enabled;
+        self
+    }
+
+    /// Handle every accepted connection in the task accepting connections instead of spawning a new task for it. By default, every
+    /// connection gets its own task.
+    ///
+    /// On slow microcontrollers only serving a single client at a time, this saves the overhead of spawning a task per connection. In
+    /// exchange, connections get handled strictly one after another:
+    /// - No new connection gets accepted until the current one is closed. A client keeping its connection alive blocks every other client
+    ///   until it closes it.
+    /// - Since the connection is read with blocking calls, the thread running the task accepting connections is blocked while it waits for
+    ///   the next request.
+    /// - A [`shutdown`](Self::shutdown) only completes once the current connection is closed.
+    pub fn handle_connections_inline(mut self, inline: bool) -> Self {
+        self.inline_connections = inline;
+        self
+    }
+
+    /// Let every connection give way to the other tasks of the runtime after answering a request, before it reads the next one.
+    ///
+    /// The task accepting connections already does this while it sleeps for the [`refresh_rate`](Self::refresh_rate) between two
+    /// [accept()](TcpListener::accept) calls, so a shorter refresh rate does not take time away from other tasks. A connection on the
+    /// other hand keeps answering requests as long as their routes respond right away and the next request is already waiting. On a
+    /// single-core microcontroller, a client pipelining many requests can therefore keep every other task on the same runtime from
+    /// running until its connection is closed. \
+    /// With this enabled, such a connection only answers one request before the other tasks get their turn, at the cost of a short delay
+    /// between pipelined requests. Together with [`handle_connections_inline`](Self::handle_connections_inline), the task accepting
+    /// connections gives way after every request as well.
+    pub fn yield_between_requests(mut self, enabled: bool) -> Self {
+        self.yield_between_requests = enabled;
+        self
+    }
+
+    /// Set the `SO_REUSEPORT` option on the internal TcpListener, so other processes can bind the same port while this HttpServer is
+    /// serving. The kernel then spreads new connections across all listeners of the port.
+    ///
+    /// This allows to replace a running process without refusing any connection: the new process binds the port next to the old one,
+    /// after which the old one calls [`graceful_shutdown`](ServeHandle::graceful_shutdown). Once its listener is closed, new connections
+    /// only go to the new process, while the old one finishes the requests it already received. \
+    /// Connections the kernel already assigned to a listener wait in its backlog until they get accepted, and would be reset if it gets
+    /// closed with them still inside. To keep them, a shutdown accepts every connection waiting in the backlog before closing the
+    /// listener. Connections arriving in the short moment between this and closing the listener can still be lost, unless the kernel
+    /// migrates them to the other listeners, which Linux does with the `net.ipv4.tcp_migrate_req` setting enabled.
+    ///
+    /// This is only available on unix platforms other than the ESP-IDF.
+    #[cfg_attr(docsrs, doc(cfg(all(unix, not(target_os = "espidf")))))]
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    pub fn reuse_port(mut self, enabled: bool) -> Self {
+        self.reuse_port = enabled;
+        self
+    }
+
+    /// Expect every connection to start with a header of the [PROXY protocol](https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt)
+    /// in version 1 or 2, which load balancers like HAProxy send to tell the address of the client they forward a connection for. The
+    /// address it contains replaces the one of the load balancer in the [`ConnectionInfo`] of every request. Headers of the `UNKNOWN`
+    /// or `LOCAL` kind, which load balancers send for their own health checks, keep the actual address.
+    ///
+    /// Connections not starting with a valid header get closed with [`ParseError::ProxyHeader`], since anyone able to connect directly
+    /// could otherwise claim any address. For the same reason, this should only be enabled if every connection comes from the load
+    /// balancer.
+    pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
+    /// Decide whether a summary of the settings of this HttpServer gets logged once it starts serving, like its address and limits. This
+    /// helps to check the configuration of a device on its serial console. \
+    /// By default, the summary only gets logged in debug builds.
+    pub fn startup_banner(mut self, enabled: bool) -> Self {
+        self.startup_banner = enabled;
+        self
+    }
+
+    /// Decide what happens if an incoming connection could not be accepted. By default, the error gets logged and the HttpServer keeps
+    /// accepting connections, as described in [`AcceptErrorPolicy`].
+    pub fn accept_error_policy(mut self, policy: AcceptErrorPolicy) -> Self {
+        self.accept_error_policy = policy;
+        self
+    }
+
+    /// Replace the listener on its own if the task accepting connections hits a fault, instead of stopping. A fault is an accept error
+    /// that the [`accept_error_policy`](Self::accept_error_policy) stops on, so this only has an effect together with
+    /// [`AcceptErrorPolicy::Stop`].
+    ///
+    /// After a fault, the broken listener gets closed and a new one gets bound to the same address once the backoff of the given
+    /// [`RestartPolicy`] is over. Connections are then accepted again and answered by the same router. If binding fails, another restart
+    /// is attempted until the limit of the policy is reached, after which the task stops with the fault as if no policy was set. \
+    /// A [`shutdown`](Self::shutdown) during the backoff cancels the pending restart. The number of restarts can be checked with
+    /// [`restarts`](Self::restarts).
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use goohttp::http_server::{
+    /// #     AcceptErrorPolicy,
+    /// #     HttpServer,
+    /// #     RestartPolicy,
+    /// # };
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?
+    ///     .accept_error_policy(AcceptErrorPolicy::Stop)
+    ///     .auto_restart(RestartPolicy {
+    ///         backoff: Duration::from_secs(1),
+    ///         max_restarts: 5,
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn auto_restart(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = Some(policy);
+        self
+    }
+
+    /// Call the given function with the result of every connection once it gets closed. \
+    /// Errors of connections, like requests that could not be parsed or clients disconnecting while a response gets written, only close the
+    /// connection they occurred on. This hook allows to count or inspect them anyway.
+    ///
+    /// ```
+    /// # use goohttp::{
+    /// #     error::Error,
+    /// #     http_server::HttpServer,
+    /// # };
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.on_connection_result(|result| {
+    ///     if let Err(Error::Parse(error)) = result {
+    ///         println!("A client sent a malformed request: {error}");
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_connection_result(
+        mut self,
+        hook: impl Fn(&Result<(), Error>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connection_result = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call the given function with the result of the task accepting connections once it ends. \
+    /// [`serve`](Self::serve) returns as soon as the listener is bound, so an error stopping the HttpServer later, like an
+    /// [`Error::Accept`] with [`AcceptErrorPolicy::Stop`], does not reach its caller. [`join`](Self::join) returns this error, but only to
+    /// the owner of the HttpServer, which has to wait for it. This hook reports it right away instead, e.g. to restart the device:
+    ///
+    /// ```
+    /// # use goohttp::http_server::HttpServer;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.on_stop(|result| {
+    ///     if let Err(error) = result {
+    ///         println!("The HttpServer stopped unexpectedly: {error}");
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// A [`shutdown`](Self::shutdown) also calls the hook, with `Ok(())`. It gets called before [`is_running`](Self::is_running) returns
+    /// `false`, and before [`ServeHandle::await_stopped`] returns. The hook does not get called if the task panicked, or got aborted by
+    /// dropping the HttpServer.
+    pub fn on_stop(mut self, hook: impl Fn(&Result<(), Error>) + Send + Sync + 'static) -> Self {
+        self.on_stop = Some(Arc::new(hook));
+        self
+    }
+
+    /// Report the requests and connections of this HttpServer to the given [`MetricsBackend`]. By default, the [`NoopMetrics`] ignore
+    /// them. \
+    /// Wrap the backend in an [`Arc`] to keep access to it, e.g. to serve the metrics collected by a
+    /// [`PrometheusMetrics`](crate::metrics::PrometheusMetrics):
+    ///
+    /// ```
+    /// # #[cfg(feature = "prometheus")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use std::sync::Arc;
+    /// # use goohttp::{
+    /// #     http_server::HttpServer,
+    /// #     metrics::PrometheusMetrics,
+    /// # };
+    /// let metrics = Arc::new(PrometheusMetrics::new());
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.metrics_backend(metrics.clone());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "prometheus"))]
+    /// # fn main() {}
+    /// ```
+    pub fn metrics_backend(mut self, backend: impl MetricsBackend) -> Self {
+        self.metrics = Arc::new(backend);
+        self
+    }
+
+    /// Match every request against the routes of the given [`RouteTable`], and report the id of its route to the
+    /// [`metrics_backend`](Self::metrics_backend) with [`on_route_end`](MetricsBackend::on_route_end). Unlike the concrete path of a
+    /// request, its route keeps the number of time series bounded:
+    ///
+    /// ```
+    /// # #[path = "../tests/router_macro"]
+    /// # mod serve_frontend {
+    /// # goohttp::router! {
+    /// #     serve_frontend {
+    /// #         index, get;
+    /// #         api
+    /// #     }
+    /// # }
+    /// # }
+    /// # #[cfg(feature = "prometheus")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # use std::sync::Arc;
+    /// # use goohttp::{
+    /// #     http_server::HttpServer,
+    /// #     metrics::{
+    /// #         PrometheusMetrics,
+    /// #         RouteTable,
+    /// #     },
+    /// # };
+    /// let routes = RouteTable::new(serve_frontend::router_routes());
+    /// let metrics = Arc::new(PrometheusMetrics::with_routes(&routes));
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None)?
+    ///     .route_table(routes)
+    ///     .metrics_backend(metrics.clone());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "prometheus"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// A `405 Method Not Allowed` response to a request not matching any route gets an `Allow` header listing the methods of the routes
+    /// matching its path, unless the router already added one. axum adds it to the responses of its method routers, but not to the ones
+    /// of other services or routes.
+    pub fn route_table(mut self, routes: RouteTable) -> Self {
+        self.route_table = Some(routes);
+        self
+    }
+
+    /// This method will close the internal TCPListener. Since the listener only gets polled every [`refresh_rate`](Self::refresh_rate), this
+    /// method waits at most this long for the listener to close. Connections that were already accepted get answered before they close
+    /// on their own. \
+    /// If this HttpServer was already offline, this method will do nothing. To stop it from somewhere not owning it, use the
+    /// [`ServeHandle`] returned by [`serve`](Self::serve) instead.
+    pub async fn shutdown(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.shutdown().await;
+        }
+        if let Some(main_task) = self.main_task.take() {
+            if let Err(error) = main_task.await {
+                error!(
+                    self.name,
+                    "The task accepting connections did not stop cleanly. Error: {error}"
                 );
-                http_response.append(&mut header_value.as_bytes().to_vec());
-                http_response.append(&mut b"\r\n".to_vec());
             }
+        }
+    }
 
-            // body
-            http_response.append(&mut b"\r\n".to_vec());
-            http_response.append(&mut body);
-
-            http_response
+    /// Wait until the task accepting connections ends, whether because of a [`shutdown`](ServeHandle::shutdown) through a [`ServeHandle`],
+    /// or because it stopped on its own. Afterwards, this HttpServer can be served again, e.g. to restart it after a fault.
+    ///
+    /// If this HttpServer is not serving, because it was never started or got shut down or joined already, `Ok(())` is returned right
+    /// away.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Accept`] is returned if the task stopped because of an error, as configured with
+    ///   [`accept_error_policy`](Self::accept_error_policy).
+    /// - [`Error::Task`] is returned if the task panicked.
+    pub async fn join(&mut self) -> Result<(), Error> {
+        let Some(main_task) = self.main_task.take() else {
+            return Ok(());
+        };
+        match main_task.await {
+            Ok(result) => result,
+            Err(error) if error.is_panic() => {
+                let panic = error.into_panic();
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(Error::Task(message))
+            }
+            Err(error) => Err(Error::Task(error.to_string())),
         }
+    }
 
-        let buf_reader = BufReader::new(&mut client);
-        let http_request: Vec<_> = buf_reader
-            .lines()
-            .map(|result| result.expect("Each request should be convertible to a String.")) // Maybe this should just cancel the connection
-            .take_while(|line| !line.is_empty())
-            .collect();
+    /// Announce this HttpServer as an `_http._tcp` service with the given instance name and its port via mDNS, so local clients can find
+    /// the device under `hostname.local`. The service stays registered until the returned [`MdnsAdvertiser`] gets dropped. \
+    /// The port is taken from [`local_addr`](Self::local_addr), so with port `0`, the HttpServer has to be served first.
+    ///
+    /// # Errors
+    ///
+    /// The error of the backend is returned if it failed to set the hostname or to register the service.
+    pub fn advertise_mdns<B: MdnsBackend>(
+        &self,
+        backend: B,
+        hostname: &str,
+        instance: &str,
+    ) -> Result<MdnsAdvertiser<B>, B::Error> {
+        MdnsAdvertiser::new(backend, hostname, instance, self.local_addr().port())
+    }
+
+    /// Get the address this HttpServer listens on. While serving, this is the address the internal TcpListener is bound to, which
+    /// contains the actual port if port `0` was given to [`bind`](Self::bind). Otherwise, the address given to `bind` is returned.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.handle
+            .as_ref()
+            .map_or(self.addr, ServeHandle::local_addr)
+    }
+
+    /// Check whether this HttpServer is currently accepting connections. \
+    /// This is not the case before [`serve`](Self::serve) got called, after a [`shutdown`](Self::shutdown), or if the task accepting
+    /// connections stopped on its own.
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(ServeHandle::is_running)
+    }
+
+    /// Get the number of open connections that are waiting for their next request. \
+    /// On devices only able to run a few tasks at once, this tells how many of them are blocked by clients keeping their connection
+    /// alive.
+    pub fn idle_connections(&self) -> usize {
+        self.connections.count(ConnectionState::Idle)
+    }
+
+    /// Get the number of open connections whose request is currently being read or answered.
+    pub fn active_connections(&self) -> usize {
+        self.connections.count(ConnectionState::Active)
+    }
+
+    /// Get a [`ConnectionSnapshot`] of every open connection, in the order they were accepted. \
+    /// This allows e.g. an admin route to show who is connected right now, and to find stale connections for
+    /// [`close_connection`](Self::close_connection).
+    pub fn connections(&self) -> Vec<ConnectionSnapshot> {
+        self.connections.snapshots()
+    }
+
+    /// Close the open connections of the client with the given address, even if they are waiting for their next request or answering
+    /// one. A response being written gets cut off. \
+    /// `false` is returned if no connection of this client could be closed, either because there is none, or because its [`Stream`]
+    /// has no [`closer`](Stream::closer).
+    pub fn close_connection(&self, peer_addr: SocketAddr) -> bool {
+        self.connections.close(peer_addr, &self.name)
+    }
+
+    /// Get the number of open connections, no matter whether they are waiting for their next request or not. A connection kept alive
+    /// for several requests only counts once. \
+    /// Unlike [`idle_connections`](Self::idle_connections) and [`active_connections`](Self::active_connections), this only reads a
+    /// counter, so it is cheap enough to be checked by a watchdog all the time.
+    pub fn open_connections(&self) -> usize {
+        self.connections.open.load(Ordering::SeqCst)
+    }
+
+    /// Get the number of requests received since this HttpServer last started serving, counting every request of a connection kept
+    /// alive individually.
+    pub fn total_requests(&self) -> u64 {
+        self.connections.total_requests.load(Ordering::SeqCst)
+    }
+
+    /// Get the number of times the listener was replaced since this HttpServer last started serving. See
+    /// [`auto_restart`](Self::auto_restart).
+    pub fn restarts(&self) -> u32 {
+        self.handle.as_ref().map_or(0, ServeHandle::restarts)
+    }
+
+    /// Get the time this HttpServer sleeps between two [accept()](TcpListener::accept) calls.
+    pub fn refresh_rate(&self) -> Duration {
+        Duration::from_nanos(self.refresh_rate.load(Ordering::SeqCst))
+    }
 
-        if http_request.is_empty() {
-            return Err(ErrorKind::InvalidData.into());
+    /// Change the time this HttpServer sleeps between two [accept()](TcpListener::accept) calls. This also works while serving, in which
+    /// case the new value gets used once the current sleep is over. \
+    /// Longer values let a device save power while it is running on battery, at the cost of new connections waiting longer to be
+    /// accepted.
+    ///
+    /// # Errors
+    ///
+    /// [`ConfigError::ZeroRefreshRate`] is returned if the given duration is zero. The refresh rate stays unchanged in this case.
+    pub fn set_refresh_rate(&self, refresh_rate: Duration) -> Result<(), ConfigError> {
+        if refresh_rate.is_zero() {
+            return Err(ConfigError::ZeroRefreshRate);
         }
+        self.refresh_rate
+            .store(duration_to_nanos(refresh_rate), Ordering::SeqCst);
+        Ok(())
+    }
 
-        let mut head_line = http_request[0].split(' ');
-        let method;
-        let uri;
-        if let Some(val) = head_line.next() {
-            if let Ok(val) = Method::from_bytes(val.as_bytes()) {
-                method = val;
-            } else {
-                return Err(ErrorKind::InvalidData.into());
-            }
-        } else {
-            return Err(ErrorKind::InvalidData.into());
+    /// Check whether the settings of this HttpServer contradict each other. This happens automatically before it starts serving.
+    ///
+    /// # Errors
+    ///
+    /// A [`ConfigError`] describing the first contradiction found is returned.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.refresh_rate().is_zero() {
+            return Err(ConfigError::ZeroRefreshRate);
         }
-        if let Some(val) = head_line.next() {
-            if let Ok(val) = val.parse::<Uri>() {
-                uri = val;
-            } else {
-                return Err(ErrorKind::InvalidData.into());
+        if self.queue_depth == Some(0) {
+            return Err(ConfigError::ZeroQueueDepth);
+        }
+        if let Some((0, _)) = self.runtime_threads {
+            return Err(ConfigError::ZeroWorkerThreads);
+        }
+        if let (Some((page, _)), Some(limit)) = (&self.not_found_page, self.max_response_body) {
+            if page.len() > limit {
+                return Err(ConfigError::NotFoundPageTooLarge {
+                    page: page.len(),
+                    limit,
+                });
             }
+        }
+        Ok(())
+    }
+
+    /// Serve the given [`HttpServer`] with the given [`Router`]. \
+    /// This function is non-blocking. The internal TcpListener gets polled for new connections every
+    /// [`refresh_rate`](Self::refresh_rate), which is also the longest time a [`shutdown`](Self::shutdown) has to wait for the listener to close.
+    ///
+    /// Since this function returns once the listener is bound, errors stopping the HttpServer afterwards cannot be returned by it. They
+    /// get logged, and [`is_running`](Self::is_running) returns `false` from then on. Use [`join`](Self::join) to wait for such an error,
+    /// or [`on_stop`](Self::on_stop) to get notified about it without waiting.
+    ///
+    /// The returned [`ServeHandle`] can be cloned and passed to other tasks, so they can stop the HttpServer without owning it.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::AlreadyRunning`] is returned if this HttpServer is already serving.
+    /// - [`Error::Config`] is returned if the settings of this HttpServer contradict each other, as described by
+    ///   [`validate`](Self::validate).
+    /// - [`Error::Bind`] is returned if the TcpListener failed to bind to the given address or could not be switched to non-blocking mode.
+    pub fn serve(&mut self, router: Router) -> Result<ServeHandle, Error> {
+        let router = self.add_layers(router);
+        self.start(router, None)
+    }
+
+    /// Serve the given [`Router`] on the connections of the given [`Listener`] instead of binding a TcpListener. \
+    /// This allows to use other transports than TCP, like the sockets of a platform without [`std::net`], or the in-memory
+    /// [`MemoryListener`](crate::testing::MemoryListener) in tests. The address given to [`bind`](Self::bind) does not get used in this
+    /// case. Everything else works like with [`serve`](Self::serve), except that the listener cannot be replaced by
+    /// [`auto_restart`](Self::auto_restart), so a fault stops the HttpServer.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::AlreadyRunning`] is returned if this HttpServer is already serving.
+    /// - [`Error::Config`] is returned if the settings of this HttpServer contradict each other, as described by
+    ///   [`validate`](Self::validate).
+    /// - [`Error::Bind`] is returned if the address of the listener could not be determined.
+    pub fn serve_on(
+        &mut self,
+        listener: impl Listener,
+        router: Router,
+    ) -> Result<ServeHandle, Error> {
+        let router = self.add_layers(router);
+        self.start(router, Some(Box::new(listener)))
+    }
+
+    /// Serve the given [`Router`] like [`serve`](Self::serve), after checking that it can answer the given requests, which are pairs of a
+    /// method and a path like `("GET", "/api/status")`. \
+    /// Each request gets answered in-process before the listener is bound, with an empty body and without headers. Like the requests of
+    /// clients, it carries a [`ConnectionInfo`], as if it was the first request of a connection from `127.0.0.1`. A route answering with
+    /// a server error or panicking stops the HttpServer from starting, so a broken build gets noticed before a client sees it. Other
+    /// statuses, like `404 Not Found` or `400 Bad Request`, count as passed.
+    ///
+    /// Without requests, the `GET` routes of the [`route_table`](Self::route_table) get checked, except for the ones marked with
+    /// `#[skip_smoke_test]`. Their parameters get filled like in the smoke tests of the [`router`](crate::router) macro:
+    /// ```no_run
+    /// # #[path = "../tests/router_macro"]
+    /// # mod serve_frontend {
+    /// # goohttp::router! {
+    /// #     router {
+    /// #         index, get;
+    /// #         api
+    /// #     }
+    /// # }
+    /// # }
+    /// # use goohttp::{
+    /// #     http_server::HttpServer,
+    /// #     metrics::RouteTable,
+    /// # };
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let routes = RouteTable::new(serve_frontend::router_routes());
+    /// let mut http_server = HttpServer::bind("0.0.0.0:80", None, None)?.route_table(routes);
+    /// http_server.serve_with_selftest(serve_frontend::router(), &[]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::SelfTest`] is returned if a request was answered with a server error, its route panicked, or it could not be built.
+    /// - Every error of [`serve`](Self::serve) can be returned as well.
+    pub async fn serve_with_selftest(
+        &mut self,
+        router: Router,
+        requests: &[(&str, &str)],
+    ) -> Result<ServeHandle, Error> {
+        let router = self.add_layers(router);
+
+        let requests: Vec<(&str, String)> = if requests.is_empty() {
+            self.route_table
+                .iter()
+                .flat_map(RouteTable::routes)
+                .filter(|route| route.method == "GET" && route.smoke_test)
+                .map(|route| ("GET", crate::__private::smoke_test_path(&route.path)))
+                .collect()
         } else {
-            return Err(ErrorKind::InvalidData.into());
+            requests
+                .iter()
+                .map(|(method, path)| (*method, path.to_string()))
+                .collect()
+        };
+        for (method, path) in &requests {
+            if let Err(error) = Self::self_test(router.clone(), method, path).await {
+                error!(self.name, "The self-test failed. Error: {error}");
+                return Err(error);
+            }
         }
+        info!(
+            self.name,
+            "The self-test passed. All {} requests were answered without a server error.",
+            requests.len()
+        );
+
+        self.start(router, None)
+    }
 
-        let request;
-        if let Ok(val) = Request::builder()
+    /// Answer a request with the given method and path with the given [`Router`], failing if it gets answered with a server error or its
+    /// route panics.
+    async fn self_test(mut router: Router, method: &str, path: &str) -> Result<(), Error> {
+        let failed = |status| Error::SelfTest {
+            path: path.to_string(),
+            status,
+        };
+        let mut request = Request::builder()
             .method(method)
-            .uri(uri)
+            .uri(path)
             .body(Body::empty())
-        {
-            request = val;
-        } else {
-            return Err(ErrorKind::InvalidData.into());
+            .map_err(|_| failed(None))?;
+        // routes extracting the connection info would fail without it
+        request.extensions_mut().insert(ConnectionInfo {
+            peer_addr: SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0),
+            raw_target: path.to_string(),
+            requests_served: 0,
+            tls: false,
+            version: Version::HTTP_11,
+        });
+
+        // the route runs in a task of its own, so a panic can be caught
+        match spawn(async move { router.call(request).await }).await {
+            Ok(Ok(response)) if response.status().is_server_error() => {
+                Err(failed(Some(response.status())))
+            }
+            Ok(_) => Ok(()),
+            Err(_) => Err(failed(None)),
         }
+    }
 
-        let response;
-        if let Ok(val) = request_to_response(request, &mut router).await {
-            response = val;
-        } else {
-            return Err(ErrorKind::InvalidData.into());
+    /// Answer the requests of the given connection with the given [`Router`], using the settings of this HttpServer. \
+    /// This is the integration point for devices that already run their own accept loop, e.g. as part of an existing event system. Requests
+    /// get parsed and responses serialized exactly like with [`serve`](Self::serve), without this HttpServer binding a TcpListener:
+    /// ```no_run
+    /// # async fn example() {
+    /// use std::net::TcpListener;
+    ///
+    /// use goohttp::{
+    ///     axum::Router,
+    ///     http_server::HttpServer,
+    /// };
+    ///
+    /// let http_server = HttpServer::bind("0.0.0.0:80", None, None).unwrap();
+    /// let listener = TcpListener::bind("0.0.0.0:80").unwrap();
+    /// for client in listener.incoming() {
+    ///     let connection = http_server.serve_connection(client.unwrap(), Router::new());
+    ///     tokio::spawn(connection);
+    /// }
+    /// # }
+    /// ```
+    /// The returned future does not borrow this HttpServer, so it can be spawned. It resolves once the connection is closed, as described
+    /// in [`on_connection_result`](Self::on_connection_result), whose hook also gets called with the result. The connection gets counted
+    /// by [`idle_connections`](Self::idle_connections) and [`active_connections`](Self::active_connections) while it is open.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors closing a connection, [`Error::Io`] is returned if the connection could not be switched to blocking mode.
+    pub fn serve_connection(
+        &self,
+        client: TcpStream,
+        router: Router,
+    ) -> impl Future<Output = Result<(), Error>> + Send + 'static {
+        let router = self.add_layers(router);
+        let config = self.handler_config();
+        let registered = client.peer_addr().map(|peer_addr| {
+            (
+                peer_addr,
+                ConnectionGuard::new(&self.connections, peer_addr, &client),
+            )
+        });
+        async move {
+            let (peer_addr, connection) = registered?;
+            client.set_nonblocking(false)?;
+            Self::handle_connection(Box::new(client), peer_addr, router, config, connection).await
         }
+    }
 
-        if client.write_all(&response_to_bytes(response)).is_err() {}
+    /// Make the connections of this HttpServer speak the binary protocol of the [`RpcServer`](crate::rpc_server::RpcServer) instead of HTTP.
+    pub(crate) fn rpc_frames(mut self) -> Self {
+        self.rpc_frames = true;
+        self
+    }
 
-        Ok(())
+    /// Bind a TcpListener to the address of this HttpServer.
+    fn bind_tcp_listener(&self) -> Result<TcpListener, Error> {
+        let tcp_listener = match bind_tcp_listener(self.addr, self.reuses_port()) {
+            Ok(listener) => listener,
+            Err(error) => {
+                let error = Error::Bind(error);
+                error!(
+                    self.name,
+                    "An error occurred while binding the TcpListener. Error: {error}"
+                );
+                return Err(error);
+            }
+        };
+
+        // a blocking accept() could not be interrupted by a shutdown
+        if let Err(error) = tcp_listener.set_nonblocking(true) {
+            let error = Error::Bind(error);
+            error!(
+                self.name,
+                "An error occurred while switching the TcpListener to non-blocking mode. Error: {error}"
+            );
+            return Err(error);
+        }
+        Ok(tcp_listener)
+    }
+
+    /// Log a summary of the settings of this HttpServer. See [`startup_banner`](Self::startup_banner).
+    fn log_startup_banner(&self, local_addr: SocketAddr, tcp: bool) {
+        /// Describe the given optional limit.
+        fn limit<T: fmt::Display>(limit: Option<T>, unit: &str, default: &str) -> String {
+            limit.map_or(default.to_string(), |limit| format!("{limit}{unit}"))
+        }
+
+        let transport = match (tcp, self.reuses_port()) {
+            (true, true) => "TCP (SO_REUSEPORT)",
+            (true, false) => "TCP",
+            (false, _) if self.is_activated() => "TCP (systemd socket activation)",
+            (false, _) => "custom listener",
+        };
+        let connections = if self.inline_connections {
+            "handled inline"
+        } else {
+            "handled in their own tasks"
+        };
+        let accept_errors = match (self.accept_error_policy, self.restart_policy) {
+            (AcceptErrorPolicy::Ignore, _) => "ignored".to_string(),
+            (AcceptErrorPolicy::Stop, Some(policy)) if tcp => format!(
+                "restart after {:?}, at most {} times",
+                policy.backoff, policy.max_restarts
+            ),
+            (AcceptErrorPolicy::Stop, _) => "stop".to_string(),
+        };
+        let lines = [
+            ("Address", local_addr.to_string()),
+            ("Transport", transport.to_string()),
+            ("TLS", "disabled".to_string()),
+            (
+                "PROXY protocol",
+                if self.proxy_protocol {
+                    "required"
+                } else {
+                    "disabled"
+                }
+                .to_string(),
+            ),
+            ("Connections", connections.to_string()),
+            (
+                "Error responses",
+                if self.error_responder.is_some() {
+                    "custom"
+                } else {
+                    "empty"
+                }
+                .to_string(),
+            ),
+            (
+                "Keep-alive timeout",
+                format!("{:?}", self.keep_alive_timeout),
+            ),
+            (
+                "Idle eviction",
+                limit(
+                    self.idle_eviction.map(|limit| format!("{limit:?}")),
+                    "",
+                    "none",
+                ),
+            ),
+            (
+                "Linger",
+                limit(
+                    self.linger.map(|linger| format!("{linger:?}")),
+                    "",
+                    "system default",
+                ),
+            ),
+            (
+                "Disconnect checks",
+                limit(
+                    self.disconnect_check
+                        .map(|interval| format!("every {interval:?}")),
+                    "",
+                    "none",
+                ),
+            ),
+            (
+                "Queue depth",
+                limit(self.queue_depth, " requests", "unlimited"),
+            ),
+            (
+                "Fast paths",
+                if self.fast_paths.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.fast_paths
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                },
+            ),
+            (
+                "Route table",
+                limit(
+                    self.route_table
+                        .as_ref()
+                        .map(|routes| routes.routes().len()),
+                    " routes",
+                    "none",
+                ),
+            ),
+            (
+                "Requests/connection",
+                limit(self.max_requests_per_connection, "", "unlimited"),
+            ),
+            (
+                "Header value limit",
+                format!("{} bytes", self.max_header_value_bytes),
+            ),
+            (
+                "Header count limit",
+                format!("{} headers", self.max_request_headers),
+            ),
+            (
+                "Request body limit",
+                limit(self.max_request_body, " bytes", "2MB (axum's default)"),
+            ),
+            (
+                "Response body limit",
+                match self.max_response_body {
+                    Some(limit) if self.truncate_large_responses => {
+                        format!("{limit} bytes (truncating)")
+                    }
+                    max_response_body => limit(max_response_body, " bytes", "unlimited"),
+                },
+            ),
+            (
+                "Response buffers",
+                self.buffer_pool
+                    .as_ref()
+                    .map_or("not pooled".to_string(), |pool| {
+                        format!("{} of {} bytes", pool.count(), pool.capacity())
+                    }),
+            ),
+            ("Refresh rate", format!("{:?}", self.refresh_rate())),
+            (
+                "Runtime",
+                self.runtime_threads.map_or(
+                    "ambient".to_string(),
+                    |(worker_threads, stack_size)| {
+                        format!("{worker_threads} workers with {stack_size} bytes of stack")
+                    },
+                ),
+            ),
+            ("Accept errors", accept_errors),
+        ];
+
+        info!(self.name, "==== {} ====", self.name);
+        for (setting, value) in lines {
+            info!(self.name, "{setting:<20} {value}");
+        }
+    }
+
+    /// Check whether the internal TcpListener gets the `SO_REUSEPORT` option. See [`reuse_port`](Self::reuse_port).
+    fn reuses_port(&self) -> bool {
+        #[cfg(all(unix, not(target_os = "espidf")))]
+        return self.reuse_port;
+        #[cfg(not(all(unix, not(target_os = "espidf"))))]
+        return false;
+    }
+
+    /// Check whether this HttpServer serves the sockets passed by systemd. See [`from_systemd`](Self::from_systemd).
+    fn is_activated(&self) -> bool {
+        #[cfg(all(unix, not(target_os = "espidf")))]
+        return !self.activated.is_empty();
+        #[cfg(not(all(unix, not(target_os = "espidf"))))]
+        return false;
+    }
+
+    /// Create a [`Listener`] accepting connections from all the sockets passed by systemd. The sockets themselves are kept, so they can
+    /// be served again after a shutdown.
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    fn activated_listeners(&self) -> Result<Box<dyn Listener>, Error> {
+        let listeners = self
+            .activated
+            .iter()
+            .map(|listener| {
+                let listener = listener.try_clone()?;
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .collect::<io::Result<Vec<TcpListener>>>();
+        match listeners {
+            Ok(listeners) => Ok(Box::new(MultiListener {
+                listeners,
+                next: AtomicUsize::new(0),
+            })),
+            Err(error) => {
+                let error = Error::Bind(error);
+                error!(
+                    self.name,
+                    "Could not set up the sockets of systemd. Error: {error}"
+                );
+                Err(error)
+            }
+        }
+    }
+
+    /// Add the [`max_request_body`](Self::max_request_body) limit and the [`priority_throttle`](Self::priority_throttle) to the given
+    /// [`Router`].
+    fn add_layers(&self, router: Router) -> Router {
+        let router = match self.max_request_body {
+            Some(max_request_body) => router.layer(DefaultBodyLimit::max(max_request_body)),
+            None => router,
+        };
+        match &self.priority_throttle {
+            Some(priority_throttle) => router.layer(priority_throttle.clone()),
+            None => router,
+        }
+    }
+
+    /// Collect the settings needed to answer requests.
+    fn handler_config(&self) -> Arc<HandlerConfig> {
+        Arc::new(HandlerConfig {
+            buffer_pool: self.buffer_pool.clone(),
+            canned_responses: CannedResponses::new(self.error_responder.as_ref(), &self.name),
+            disconnect_check: self.disconnect_check,
+            fast_paths: self.fast_paths.clone(),
+            keep_alive_timeout: self.keep_alive_timeout,
+            linger: self.linger,
+            log_unmatched_routes: self.log_unmatched_routes,
+            max_requests_per_connection: self.max_requests_per_connection,
+            max_header_value_bytes: self.max_header_value_bytes,
+            max_request_headers: self.max_request_headers,
+            max_response_body: self.max_response_body,
+            metrics: self.metrics.clone(),
+            name: self.name.clone(),
+            not_found_page: self.not_found_page.clone(),
+            on_connection_result: self.on_connection_result.clone(),
+            proxy_protocol: self.proxy_protocol,
+            queue_slots: self.queue_slots.clone(),
+            request_permits: self.request_permits.clone(),
+            route_table: self.route_table.clone(),
+            rpc_frames: self.rpc_frames,
+            truncate_large_responses: self.truncate_large_responses,
+            yield_between_requests: self.yield_between_requests,
+        })
+    }
+
+    /// Serve the given [`HttpServer`] with the given [`Dispatch`] function instead of a [`Router`]. \
+    /// This low-level mode is meant for the most constrained devices, on which even the routing of axum is too heavy. Requests still get
+    /// parsed and responses serialized like with [`serve`](Self::serve), but the route of every request gets picked by a plain function
+    /// matching its method and path:
+    /// ```
+    /// use goohttp::{
+    ///     axum::http::Method,
+    ///     http_server::RequestHandler,
+    /// };
+    /// use hyper::{
+    ///     Body,
+    ///     Request,
+    ///     Response,
+    /// };
+    ///
+    /// fn index(_: Request<Body>) -> Response<Body> {
+    ///     Response::new(Body::from("Hello World!"))
+    /// }
+    ///
+    /// fn dispatch(method: &Method, path: &str) -> Option<RequestHandler> {
+    ///     match (method, path) {
+    ///         (&Method::GET, "/") => Some(index),
+    ///         _ => None,
+    ///     }
+    /// }
+    /// ```
+    /// Requests without a handler get answered with `404 Not Found`. Since no axum extractors are involved, the
+    /// [`max_request_body`](Self::max_request_body) limit does not apply in this mode.
+    ///
+    /// # Errors
+    ///
+    /// The same errors as for [`serve`](Self::serve) are returned.
+    pub fn serve_dispatch(&mut self, dispatch: Dispatch) -> Result<ServeHandle, Error> {
+        self.start(DispatchService(dispatch), None)
+    }
+
+    /// Start accepting connections of the given listener and answer their requests with the given service. Without a listener, a
+    /// TcpListener gets bound to the address of this HttpServer, which can be replaced after a fault.
+    fn start<S, B>(
+        &mut self,
+        service: S,
+        listener: Option<Box<dyn Listener>>,
+    ) -> Result<ServeHandle, Error>
+    where
+        S: Service<Request<Body>, Response = Response<B>, Error = Infallible>
+            + Clone
+            + Send
+            + 'static,
+        S::Future: Send,
+        B: HttpBody<Data = Bytes> + Send + Unpin + 'static,
+    {
+        if self.is_running() {
+            error!(
+                self.name,
+                "Could not start. Error: {}",
+                Error::AlreadyRunning
+            );
+            return Err(Error::AlreadyRunning);
+        }
+
+        info!(self.name, "Starting...");
+
+        if let Err(error) = self.validate() {
+            let error = Error::Config(error);
+            error!(self.name, "The settings are invalid. Error: {error}");
+            return Err(error);
+        }
+        if let (Some((worker_threads, stack_size)), None) = (self.runtime_threads, &self.runtime) {
+            let runtime = runtime::Builder::new_multi_thread()
+                .worker_threads(worker_threads)
+                .thread_stack_size(stack_size)
+                .thread_name(format!("{}-worker", self.name))
+                .enable_time()
+                .build();
+            match runtime {
+                Ok(runtime) => self.runtime = Some(runtime),
+                Err(error) => {
+                    let error = Error::Runtime(error);
+                    error!(self.name, "Could not create the runtime. Error: {error}");
+                    return Err(error);
+                }
+            }
+        }
+
+        #[cfg(all(unix, not(target_os = "espidf")))]
+        let listener = match listener {
+            None if self.is_activated() => Some(self.activated_listeners()?),
+            listener => listener,
+        };
+        // only a TcpListener bound by this HttpServer can be bound again after a fault
+        let replaceable = listener.is_none();
+        let listener = match listener {
+            Some(listener) => listener,
+            None => Box::new(self.bind_tcp_listener()?),
+        };
+
+        let local_addr = match listener.local_addr() {
+            Ok(local_addr) => local_addr,
+            Err(error) => {
+                let error = Error::Bind(error);
+                error!(
+                    self.name,
+                    "Could not get the address of the listener. Error: {error}"
+                );
+                return Err(error);
+            }
+        };
+
+        // with port `0`, the address given to `bind` does not tell which port clients have to use
+        info!(
+            self.name,
+            "Started! Now listening for clients on `{local_addr}`..."
+        );
+        if self.startup_banner {
+            self.log_startup_banner(local_addr, replaceable);
+        }
+
+        let name = self.name.clone();
+        let connections = self.connections.clone();
+        connections.total_requests.store(0, Ordering::SeqCst);
+        let config = self.handler_config();
+        let accept_error_policy = self.accept_error_policy;
+        let inline_connections = self.inline_connections;
+        let refresh_rate = self.refresh_rate.clone();
+        let restart_policy = self.restart_policy.filter(|_| replaceable);
+        let reuse_port = replaceable && self.reuses_port();
+        let on_stop = self.on_stop.clone();
+        let idle_eviction = self.idle_eviction;
+        let handle = ServeHandle {
+            state: Arc::new(ServeState {
+                connections: self.connections.clone(),
+                local_addr,
+                name: self.name.clone(),
+                refresh_rate: self.refresh_rate.clone(),
+                restarts: AtomicU32::new(0),
+                running: AtomicBool::new(true),
+                stopped: AtomicBool::new(false),
+                stopped_notify: Notify::new(),
+            }),
+        };
+        let stopped = StoppedGuard(handle.state.clone());
+        let main_task = async move {
+            // marks the session as stopped however this task ends, even if it gets aborted
+            let stopped = stopped;
+            let state = &stopped.0;
+            let result = async move {
+                let mut listener = listener;
+                'serving: while state.running.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((client, client_addr)) => {
+                            trace!(
+                                name,
+                                "A new client with the address `{client_addr}` connected."
+                            );
+
+                            // with fast paths, a request skipping the queue could be waiting on the new connection
+                            if let (Some(permits), true) =
+                                (&config.request_permits, config.fast_paths.is_empty())
+                            {
+                                Self::wait_for_permit(state, permits).await;
+                            }
+                            let connection =
+                                ConnectionGuard::new(&connections, client_addr, &*client);
+                            let handling = Self::handle_connection(
+                                client,
+                                client_addr,
+                                service.clone(),
+                                config.clone(),
+                                connection,
+                            );
+                            if inline_connections {
+                                let _ = handling.await;
+                            } else {
+                                spawn(handling);
+                            }
+                        }
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+                        Err(error) => {
+                            let error = Error::Accept(error);
+                            if accept_error_policy == AcceptErrorPolicy::Stop {
+                                let Some(policy) = restart_policy else {
+                                    error!(name, "Could not accept an incoming connection. The HttpServer will stop. Error: {error}");
+                                    return Err(error);
+                                };
+                                error!(name, "Could not accept an incoming connection. The listener will be replaced. Error: {error}");
+                                // the broken listener has to be closed before its address can be bound again
+                                drop(listener);
+                                loop {
+                                    let restart = state.restarts.load(Ordering::SeqCst) + 1;
+                                    if restart > policy.max_restarts {
+                                        error!(name, "The listener was already replaced {} times. The HttpServer will stop.", policy.max_restarts);
+                                        return Err(error);
+                                    }
+                                    state.restarts.store(restart, Ordering::SeqCst);
+                                    warn!(
+                                        name,
+                                        "Restarting in {:?} ({restart}/{}).",
+                                        policy.backoff,
+                                        policy.max_restarts
+                                    );
+                                    if !Self::wait_for_restart(state, policy.backoff).await {
+                                        info!(name, "The pending restart was cancelled.");
+                                        return Ok(());
+                                    }
+                                    match bind_tcp_listener(local_addr, reuse_port).and_then(
+                                        |listener| listener.set_nonblocking(true).map(|()| listener),
+                                    ) {
+                                        Ok(tcp_listener) => {
+                                            info!(
+                                                name,
+                                                "Restarted! Now listening for clients again..."
+                                            );
+                                            listener = Box::new(tcp_listener);
+                                            continue 'serving;
+                                        }
+                                        Err(error) => {
+                                            error!(
+                                                name,
+                                                "Could not bind a new listener. Error: {error}"
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                            error!(name, "Could not accept an incoming connection. It will be ignored. Error: {error}");
+                        }
+                    }
+                    if let Some(limit) = idle_eviction {
+                        connections.evict_idle(limit, &name);
+                    }
+                    // we need to sleep here to give the handlers a chance to execute
+                    sleep(Duration::from_nanos(refresh_rate.load(Ordering::SeqCst))).await;
+                }
+
+                // connections waiting in the backlog would get reset once the listener is closed, while other listeners bound to the same port
+                // could have answered them
+                if reuse_port {
+                    while let Ok((client, client_addr)) = listener.accept() {
+                        trace!(
+                            name,
+                            "A new client with the address `{client_addr}` connected while the listener was closing."
+                        );
+                        let connection = ConnectionGuard::new(&connections, client_addr, &*client);
+                        spawn(Self::handle_connection(
+                            client,
+                            client_addr,
+                            service.clone(),
+                            config.clone(),
+                            connection,
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            .await;
+
+            if let Some(on_stop) = on_stop {
+                on_stop(&result);
+            }
+            result
+        };
+        // the tasks of the connections get spawned on the runtime of the task accepting them
+        let main_task = match &self.runtime {
+            Some(runtime) => runtime.spawn(main_task),
+            None => spawn(main_task),
+        };
+
+        self.main_task = Some(main_task);
+        self.handle = Some(handle.clone());
+
+        Ok(handle)
+    }
+    /// Wait for the backoff of a restart to be over. `false` is returned if the HttpServer got shut down in the meantime.
+    async fn wait_for_restart(state: &ServeState, backoff: Duration) -> bool {
+        let waiting = async {
+            while state.running.load(Ordering::SeqCst) {
+                sleep(Duration::from_nanos(
+                    state.refresh_rate.load(Ordering::SeqCst),
+                ))
+                .await;
+            }
+        };
+        timeout(backoff, waiting).await.is_err() && state.running.load(Ordering::SeqCst)
+    }
+    /// Wait until one of the given permits is available, so a new connection would not have to wait for one right away. This also returns
+    /// once the HttpServer got shut down.
+    async fn wait_for_permit(state: &ServeState, permits: &Semaphore) {
+        while permits.available_permits() == 0 && state.running.load(Ordering::SeqCst) {
+            sleep(Duration::from_nanos(
+                state.refresh_rate.load(Ordering::SeqCst),
+            ))
+            .await;
+        }
+    }
+    /// Handle the given connection and report its result.
+    async fn handle_connection<S, B>(
+        client: Box<dyn Stream>,
+        peer_addr: SocketAddr,
+        service: S,
+        config: Arc<HandlerConfig>,
+        connection: ConnectionGuard,
+    ) -> Result<(), Error>
+    where
+        S: Service<Request<Body>, Response = Response<B>, Error = Infallible>,
+        B: HttpBody<Data = Bytes> + Unpin,
+    {
+        config.metrics.on_connection_change(1);
+        if let Some(linger) = config.linger {
+            // `error` is only used by `debug!`, which is left out of release builds
+            #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+            if let Err(error) = client.set_linger(Some(linger)) {
+                debug!(
+                    config.name,
+                    "Could not set the linger option of a connection. Error: {error}"
+                );
+            }
+        }
+        let client = connection.count_traffic(client);
+        let result = if config.rpc_frames {
+            Self::rpc_handler(client, peer_addr, service, config.clone(), connection).await
+        } else {
+            Self::handler(client, peer_addr, service, config.clone(), connection).await
+        };
+        config.metrics.on_connection_change(-1);
+        if let Err(error) = &result {
+            trace!(config.name, "A connection was closed. Error: {error}");
+        }
+        if let Some(on_connection_result) = &config.on_connection_result {
+            on_connection_result(&result);
+        }
+        result
+    }
+    /// The handler of each client. \
+    /// Requests get read one after another from the same connection, which allows clients to pipeline their requests. The connection gets
+    /// closed once the client closes it, an error occurs, or a request asks for it to be closed.
+    async fn handler<S, B>(
+        client: Box<dyn Stream>,
+        peer_addr: SocketAddr,
+        mut service: S,
+        config: Arc<HandlerConfig>,
+        connection: ConnectionGuard,
+    ) -> Result<(), Error>
+    where
+        S: Service<Request<Body>, Response = Response<B>, Error = Infallible>,
+        B: HttpBody<Data = Bytes> + Unpin,
+    {
+        /// Read the head of the next [`Request`], how its body is framed, and its request-target as it was sent from the given reader. \
+        /// `None` is returned if the client closed the connection before sending another request, or did not send one before the read
+        /// timeout of the connection ran out.
+        fn read_request(
+            reader: &mut impl BufRead,
+            config: &HandlerConfig,
+        ) -> Result<Option<(Request<()>, BodyReadStrategy, String)>, Error> {
+            // request line (empty lines in front of it should be ignored)
+            let mut request_line = String::new();
+            while request_line.is_empty() {
+                match reader.fill_buf() {
+                    Ok([]) => return Ok(None),
+                    Ok(_) => {}
+                    Err(error)
+                        if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                    {
+                        trace!(
+                            config.name,
+                            "A connection was closed, since it did not send a request within {:?}.",
+                            config.keep_alive_timeout
+                        );
+                        return Ok(None);
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+                // the request line may be as long as a header line
+                let max_line =
+                    (config.max_header_value_bytes as u64).saturating_add(MAX_HEADER_NAME_BYTES);
+                request_line = read_line(reader, max_line)?.ok_or(ParseError::UriTooLong)?;
+            }
+
+            let mut request_line = request_line.split(' ');
+            let (Some(method), Some(uri), Some(version), None) = (
+                request_line.next(),
+                request_line.next(),
+                request_line.next(),
+                request_line.next(),
+            ) else {
+                return Err(ParseError::RequestLine.into());
+            };
+            let Ok(method) = Method::from_bytes(method.as_bytes()) else {
+                return Err(ParseError::Method.into());
+            };
+            let raw_target = uri.to_string();
+            let Ok(uri) = uri.parse::<Uri>() else {
+                return Err(ParseError::Uri.into());
+            };
+            let version = match version {
+                "HTTP/1.0" => Version::HTTP_10,
+                "HTTP/1.1" => Version::HTTP_11,
+                _ => return Err(ParseError::Version.into()),
+            };
+
+            let mut request = Request::builder().method(method).uri(uri).version(version);
+
+            // headers
+            let headers = read_headers(
+                reader,
+                config.max_header_value_bytes,
+                config.max_request_headers,
+            )?;
+            if let Some(request_headers) = request.headers_mut() {
+                *request_headers = headers;
+            }
+
+            // body
+            let headers = request.headers_ref().ok_or(ParseError::Header)?;
+            let mut framing = BodyReadStrategy::Length(0);
+            // HTTP/1.0 clients may end the body by closing the connection, unless they want to keep it open for the next request. Requests
+            // whose method does not define a body are sent without one, and waiting for the connection to close would only have the
+            // route wait for a client waiting for its response.
+            let may_have_body = request.method_ref().is_some_and(|method| {
+                ![
+                    Method::GET,
+                    Method::HEAD,
+                    Method::DELETE,
+                    Method::OPTIONS,
+                    Method::TRACE,
+                    Method::CONNECT,
+                ]
+                .contains(method)
+            });
+            let keeps_alive = headers.get_all(CONNECTION).iter().any(|connection| {
+                connection.to_str().is_ok_and(|connection| {
+                    connection
+                        .split(',')
+                        .any(|option| option.trim().eq_ignore_ascii_case("keep-alive"))
+                })
+            });
+            if version == Version::HTTP_10 && !keeps_alive && may_have_body {
+                framing = BodyReadStrategy::UntilClose;
+            }
+            if let Some(transfer_encoding) = headers.get(TRANSFER_ENCODING) {
+                // without decoding the body we would not know where the next request starts, and a `Content-Length` next to it could
+                // be used to make a proxy in front of the server disagree about it
+                if !transfer_encoding
+                    .as_bytes()
+                    .eq_ignore_ascii_case(b"chunked")
+                    || headers.get_all(TRANSFER_ENCODING).iter().count() > 1
+                    || headers.contains_key(CONTENT_LENGTH)
+                    || version == Version::HTTP_10
+                {
+                    return Err(ParseError::TransferEncoding.into());
+                }
+                framing = BodyReadStrategy::Chunked;
+            } else if let Some(content_length) = headers.get(CONTENT_LENGTH) {
+                let Some(content_length) = content_length
+                    .to_str()
+                    .ok()
+                    .and_then(|content_length| content_length.parse::<u64>().ok())
+                else {
+                    return Err(ParseError::ContentLength.into());
+                };
+                framing = BodyReadStrategy::Length(content_length);
+            }
+
+            match request.body(()) {
+                Ok(request) => Ok(Some((request, framing, raw_target))),
+                Err(_) => Err(ParseError::Header.into()),
+            }
+        }
+        /// Remove an offer to upgrade the connection to HTTP/2 from the given [`Request`]. \
+        /// This HttpServer only speaks HTTP/1.x, so the offer gets ignored as allowed by RFC 7540 and the request gets answered with
+        /// HTTP/1.1 instead. Removing the headers belonging to it keeps routes from mistaking the request for an upgrade they could
+        /// accept.
+        fn ignore_h2c_upgrade(request: &mut Request<Body>, name: &str) {
+            let offers_h2c = request.headers().get_all(UPGRADE).iter().any(|upgrade| {
+                upgrade.to_str().is_ok_and(|upgrade| {
+                    upgrade
+                        .split(',')
+                        .any(|protocol| protocol.trim().eq_ignore_ascii_case("h2c"))
+                })
+            });
+            if !offers_h2c {
+                return;
+            }
+
+            trace!(
+                name,
+                "A client offered to upgrade to HTTP/2. The offer will be ignored."
+            );
+            let headers = request.headers_mut();
+            headers.remove(UPGRADE);
+            headers.remove("http2-settings");
+        }
+        /// Check whether the connection should be kept open after answering the given [`Request`].
+        fn keep_alive(request: &Request<Body>) -> bool {
+            let mut keep_alive = request.version() >= Version::HTTP_11;
+            for connection in request.headers().get_all(CONNECTION) {
+                let Ok(connection) = connection.to_str() else {
+                    continue;
+                };
+                for option in connection.split(',') {
+                    let option = option.trim();
+                    if option.eq_ignore_ascii_case("close") {
+                        return false;
+                    } else if option.eq_ignore_ascii_case("keep-alive") {
+                        keep_alive = true;
+                    }
+                }
+            }
+            keep_alive
+        }
+        /// Check whether the connection has to be closed after the given response, because the request may not have been read the way
+        /// the client meant it, or the route asked for it with `Connection: close`. This header of the route gets removed, since it
+        /// gets replaced by the ones of [`connection_headers`].
+        fn closes_connection(status: StatusCode, headers: &mut HeaderMap) -> bool {
+            let asked_to_close = headers.get_all(CONNECTION).iter().any(|connection| {
+                connection.to_str().is_ok_and(|connection| {
+                    connection
+                        .split(',')
+                        .any(|option| option.trim().eq_ignore_ascii_case("close"))
+                })
+            });
+            if asked_to_close {
+                headers.remove(CONNECTION);
+            }
+            asked_to_close
+                || status == StatusCode::BAD_REQUEST
+                || status == StatusCode::INTERNAL_SERVER_ERROR
+        }
+        /// Add the given `Allow` header to a `405 Method Not Allowed` response, unless the route already sent one. An empty list of
+        /// methods is still sent, since it tells the client that the path does not take any method right now.
+        fn add_allow_header(
+            status: StatusCode,
+            headers: &mut HeaderMap,
+            allow: Option<HeaderValue>,
+        ) {
+            if let Some(allow) = allow.filter(|_| status == StatusCode::METHOD_NOT_ALLOWED) {
+                headers.entry(ALLOW).or_insert(allow);
+            }
+        }
+        /// Remove the body of the given response to a `HEAD` request. Its `Content-Length` stays the length of the body, unless the route
+        /// already removed the body and left the given `Content-Length` of the `GET` response.
+        fn strip_body(response: &mut Response<Vec<u8>>, content_length: Option<HeaderValue>) {
+            if response.body().is_empty() {
+                if let Some(content_length) = content_length {
+                    response
+                        .headers_mut()
+                        .insert(CONTENT_LENGTH, content_length);
+                }
+            }
+            response.body_mut().clear();
+        }
+        /// Write the given response to the client and flush it. \
+        /// If this fails, the number of bytes already written gets logged, which helps to tell a truncating network apart from the
+        /// server.
+        fn write_response(client: &mut impl Write, response: &[u8], name: &str) -> io::Result<()> {
+            let mut written = 0;
+            while written < response.len() {
+                match client.write(&response[written..]) {
+                    Ok(0) => {
+                        error!(
+                            name,
+                            "The connection stopped accepting data after {written} of {} bytes of the response were written.",
+                            response.len()
+                        );
+                        return Err(ErrorKind::WriteZero.into());
+                    }
+                    Ok(bytes) => written += bytes,
+                    Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                    Err(error) => {
+                        error!(
+                            name,
+                            "Failed to write the response after {written} of {} bytes were written. Error: {error}",
+                            response.len()
+                        );
+                        return Err(error);
+                    }
+                }
+            }
+
+            if let Err(error) = client.flush() {
+                error!(
+                    name,
+                    "Failed to flush the response after all {written} bytes were written. Error: {error}"
+                );
+                return Err(error);
+            }
+            Ok(())
+        }
+        /// Get the headers telling the client whether the connection stays open after the current response, and under which conditions.
+        fn connection_headers(
+            keep_alive: bool,
+            version: Version,
+            remaining_requests: Option<u64>,
+            config: &HandlerConfig,
+        ) -> Vec<(HeaderName, HeaderValue)> {
+            if !keep_alive {
+                return vec![(CONNECTION, HeaderValue::from_static("close"))];
+            }
+
+            let mut headers = vec![];
+            // HTTP/1.0 clients assume that the connection gets closed unless told otherwise
+            if version == Version::HTTP_10 {
+                headers.push((CONNECTION, HeaderValue::from_static("keep-alive")));
+            }
+            let mut parameters = vec![format!("timeout={}", config.keep_alive_timeout.as_secs())];
+            if let Some(remaining_requests) = remaining_requests {
+                parameters.push(format!("max={remaining_requests}"));
+            }
+            headers.push((
+                HeaderName::from_static("keep-alive"),
+                HeaderValue::from_str(&parameters.join(", "))
+                    .expect("The parameters should only contain visible ASCII characters."),
+            ));
+            headers
+        }
+        /// Write the [`EarlyHints`] of the given response to the client, and remove them from its extensions. HTTP/1.0 clients do not
+        /// know informational responses, so they do not get them.
+        fn write_early_hints<B>(
+            client: &Mutex<BufReader<Box<dyn Stream>>>,
+            response: &mut Response<B>,
+            version: Version,
+            name: &str,
+        ) -> Result<(), Error> {
+            let Some(EarlyHints(hints)) = response.extensions_mut().remove::<EarlyHints>() else {
+                return Ok(());
+            };
+            if version < Version::HTTP_11 {
+                return Ok(());
+            }
+            let mut messages = vec![];
+            for mut headers in hints {
+                // a 103 response only gives hints, so it can go without the headers that had to be removed
+                sanitize_headers(&mut headers, name);
+                messages.extend_from_slice(b"HTTP/1.1 103 Early Hints\r\n");
+                for (header_name, header_value) in &headers {
+                    messages.extend_from_slice(header_name.as_str().as_bytes());
+                    messages.extend_from_slice(b": ");
+                    messages.extend_from_slice(header_value.as_bytes());
+                    messages.extend_from_slice(b"\r\n");
+                }
+                messages.extend_from_slice(b"\r\n");
+            }
+            write_response(lock_reader(client)?.get_mut(), &messages, name)?;
+            Ok(())
+        }
+        /// Write the head of the given [`StreamBody`](crate::response::StreamBody) response to the client, followed by every chunk of its
+        /// body as soon as it arrives. Without `chunked`, the chunks get written as they are, so the client has to read until the
+        /// connection gets closed. A response with a `Content-Length` gets its chunks written as they are as well, but fails if its body
+        /// does not have this length. With `head`, only the head gets written. \
+        /// The size of the written body gets returned.
+        async fn write_streamed_response<B>(
+            client: &Mutex<BufReader<Box<dyn Stream>>>,
+            response: Response<B>,
+            chunked: bool,
+            head: bool,
+            config: &HandlerConfig,
+        ) -> Result<usize, Error>
+        where
+            B: HttpBody<Data = Bytes> + Unpin,
+        {
+            let (name, buffer_pool) = (&config.name, config.buffer_pool.as_ref());
+            let (mut parts, mut body) = response.into_parts();
+            if !sanitize_headers(&mut parts.headers, name) {
+                // the head of the streamed response cannot be sent, and its body has no length to tell where it ends
+                write_response(
+                    lock_reader(client)?.get_mut(),
+                    &config.canned_responses.internal_server_error.bytes,
+                    name,
+                )?;
+                return Err(SerializeError::Header.into());
+            }
+            let length = declared_length(&parts.headers);
+            let chunked = chunked && length.is_none();
+            if length.is_none() {
+                parts.headers.remove(CONTENT_LENGTH);
+            }
+            if chunked {
+                parts
+                    .headers
+                    .insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+            } else {
+                parts.headers.remove(TRANSFER_ENCODING);
+            }
+            write_response(
+                lock_reader(client)?.get_mut(),
+                &response_to_bytes(Response::from_parts(parts, vec![]), buffer_pool)?,
+                name,
+            )?;
+            if head {
+                return Ok(0);
+            }
+
+            let mut body_bytes = 0;
+            while let Some(chunk) = body.data().await {
+                let chunk = chunk.map_err(|_| SerializeError::Body)?;
+                // bytes beyond the declared length would be mistaken for the next response
+                if let Some(left) = length
+                    .map(|length| length - body_bytes as u64)
+                    .filter(|left| chunk.len() as u64 > *left)
+                {
+                    write_response(
+                        lock_reader(client)?.get_mut(),
+                        &chunk[..left as usize],
+                        name,
+                    )?;
+                    return Err(SerializeError::Body.into());
+                }
+                body_bytes += chunk.len();
+                // an empty chunk would mark the end of the body
+                if chunk.is_empty() {
+                    continue;
+                }
+                if chunked {
+                    let mut framed = format!("{:x}\r\n", chunk.len()).into_bytes();
+                    framed.extend_from_slice(&chunk);
+                    framed.extend_from_slice(b"\r\n");
+                    write_response(lock_reader(client)?.get_mut(), &framed, name)?;
+                } else {
+                    write_response(lock_reader(client)?.get_mut(), &chunk, name)?;
+                }
+            }
+            if chunked {
+                write_response(lock_reader(client)?.get_mut(), b"0\r\n\r\n", name)?;
+            }
+            if length.is_some_and(|length| length != body_bytes as u64) {
+                return Err(SerializeError::Body.into());
+            }
+            Ok(body_bytes)
+        }
+
+        // the body of a request gets read while the request is answered, so the connection has to be shared with it
+        let reader = Arc::new(Mutex::new(BufReader::new(client)));
+        let mut peer_addr = peer_addr;
+        if config.proxy_protocol {
+            if let Some(source) = blocking(|| read_proxy_header(&mut *lock_reader(&reader)?))? {
+                peer_addr = source;
+                connection.set_peer_addr(source);
+            }
+        }
+        for requests_served in 0.. {
+            connection.set_state(ConnectionState::Idle);
+            // the timeout also covers the rest of the head, so a client sending it slowly cannot hold the connection either. With a
+            // timeout of zero, the connection gets closed after the first response anyway.
+            lock_reader(&reader)?.get_mut().set_read_timeout(
+                Some(config.keep_alive_timeout).filter(|timeout| !timeout.is_zero()),
+            )?;
+            // the guard of the reader has to be dropped before the response below can be written
+            let next_request = blocking(|| read_request(&mut *lock_reader(&reader)?, &config));
+            lock_reader(&reader)?.get_mut().set_read_timeout(None)?;
+            if let Some(canned) = next_request
+                .as_ref()
+                .err()
+                .and_then(|error| config.canned_responses.for_read_error(error))
+            {
+                write_response(lock_reader(&reader)?.get_mut(), &canned.bytes, &config.name)?;
+            }
+            let Some((request, framing, raw_target)) = next_request? else {
+                return Ok(());
+            };
+            let fast_path = config.fast_paths.get(request.uri().path()).copied();
+            // the permit is held until the response was written
+            let _permit = match (&config.request_permits, fast_path) {
+                (Some(permits), None) => match permits.clone().try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    Err(_) => {
+                        // the place in the queue is only needed until the request got its permit
+                        let Some(_queued) = config
+                            .queue_slots
+                            .as_ref()
+                            .and_then(|slots| slots.try_acquire().ok())
+                        else {
+                            write_response(
+                                lock_reader(&reader)?.get_mut(),
+                                &config.canned_responses.service_unavailable.bytes,
+                                &config.name,
+                            )?;
+                            return Ok(());
+                        };
+                        permits.clone().acquire_owned().await.ok()
+                    }
+                },
+                _ => None,
+            };
+            connection.start_request();
+            let started = Instant::now();
+            config
+                .metrics
+                .on_request_start(request.method(), request.uri().path());
+            let route = config
+                .route_table
+                .as_ref()
+                .map(|routes| routes.find(request.method(), request.uri().path()));
+            // the methods the client could have used, in case the router answers with `405 Method Not Allowed`
+            let allow = match (&config.route_table, route) {
+                (Some(routes), Some(None)) => {
+                    HeaderValue::from_str(&routes.methods(request.uri().path()).join(", ")).ok()
+                }
+                _ => None,
+            };
+            let remaining = Arc::new(AtomicU64::new(0));
+            // whether the last chunk and the trailers of a chunked body were read
+            let chunks_finished = Arc::new(AtomicBool::new(true));
+            let mut trailers = None;
+            let mut request = request.map(|()| match framing {
+                BodyReadStrategy::Length(0) => Body::empty(),
+                BodyReadStrategy::Length(body_length) => {
+                    remaining.store(body_length, Ordering::SeqCst);
+                    Body::wrap_stream(BodyStream {
+                        reader: reader.clone(),
+                        remaining: remaining.clone(),
+                        until_close: false,
+                    })
+                }
+                BodyReadStrategy::UntilClose => {
+                    remaining.store(u64::MAX, Ordering::SeqCst);
+                    Body::wrap_stream(BodyStream {
+                        reader: reader.clone(),
+                        remaining: remaining.clone(),
+                        until_close: true,
+                    })
+                }
+                BodyReadStrategy::Chunked => {
+                    chunks_finished.store(false, Ordering::SeqCst);
+                    let request_trailers = Trailers::default();
+                    trailers = Some(request_trailers.clone());
+                    Body::wrap_stream(ChunkedBodyStream {
+                        reader: reader.clone(),
+                        chunk_remaining: remaining.clone(),
+                        finished: chunks_finished.clone(),
+                        trailers: request_trailers,
+                        config: config.clone(),
+                    })
+                }
+            });
+            if let Some(trailers) = trailers {
+                request.extensions_mut().insert(trailers);
+            }
+            let expects_continue = request.headers().contains_key(EXPECT);
+            let connection_info = ConnectionInfo {
+                peer_addr,
+                raw_target,
+                requests_served,
+                tls: false,
+                version: request.version(),
+            };
+            request.extensions_mut().insert(connection_info);
+            ignore_h2c_upgrade(&mut request, &config.name);
+            let mut keep_alive = keep_alive(&request);
+            let version = request.version();
+            // the number of requests the client may send after this one
+            let remaining_requests = config
+                .max_requests_per_connection
+                .map(|max_requests| max_requests.saturating_sub(requests_served + 1));
+            if remaining_requests == Some(0) || config.keep_alive_timeout.is_zero() {
+                keep_alive = false;
+            }
+            let unmatched_route = config
+                .log_unmatched_routes
+                .then(|| format!("{} {}", request.method(), request.uri().path()));
+            // the response to a `HEAD` request gets the headers of the `GET` response, but no body
+            let head = request.method() == Method::HEAD;
+            // HTTP/1.0 clients do not understand chunked bodies, so streamed bodies can only end with the connection
+            let chunked = request.version() >= Version::HTTP_11;
+
+            let answer = match fast_path {
+                Some(handler) => Answer::FastPath(handler(request)),
+                None => match call_service(&mut service, request, &reader, &config).await {
+                    Some(response) => Answer::Routed(response),
+                    None => return Ok(()),
+                },
+            };
+            let streamed = matches!(&answer, Answer::Routed(response) if response.extensions().get::<Streamed>().is_some());
+            let streamed_length = match &answer {
+                Answer::Routed(response) if streamed => declared_length(response.headers()),
+                _ => None,
+            };
+            // a body the route did not read, like the one of a `GET` request, gets skipped, so the next request can still be read. The
+            // body of a streamed response may still be read by it, and a client expecting `100 Continue` would never send its body.
+            if !streamed && !expects_continue {
+                let unread = match framing {
+                    BodyReadStrategy::Length(_) => Some(Body::wrap_stream(BodyStream {
+                        reader: reader.clone(),
+                        remaining: remaining.clone(),
+                        until_close: false,
+                    })),
+                    BodyReadStrategy::Chunked => Some(Body::wrap_stream(ChunkedBodyStream {
+                        reader: reader.clone(),
+                        chunk_remaining: remaining.clone(),
+                        finished: chunks_finished.clone(),
+                        trailers: Trailers::default(),
+                        config: config.clone(),
+                    })),
+                    BodyReadStrategy::UntilClose => None,
+                };
+                if let Some(unread) = unread {
+                    drain_body(unread).await;
+                }
+            }
+            // an unread rest of the body would be mistaken for the next request
+            if remaining.load(Ordering::SeqCst) != 0
+                || !chunks_finished.load(Ordering::SeqCst)
+                || (streamed && !chunked && streamed_length.is_none())
+            {
+                keep_alive = false;
+            }
+
+            let (status, body_bytes) = match answer {
+                Answer::Routed(mut response) if streamed => {
+                    let status = response.status();
+                    write_early_hints(&reader, &mut response, version, &config.name)?;
+                    add_allow_header(status, response.headers_mut(), allow);
+                    keep_alive &= !closes_connection(status, response.headers_mut());
+                    response.headers_mut().extend(connection_headers(
+                        keep_alive,
+                        version,
+                        remaining_requests,
+                        &config,
+                    ));
+                    let body_bytes =
+                        write_streamed_response(&reader, response, chunked, head, &config).await?;
+                    (status, body_bytes)
+                }
+                answer => {
+                    // axum strips the body of responses to `HEAD` requests, but keeps their length, and the headers of a replaced response
+                    // only get known once it is buffered
+                    let (content_length, mut response) = match answer {
+                        Answer::Routed(response) => (
+                            response.headers().get(CONTENT_LENGTH).cloned(),
+                            buffer_response(response, unmatched_route.as_deref(), &config).await?,
+                        ),
+                        Answer::FastPath(response) => (
+                            response.headers().get(CONTENT_LENGTH).cloned(),
+                            buffer_response(response, None, &config).await?,
+                        ),
+                    };
+                    // the rejection of a body limit gets sent like the other errors of the HttpServer, and the connection gets closed anyway
+                    // because of the unread body
+                    if response.status() == StatusCode::PAYLOAD_TOO_LARGE
+                        && response.body().starts_with(BODY_LIMIT_REJECTION)
+                    {
+                        let canned = &config.canned_responses.payload_too_large;
+                        write_response(
+                            lock_reader(&reader)?.get_mut(),
+                            &canned.bytes,
+                            &config.name,
+                        )?;
+                        keep_alive = false;
+                        (StatusCode::PAYLOAD_TOO_LARGE, canned.body_length)
+                    } else {
+                        write_early_hints(&reader, &mut response, version, &config.name)?;
+                        add_allow_header(response.status(), response.headers_mut(), allow);
+                        keep_alive &= !closes_connection(response.status(), response.headers_mut());
+                        response.headers_mut().extend(connection_headers(
+                            keep_alive,
+                            version,
+                            remaining_requests,
+                            &config,
+                        ));
+                        if head {
+                            strip_body(&mut response, content_length);
+                        }
+                        let (status, body_bytes) = (response.status(), response.body().len());
+                        write_response(
+                            lock_reader(&reader)?.get_mut(),
+                            &response_to_bytes(response, config.buffer_pool.as_ref())?,
+                            &config.name,
+                        )?;
+                        (status, body_bytes)
+                    }
+                }
+            };
+            let duration = started.elapsed();
+            config.metrics.on_request_end(status, duration, body_bytes);
+            if let Some(route) = route {
+                config.metrics.on_route_end(route, status, duration);
+            }
+
+            if !keep_alive {
+                return Ok(());
+            }
+            if config.yield_between_requests {
+                yield_now().await;
+            }
+        }
+        Ok(())
+    }
+    /// The handler of each client of an [`RpcServer`](crate::rpc_server::RpcServer). \
+    /// Like with HTTP, frames get read one after another from the same connection. The connection gets closed once the client closes it,
+    /// an error occurs, a route did not read the whole body of its request, or the
+    /// [`max_requests_per_connection`](Self::max_requests_per_connection) were answered.
+    async fn rpc_handler<S, B>(
+        client: Box<dyn Stream>,
+        peer_addr: SocketAddr,
+        mut service: S,
+        config: Arc<HandlerConfig>,
+        connection: ConnectionGuard,
+    ) -> Result<(), Error>
+    where
+        S: Service<Request<Body>, Response = Response<B>, Error = Infallible>,
+        B: HttpBody<Data = Bytes> + Unpin,
+    {
+        let reader = Arc::new(Mutex::new(BufReader::new(client)));
+        let mut peer_addr = peer_addr;
+        if config.proxy_protocol {
+            if let Some(source) = blocking(|| read_proxy_header(&mut *lock_reader(&reader)?))? {
+                peer_addr = source;
+                connection.set_peer_addr(source);
+            }
+        }
+        for requests_served in 0.. {
+            connection.set_state(ConnectionState::Idle);
+            let Some((path, body_length)) = blocking(|| {
+                read_request_frame(&mut *lock_reader(&reader)?, config.max_header_value_bytes)
+            })?
+            else {
+                return Ok(());
+            };
+            // the permit is held until the response was written
+            let _permit = match &config.request_permits {
+                Some(permits) => permits.clone().acquire_owned().await.ok(),
+                None => None,
+            };
+            connection.start_request();
+            let started = Instant::now();
+            config.metrics.on_request_start(&Method::POST, &path);
+            let route = config
+                .route_table
+                .as_ref()
+                .map(|routes| routes.find(&Method::POST, &path));
+            let remaining = Arc::new(AtomicU64::new(body_length));
+            let body = if body_length == 0 {
+                Body::empty()
+            } else {
+                Body::wrap_stream(BodyStream {
+                    reader: reader.clone(),
+                    remaining: remaining.clone(),
+                    until_close: false,
+                })
+            };
+            let mut request = Request::post(path.as_str())
+                .header(CONTENT_LENGTH, body_length)
+                .body(body)
+                .map_err(|_| ParseError::RpcFrame)?;
+            let unmatched_route = config.log_unmatched_routes.then(|| format!("POST {path}"));
+            request.extensions_mut().insert(ConnectionInfo {
+                peer_addr,
+                raw_target: path,
+                requests_served,
+                tls: false,
+                version: Version::HTTP_11,
+            });
+
+            let Some(response) = call_service(&mut service, request, &reader, &config).await else {
+                return Ok(());
+            };
+            let response = buffer_response(response, unmatched_route.as_deref(), &config).await?;
+            let (status, body_bytes) = (response.status(), response.body().len());
+            write_response_frame(lock_reader(&reader)?.get_mut(), status, response.body())?;
+            let duration = started.elapsed();
+            config.metrics.on_request_end(status, duration, body_bytes);
+            if let Some(route) = route {
+                config.metrics.on_route_end(route, status, duration);
+            }
+
+            // an unread rest of the body would be mistaken for the next frame
+            if remaining.load(Ordering::SeqCst) != 0
+                || config
+                    .max_requests_per_connection
+                    .is_some_and(|max_requests| requests_served + 1 >= max_requests)
+            {
+                return Ok(());
+            }
+            if config.yield_between_requests {
+                yield_now().await;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Dropping a running HttpServer stops the task accepting connections right away, which closes the internal TcpListener, so the address
+/// can be bound again. Unlike [`shutdown`](HttpServer::shutdown), this does not wait for the task to stop. Connections that were already
+/// accepted get answered before they close on their own. \
+/// If this HttpServer was already shut down, nothing happens.
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        if let Some(main_task) = self.main_task.take() {
+            if let Some(handle) = &self.handle {
+                handle.state.running.store(false, Ordering::SeqCst);
+            }
+            main_task.abort();
+            debug!(
+                self.name,
+                "Stopped, since the HttpServer was dropped without being shut down."
+            );
+        }
+        // a runtime cannot be dropped while blocking is not allowed, like in an async function
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// A handle to a serving [`HttpServer`], which gets returned by [`serve`](HttpServer::serve). It can be cloned and passed to other tasks,
+/// so they can stop the HttpServer without owning it:
+/// ```no_run
+/// # async fn example() {
+/// use goohttp::{
+///     axum::Router,
+///     http_server::HttpServer,
+/// };
+///
+/// let mut http_server = HttpServer::bind("0.0.0.0:80", None, None).unwrap();
+/// let handle = http_server.serve(Router::new()).unwrap();
+///
+/// let remote = handle.clone();
+/// tokio::spawn(async move {
+///     // e.g. once the device is about to enter deep sleep
+///     remote.shutdown().await;
+/// });
+///
+/// handle.await_stopped().await;
+/// # }
+/// ```
+/// The handle does not keep the HttpServer alive. Dropping the HttpServer stops serving, as described in its [`Drop`] implementation.
+#[derive(Clone)]
+pub struct ServeHandle {
+    /// The state shared with the task accepting connections.
+    state: Arc<ServeState>,
+}
+impl ServeHandle {
+    /// Stop accepting connections and wait until the internal TcpListener is closed. Since the listener only gets polled every
+    /// [`refresh_rate`](HttpServer::refresh_rate), this method waits at most this long. Connections that were already accepted get
+    /// answered before they close on their own. \
+    /// If the HttpServer already stopped, this method will do nothing.
+    pub async fn shutdown(&self) {
+        if self.state.running.swap(false, Ordering::SeqCst) {
+            self.await_stopped().await;
+            info!(self.state.name, "Stopped.");
+        } else {
+            self.await_stopped().await;
+        }
+    }
+
+    /// Stop accepting connections like [`shutdown`](Self::shutdown), and then wait until no request is being read or answered anymore, but
+    /// at most for the given duration. Connections waiting for their next request do not get waited for, since a client may keep them
+    /// open indefinitely. \
+    /// `true` is returned if all requests were answered in time.
+    pub async fn graceful_shutdown(&self, grace_period: Duration) -> bool {
+        let drained = timeout(grace_period, async {
+            self.shutdown().await;
+            while self.state.connections.count(ConnectionState::Active) > 0 {
+                sleep(Duration::from_nanos(
+                    self.state.refresh_rate.load(Ordering::SeqCst),
+                ))
+                .await;
+            }
+        })
+        .await
+        .is_ok();
+        if !drained {
+            // the listener has to be closed even if the grace period is over
+            self.shutdown().await;
+            warn!(
+                self.state.name,
+                "Not all requests were answered within the grace period of {grace_period:?}."
+            );
+        }
+        drained
+    }
+
+    /// Check whether the HttpServer is still accepting connections. \
+    /// This is not the case after a [`shutdown`](Self::shutdown), or if the task accepting connections stopped on its own.
+    pub fn is_running(&self) -> bool {
+        !self.state.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Get the address the internal TcpListener is bound to. Unlike the address given to [`bind`](HttpServer::bind), this contains the
+    /// actual port if port `0` was used.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.state.local_addr
+    }
+
+    /// Wait until the HttpServer stopped accepting connections, whether because of a [`shutdown`](Self::shutdown) from any clone of this
+    /// handle, or because the task accepting connections stopped on its own.
+    pub async fn await_stopped(&self) {
+        loop {
+            // created before checking the flag, so a notification in between does not get lost
+            let notified = self.state.stopped_notify.notified();
+            if self.state.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Get the number of open connections. See [`HttpServer::open_connections`].
+    pub fn open_connections(&self) -> usize {
+        self.state.connections.open.load(Ordering::SeqCst)
+    }
+
+    /// Get a snapshot of every open connection. See [`HttpServer::connections`].
+    pub fn connections(&self) -> Vec<ConnectionSnapshot> {
+        self.state.connections.snapshots()
+    }
+
+    /// Close the open connections of the client with the given address. See [`HttpServer::close_connection`].
+    pub fn close_connection(&self, peer_addr: SocketAddr) -> bool {
+        self.state.connections.close(peer_addr, &self.state.name)
+    }
+
+    /// Get the number of requests received since the HttpServer started serving. See [`HttpServer::total_requests`].
+    pub fn total_requests(&self) -> u64 {
+        self.state.connections.total_requests.load(Ordering::SeqCst)
+    }
+
+    /// Get the number of times the listener was replaced since the HttpServer started serving. See [`HttpServer::restarts`].
+    pub fn restarts(&self) -> u32 {
+        self.state.restarts.load(Ordering::SeqCst)
+    }
+}
+impl fmt::Debug for ServeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServeHandle")
+            .field("local_addr", &self.state.local_addr)
+            .field("running", &self.is_running())
+            .finish()
+    }
+}
+
+/// Decides what an [`HttpServer`] does if an incoming connection could not be accepted. See
+/// [`accept_error_policy`](HttpServer::accept_error_policy).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AcceptErrorPolicy {
+    /// Log the error and keep accepting connections. Most errors, like a client resetting its connection before it got accepted, only
+    /// concern a single connection.
+    #[default]
+    Ignore,
+    /// Log the error and stop accepting connections. [`join`](HttpServer::join) returns the error, so the HttpServer can be restarted,
+    /// e.g. after the network interface of a device went down and left the listener unusable.
+    Stop,
+}
+
+/// The default of [`HttpServer::keep_alive_timeout`].
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The default of [`HttpServer::max_header_value_bytes`].
+const DEFAULT_MAX_HEADER_VALUE_BYTES: usize = 8 * 1024;
+/// The default of [`HttpServer::max_request_headers`].
+const DEFAULT_MAX_REQUEST_HEADERS: usize = 100;
+/// The number of bytes a header line may have in addition to [`HttpServer::max_header_value_bytes`] for its name.
+const MAX_HEADER_NAME_BYTES: u64 = 256;
+/// The number of bytes the line in front of a chunk of a chunked request body may have, including chunk extensions.
+const MAX_CHUNK_SIZE_LINE_BYTES: u64 = 1024;
+/// The number of bytes of a request body the route did not read that get skipped to keep the connection open.
+const MAX_DRAINED_BODY_BYTES: usize = 64 * 1024;
+/// The start of the body axum's extractors answer requests exceeding their [`DefaultBodyLimit`] with.
+const BODY_LIMIT_REJECTION: &[u8] = b"Failed to buffer the request body: length limit exceeded";
+
+/// Decides how an [`HttpServer`] replaces its listener after a fault. See [`auto_restart`](HttpServer::auto_restart).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// The time to wait before binding a new listener. This gives the network interface a chance to come back up.
+    pub backoff: Duration,
+    /// The number of restarts after which the HttpServer gives up and stops.
+    pub max_restarts: u32,
+}
+
+/// A source of connections for an [`HttpServer`], which allows to serve other transports than TCP with
+/// [`serve_on`](HttpServer::serve_on). \
+/// It is implemented for the [`TcpListener`] of the standard library, which [`serve`](HttpServer::serve) uses. The in-memory
+/// [`MemoryListener`](crate::testing::MemoryListener) is another implementation.
+pub trait Listener: Send + 'static {
+    /// Accept the next incoming connection and return it together with the address of the client. \
+    /// This must not block, since a [`shutdown`](HttpServer::shutdown) could not interrupt it. If no connection is waiting, an error of
+    /// kind [`ErrorKind::WouldBlock`] has to be returned, after which the HttpServer tries again once its
+    /// [`refresh_rate`](HttpServer::refresh_rate) is over. Any other error is handled as described by
+    /// [`accept_error_policy`](HttpServer::accept_error_policy).
+    fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)>;
+
+    /// Get the address this listener accepts connections on.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+impl Listener for TcpListener {
+    fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)> {
+        let (client, client_addr) = TcpListener::accept(self)?;
+        // depending on the platform, the client inherits the non-blocking mode of the listener
+        client.set_nonblocking(false)?;
+        Ok((Box::new(client), client_addr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        TcpListener::local_addr(self)
+    }
+}
+
+/// Accepts the connections of several TcpListeners, like the sockets passed by systemd.
+#[cfg(all(unix, not(target_os = "espidf")))]
+struct MultiListener {
+    /// The listeners, which are all in non-blocking mode.
+    listeners: Vec<TcpListener>,
+    /// The index of the listener asked first by the next accept, so a busy listener cannot starve the others.
+    next: AtomicUsize,
+}
+#[cfg(all(unix, not(target_os = "espidf")))]
+impl Listener for MultiListener {
+    fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)> {
+        let first = self.next.fetch_add(1, Ordering::Relaxed);
+        for index in 0..self.listeners.len() {
+            let listener = &self.listeners[(first + index) % self.listeners.len()];
+            match Listener::accept(listener) {
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+        }
+        Err(ErrorKind::WouldBlock.into())
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listeners[0].local_addr()
+    }
+}
+
+/// A connection accepted by a [`Listener`]. \
+/// Requests get read from it with blocking calls, so reads should wait until data arrives or the client closes the connection.
+pub trait Stream: Read + Write + Send {
+    /// Limit the time a read waits for data, or remove the limit with `None`. A read running into the limit has to fail with an error of
+    /// kind [`ErrorKind::WouldBlock`] or [`ErrorKind::TimedOut`]. \
+    /// This is used for the [`keep_alive_timeout`](HttpServer::keep_alive_timeout). By default, the limit gets ignored, so idle
+    /// connections stay open until the client closes them.
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        let _ = timeout;
+        Ok(())
+    }
+
+    /// Get a function closing this connection from another thread, which has to make a read blocking on it return. \
+    /// This is used by [`close_connection`](HttpServer::close_connection) and the [`idle_eviction`](HttpServer::idle_eviction). By
+    /// default, `None` is returned, so the connection cannot be closed before its client closes it.
+    fn closer(&self) -> Option<Closer> {
+        None
+    }
+
+    /// Check without blocking whether the client closed this connection. \
+    /// This is used by [`cancel_on_disconnect`](HttpServer::cancel_on_disconnect). By default, `false` is returned, so the connection
+    /// never counts as closed.
+    fn peer_closed(&self) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Set how long closing this connection waits for data not yet sent to the client, like the `SO_LINGER` option of a socket. \
+    /// This is used by [`linger`](HttpServer::linger). By default, the option gets ignored.
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        let _ = linger;
+        Ok(())
+    }
+}
+impl Stream for TcpStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn closer(&self) -> Option<Closer> {
+        let stream = self.try_clone().ok()?;
+        Some(Box::new(move || {
+            // the connection might already be closed by the client
+            let _ = stream.shutdown(Shutdown::Both);
+        }))
+    }
+
+    fn peer_closed(&self) -> io::Result<bool> {
+        self.set_nonblocking(true)?;
+        let peeked = self.peek(&mut [0]);
+        self.set_nonblocking(false)?;
+        match peeked {
+            Ok(read) => Ok(read == 0),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+                ) =>
+            {
+                Ok(true)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    #[cfg(unix)]
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        socket2::SockRef::from(self).set_linger(linger)
+    }
+}
+
+/// A function closing a connection from another thread, as returned by [`Stream::closer`].
+pub type Closer = Box<dyn Fn() + Send + Sync>;
+
+/// Details about a [`Request`] and the connection it was sent over, which the [`HttpServer`] adds to the extensions of every request. \
+/// Routes can get it with either the `Extension<ConnectionInfo>` or the [`ConnInfo`] extractor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The address of the client.
+    pub peer_addr: SocketAddr,
+    /// The request-target exactly as the client sent it in the request line.
+    pub raw_target: String,
+    /// The number of requests answered on this connection before this one.
+    pub requests_served: u64,
+    /// Whether the connection is encrypted with TLS.
+    pub tls: bool,
+    /// The HTTP version of the request.
+    pub version: Version,
+}
+
+/// Extracts the [`ConnectionInfo`] of a request.
+///
+/// ```
+/// use goohttp::http_server::ConnInfo;
+///
+/// async fn whoami(ConnInfo(info): ConnInfo) -> String {
+///     format!("{} ({:?})", info.peer_addr, info.version)
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConnInfo(pub ConnectionInfo);
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ConnInfo {
+    type Rejection = MissingConnectionInfo;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ConnectionInfo>()
+            .cloned()
+            .map(ConnInfo)
+            .ok_or(MissingConnectionInfo)
+    }
+}
+
+/// Rejection of the [`ConnInfo`] extractor for requests which were not received by an [`HttpServer`]. \
+/// It gets answered with `500 Internal Server Error`.
+#[derive(Clone, Copy, Debug)]
+pub struct MissingConnectionInfo;
+impl IntoResponse for MissingConnectionInfo {
+    fn into_response(self) -> axum::response::Response {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "The request was not received by a goohttp HttpServer.",
+        )
+            .into_response()
+    }
+}
+
+/// The trailers of a request sent with `Transfer-Encoding: chunked`, which the [`HttpServer`] adds to the extensions of such requests. \
+/// Trailers follow the last chunk of the body, so they are only known once the route read the whole body. Routes can get them with the
+/// `Extension<Trailers>` extractor.
+///
+/// ```
+/// use goohttp::{
+///     axum::{
+///         body::Bytes,
+///         Extension,
+///     },
+///     http_server::Trailers,
+/// };
+///
+/// async fn upload(Extension(trailers): Extension<Trailers>, body: Bytes) -> String {
+///     let checksum = trailers
+///         .get()
+///         .and_then(|trailers| trailers.get("x-checksum").cloned());
+///     format!("{} bytes, checksum {checksum:?}", body.len())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Trailers(Arc<Mutex<Option<HeaderMap>>>);
+impl Trailers {
+    /// Get the trailers of the request. `None` is returned if the body has not been read completely yet. A client sending no trailers
+    /// results in an empty [`HeaderMap`].
+    pub fn get(&self) -> Option<HeaderMap> {
+        self.0.lock().ok().and_then(|trailers| trailers.clone())
+    }
+}
+
+/// Informational `103 Early Hints` responses, which the [`HttpServer`] sends ahead of the response they were added to as an extension.
+/// Every [`HeaderMap`] becomes one of them. \
+/// A browser can use them to preload the resources of a page, like its stylesheets, while the route is still answering the request.
+/// HTTP/1.0 clients do not know informational responses, so they only get the final response.
+///
+/// ```
+/// use goohttp::{
+///     axum::{
+///         http::{
+///             header::LINK,
+///             HeaderMap,
+///         },
+///         response::{
+///             Html,
+///             IntoResponse,
+///         },
+///     },
+///     http_server::EarlyHints,
+/// };
+///
+/// async fn index() -> impl IntoResponse {
+///     let mut hints = HeaderMap::new();
+///     hints.insert(LINK, "</style.css>; rel=preload; as=style".parse().unwrap());
+///     let mut response = Html("<link rel=\"stylesheet\" href=\"/style.css\">").into_response();
+///     response.extensions_mut().insert(EarlyHints(vec![hints]));
+///     response
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EarlyHints(pub Vec<HeaderMap>);
+
+/// Describes settings of an [`HttpServer`] which contradict each other, as found by [`HttpServer::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The refresh rate is zero, which would keep the task accepting connections busy all the time.
+    ZeroRefreshRate,
+    /// The queue depth is zero, so no request could ever be answered.
+    ZeroQueueDepth,
+    /// The [`dedicated_runtime`](HttpServer::dedicated_runtime) has no worker threads, so no task could ever run on it.
+    ZeroWorkerThreads,
+    /// The 404 page is larger than the limit for response bodies.
+    NotFoundPageTooLarge {
+        /// The size of the 404 page in bytes.
+        page: usize,
+        /// The limit for the size of response bodies in bytes.
+        limit: usize,
+    },
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroRefreshRate => write!(
+                f,
+                "The refresh rate must not be zero, since the task accepting connections would never give other tasks a chance to run."
+            ),
+            Self::ZeroQueueDepth => write!(
+                f,
+                "The queue depth must not be zero, since no request could ever be answered."
+            ),
+            Self::ZeroWorkerThreads => write!(
+                f,
+                "The dedicated runtime needs at least one worker thread, since no task could ever run on it otherwise."
+            ),
+            Self::NotFoundPageTooLarge { page, limit } => write!(
+                f,
+                "The 404 page has {page} bytes, which exceeds the limit of {limit} bytes for response bodies."
+            ),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+/// A function answering a [`Request`] in the low-level mode of [`HttpServer::serve_dispatch`].
+pub type RequestHandler = fn(Request<Body>) -> Response<Body>;
+
+/// A function picking the [`RequestHandler`] for the method and path of a [`Request`] in the low-level mode of
+/// [`HttpServer::serve_dispatch`]. Returning `None` answers the request with `404 Not Found`.
+pub type Dispatch = fn(&Method, &str) -> Option<RequestHandler>;
+
+/// Answers requests with the [`RequestHandler`] its [`Dispatch`] function picks.
+#[derive(Clone, Copy)]
+struct DispatchService(Dispatch);
+impl Service<Request<Body>> for DispatchService {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let response = match (self.0)(request.method(), request.uri().path()) {
+            Some(request_handler) => request_handler(request),
+            None => {
+                let mut response = Response::new(Body::empty());
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                response
+            }
+        };
+        ready(Ok(response))
+    }
+}
+
+/// The response to a request, coming either from the router or from a [fast path](HttpServer::fast_path).
+enum Answer<B> {
+    /// The response of the router.
+    Routed(Response<B>),
+    /// The response of the handler of a fast path.
+    FastPath(Response<Body>),
+}
+
+/// A function called with the result of a connection. See [`HttpServer::on_connection_result`].
+type ConnectionResultHook = Arc<dyn Fn(&Result<(), Error>) + Send + Sync>;
+
+/// A function called with the result of the task accepting connections. See [`HttpServer::on_stop`].
+type StopHook = Arc<dyn Fn(&Result<(), Error>) + Send + Sync>;
+
+/// A function building the error responses sent without the router. See [`HttpServer::error_responder`].
+type ErrorResponder = Arc<dyn Fn(StatusCode) -> Response<Vec<u8>> + Send + Sync>;
+
+/// The serialized error responses an [`HttpServer`] sends on its own. See [`HttpServer::error_responder`].
+struct CannedResponses {
+    /// The `400 Bad Request` response.
+    bad_request: CannedResponse,
+    /// The `431 Request Header Fields Too Large` response.
+    header_fields_too_large: CannedResponse,
+    /// The `500 Internal Server Error` response.
+    internal_server_error: CannedResponse,
+    /// The `413 Payload Too Large` response.
+    payload_too_large: CannedResponse,
+    /// The `408 Request Timeout` response.
+    request_timeout: CannedResponse,
+    /// The `503 Service Unavailable` response.
+    service_unavailable: CannedResponse,
+    /// The `414 URI Too Long` response.
+    uri_too_long: CannedResponse,
+}
+impl CannedResponses {
+    /// Build and serialize the error responses with the given responder, or with an empty body without one.
+    fn new(responder: Option<&ErrorResponder>, name: &str) -> Self {
+        let canned = |status: StatusCode| {
+            let mut response =
+                responder.map_or_else(|| Response::new(vec![]), |responder| responder(status));
+            *response.status_mut() = status;
+            *response.version_mut() = Version::HTTP_11;
+            let body_length = response.body().len();
+            let headers = response.headers_mut();
+            // like the headers of routes, the ones of the responder must not be able to end their line early
+            sanitize_headers(headers, name);
+            headers.remove(TRANSFER_ENCODING);
+            headers.insert(CONTENT_LENGTH, body_length.into());
+            headers.insert(CONNECTION, HeaderValue::from_static("close"));
+            let bytes = response_to_bytes(response, None)
+                .expect("The status codes of the canned responses have a canonical reason.")
+                .to_vec()
+                .into_boxed_slice();
+            CannedResponse { bytes, body_length }
+        };
+        Self {
+            bad_request: canned(StatusCode::BAD_REQUEST),
+            header_fields_too_large: canned(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE),
+            internal_server_error: canned(StatusCode::INTERNAL_SERVER_ERROR),
+            payload_too_large: canned(StatusCode::PAYLOAD_TOO_LARGE),
+            request_timeout: canned(StatusCode::REQUEST_TIMEOUT),
+            service_unavailable: canned(StatusCode::SERVICE_UNAVAILABLE),
+            uri_too_long: canned(StatusCode::URI_TOO_LONG),
+        }
+    }
+
+    /// Get the response to a request that could not be read because of the given error, or `None` if the connection should just be
+    /// closed.
+    fn for_read_error(&self, error: &Error) -> Option<&CannedResponse> {
+        match error {
+            Error::Parse(ParseError::HeaderTooLarge | ParseError::TooManyHeaders) => {
+                Some(&self.header_fields_too_large)
+            }
+            Error::Parse(ParseError::UriTooLong) => Some(&self.uri_too_long),
+            Error::Parse(_) => Some(&self.bad_request),
+            Error::Io(error) if error.kind() == ErrorKind::InvalidData => Some(&self.bad_request),
+            Error::Io(error)
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                Some(&self.request_timeout)
+            }
+            // the client closed the connection, or cannot be written to anymore
+            _ => None,
+        }
+    }
+}
+
+/// An error response serialized by [`CannedResponses`].
+struct CannedResponse {
+    /// The whole response, including its head.
+    bytes: Box<[u8]>,
+    /// The size of the body of the response.
+    body_length: usize,
+}
+
+/// The state of a serving session, shared between its [`ServeHandle`]s and the task accepting connections.
+struct ServeState {
+    /// The open connections and the number of requests they sent.
+    connections: Arc<Connections>,
+    /// The address the TcpListener is bound to.
+    local_addr: SocketAddr,
+    /// The name of the HttpServer, which gets used in log messages.
+    name: String,
+    /// The time in nanoseconds the HttpServer sleeps between two [accept()](TcpListener::accept) calls.
+    refresh_rate: Arc<AtomicU64>,
+    /// The number of times the listener was replaced after a fault.
+    restarts: AtomicU32,
+    /// Whether the task accepting connections should keep running.
+    running: AtomicBool,
+    /// Whether the task accepting connections has ended.
+    stopped: AtomicBool,
+    /// Gets notified once the task accepting connections has ended.
+    stopped_notify: Notify,
+}
+
+/// Marks a serving session as stopped once it gets dropped together with the task accepting connections.
+struct StoppedGuard(Arc<ServeState>);
+impl Drop for StoppedGuard {
+    fn drop(&mut self) {
+        self.0.stopped.store(true, Ordering::SeqCst);
+        self.0.stopped_notify.notify_waiters();
+    }
+}
+
+/// The settings of an [`HttpServer`] needed to answer requests.
+struct HandlerConfig {
+    /// See [`HttpServer::response_buffer_pool`].
+    buffer_pool: Option<Arc<BufferPool>>,
+    /// See [`HttpServer::error_responder`].
+    canned_responses: CannedResponses,
+    /// See [`HttpServer::cancel_on_disconnect`].
+    disconnect_check: Option<Duration>,
+    /// See [`HttpServer::fast_path`].
+    fast_paths: BTreeMap<String, RequestHandler>,
+    /// See [`HttpServer::keep_alive_timeout`].
+    keep_alive_timeout: Duration,
+    /// See [`HttpServer::linger`].
+    linger: Option<Duration>,
+    /// See [`HttpServer::log_unmatched_routes`].
+    log_unmatched_routes: bool,
+    /// See [`HttpServer::max_header_value_bytes`].
+    max_header_value_bytes: usize,
+    /// See [`HttpServer::max_request_headers`].
+    max_request_headers: usize,
+    /// See [`HttpServer::max_requests_per_connection`].
+    max_requests_per_connection: Option<u64>,
+    /// See [`HttpServer::max_response_body`].
+    max_response_body: Option<usize>,
+    /// See [`HttpServer::metrics_backend`].
+    metrics: Arc<dyn MetricsBackend>,
+    /// The name of the [`HttpServer`], which gets used in log messages.
+    name: String,
+    /// See [`HttpServer::not_found_page`].
+    not_found_page: Option<(Vec<u8>, HeaderValue)>,
+    /// See [`HttpServer::on_connection_result`].
+    on_connection_result: Option<ConnectionResultHook>,
+    /// See [`HttpServer::proxy_protocol`].
+    proxy_protocol: bool,
+    /// The places of the requests waiting for a permit. See [`HttpServer::queue_depth`].
+    queue_slots: Option<Arc<Semaphore>>,
+    /// The permits of the requests being answered. See [`HttpServer::queue_depth`].
+    request_permits: Option<Arc<Semaphore>>,
+    /// See [`HttpServer::route_table`].
+    route_table: Option<RouteTable>,
+    /// Whether connections speak the binary protocol of the [`RpcServer`](crate::rpc_server::RpcServer) instead of HTTP.
+    rpc_frames: bool,
+    /// See [`HttpServer::truncate_large_responses`].
+    truncate_large_responses: bool,
+    /// See [`HttpServer::yield_between_requests`].
+    yield_between_requests: bool,
+}
+
+/// The state of a connection to the [`HttpServer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The connection is waiting for the next request.
+    Idle,
+    /// A request is being read or answered.
+    Active,
+}
+
+/// An open connection of the [`HttpServer`] at the time of a snapshot, as returned by [`connections`](HttpServer::connections).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionSnapshot {
+    /// The address of the client. With the [PROXY protocol](HttpServer::proxy_protocol), this is the address it forwarded.
+    pub peer_addr: SocketAddr,
+    /// The time the connection was accepted.
+    pub connected_at: Instant,
+    /// Whether the connection is waiting for its next request or answering one.
+    pub state: ConnectionState,
+    /// The time the connection entered its current state.
+    pub state_since: Instant,
+    /// The number of requests received over the connection, including the one being answered.
+    pub requests: u64,
+    /// The number of bytes received from the client.
+    pub bytes_received: u64,
+    /// The number of bytes sent to the client.
+    pub bytes_sent: u64,
+}
+
+/// The open connections of an [`HttpServer`] and the number of requests they sent.
+#[derive(Default)]
+struct Connections {
+    /// Every open connection, stored under the id it got when it was accepted.
+    entries: Mutex<BTreeMap<u64, ConnectionEntry>>,
+    /// The id the next connection gets.
+    next_id: AtomicU64,
+    /// The number of open connections.
+    open: AtomicUsize,
+    /// The number of requests received since the HttpServer last started serving.
+    total_requests: AtomicU64,
+}
+impl Connections {
+    /// Lock the open connections. A panic while they were locked cannot leave an entry half-changed, so poisoning gets ignored.
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<u64, ConnectionEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    /// Count the open connections in the given state.
+    fn count(&self, state: ConnectionState) -> usize {
+        self.lock()
+            .values()
+            .filter(|entry| entry.state == state)
+            .count()
+    }
+    /// Take a snapshot of every open connection, in the order they were accepted.
+    fn snapshots(&self) -> Vec<ConnectionSnapshot> {
+        self.lock()
+            .values()
+            .map(|entry| ConnectionSnapshot {
+                peer_addr: entry.peer_addr,
+                connected_at: entry.connected_at,
+                state: entry.state,
+                state_since: entry.state_since,
+                requests: entry.requests,
+                bytes_received: entry.traffic.received.load(Ordering::SeqCst),
+                bytes_sent: entry.traffic.sent.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+    /// Close the open connections of the client with the given address. `false` is returned if none of them could be closed.
+    // `name` is only used by `debug!`, which is left out of release builds
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn close(&self, peer_addr: SocketAddr, name: &str) -> bool {
+        let mut closed = false;
+        for entry in self.lock().values_mut() {
+            if entry.peer_addr == peer_addr && entry.close() {
+                debug!(
+                    name,
+                    "The connection of `{peer_addr}` was closed on request."
+                );
+                closed = true;
+            }
+        }
+        closed
+    }
+    /// Close the connections that waited longer than the given limit for their next request.
+    // `name` is only used by `debug!`, which is left out of release builds
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn evict_idle(&self, limit: Duration, name: &str) {
+        for entry in self.lock().values_mut() {
+            let idle = entry.state_since.elapsed();
+            if entry.state == ConnectionState::Idle && idle > limit && entry.close() {
+                debug!(
+                    name,
+                    "The connection of `{}` was closed, since it was idle for {idle:?}.",
+                    entry.peer_addr
+                );
+            }
+        }
+    }
+}
+
+/// An open connection in the [`Connections`] of the [`HttpServer`].
+struct ConnectionEntry {
+    /// See [`ConnectionSnapshot::peer_addr`].
+    peer_addr: SocketAddr,
+    /// See [`ConnectionSnapshot::connected_at`].
+    connected_at: Instant,
+    /// See [`ConnectionSnapshot::state`].
+    state: ConnectionState,
+    /// See [`ConnectionSnapshot::state_since`].
+    state_since: Instant,
+    /// See [`ConnectionSnapshot::requests`].
+    requests: u64,
+    /// The bytes received and sent, which get counted without locking the connections.
+    traffic: Arc<Traffic>,
+    /// Closes the connection, if its [`Stream`] supports it. It gets removed once it was called, so the connection only gets closed once.
+    closer: Option<Closer>,
+}
+impl ConnectionEntry {
+    /// Close this connection. `false` is returned if it cannot be closed, or already was.
+    fn close(&mut self) -> bool {
+        match self.closer.take() {
+            Some(closer) => {
+                closer();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The number of bytes received from and sent to a client.
+#[derive(Default)]
+struct Traffic {
+    /// The number of bytes received from the client.
+    received: AtomicU64,
+    /// The number of bytes sent to the client.
+    sent: AtomicU64,
+}
+
+/// Counts the bytes read from and written to a connection in its [`Traffic`].
+struct CountingStream {
+    /// The connection.
+    inner: Box<dyn Stream>,
+    /// The counted bytes.
+    traffic: Arc<Traffic>,
+}
+impl Read for CountingStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.traffic
+            .received
+            .fetch_add(read as u64, Ordering::SeqCst);
+        Ok(read)
+    }
+}
+impl Write for CountingStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.traffic
+            .sent
+            .fetch_add(written as u64, Ordering::SeqCst);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl Stream for CountingStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+
+    fn closer(&self) -> Option<Closer> {
+        self.inner.closer()
+    }
+
+    fn peer_closed(&self) -> io::Result<bool> {
+        self.inner.peer_closed()
+    }
+
+    fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        self.inner.set_linger(linger)
+    }
+}
+
+/// Tracks a connection in the [`Connections`] of the [`HttpServer`] and removes it from there once the connection gets dropped, however
+/// it ends.
+struct ConnectionGuard {
+    /// The id the connection is stored under.
+    id: u64,
+    /// The open connections.
+    connections: Arc<Connections>,
+    /// The bytes received and sent over the connection.
+    traffic: Arc<Traffic>,
+}
+impl ConnectionGuard {
+    /// Add the given new, idle connection of the client with the given address to the given connections.
+    fn new(connections: &Arc<Connections>, peer_addr: SocketAddr, client: &dyn Stream) -> Self {
+        let connection = Self {
+            id: connections.next_id.fetch_add(1, Ordering::SeqCst),
+            connections: connections.clone(),
+            traffic: Arc::default(),
+        };
+        let now = Instant::now();
+        connections.lock().insert(
+            connection.id,
+            ConnectionEntry {
+                peer_addr,
+                connected_at: now,
+                state: ConnectionState::Idle,
+                state_since: now,
+                requests: 0,
+                traffic: connection.traffic.clone(),
+                closer: client.closer(),
+            },
+        );
+        connections.open.fetch_add(1, Ordering::SeqCst);
+        connection
+    }
+    /// Wrap the given stream of this connection, so the bytes read from and written to it get counted.
+    fn count_traffic(&self, client: Box<dyn Stream>) -> Box<dyn Stream> {
+        Box::new(CountingStream {
+            inner: client,
+            traffic: self.traffic.clone(),
+        })
+    }
+    /// Mark this connection as active and count the request it received.
+    fn start_request(&self) {
+        self.connections
+            .total_requests
+            .fetch_add(1, Ordering::SeqCst);
+        if let Some(entry) = self.connections.lock().get_mut(&self.id) {
+            entry.requests += 1;
+        }
+        self.set_state(ConnectionState::Active);
+    }
+    /// Update the state of this connection.
+    fn set_state(&self, state: ConnectionState) {
+        if let Some(entry) = self.connections.lock().get_mut(&self.id) {
+            if entry.state != state {
+                entry.state = state;
+                entry.state_since = Instant::now();
+            }
+        }
+    }
+    /// Update the address of the client of this connection, e.g. to the one forwarded by a proxy.
+    fn set_peer_addr(&self, peer_addr: SocketAddr) {
+        if let Some(entry) = self.connections.lock().get_mut(&self.id) {
+            entry.peer_addr = peer_addr;
+        }
+    }
+}
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.connections.lock().remove(&self.id);
+        self.connections.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Read the PROXY protocol header at the start of a connection and return the address of the client it contains. `None` is returned for
+/// headers not forwarding a connection, like the health checks of a load balancer.
+fn read_proxy_header(reader: &mut impl BufRead) -> Result<Option<SocketAddr>, Error> {
+    /// The signature every header of version 2 starts with.
+    const SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+    /// The longest header of version 1, including its line break.
+    const MAX_V1_LENGTH: u64 = 107;
+
+    /// Read exactly enough bytes to fill the given buffer. A connection closing early counts as a malformed header.
+    fn read_exact(reader: &mut impl BufRead, buffer: &mut [u8]) -> Result<(), Error> {
+        reader.read_exact(buffer).map_err(|error| {
+            if error.kind() == ErrorKind::UnexpectedEof {
+                ParseError::ProxyHeader.into()
+            } else {
+                Error::Io(error)
+            }
+        })
+    }
+    /// Parse the rest of a header of version 1 after its `PROXY ` prefix.
+    fn parse_v1(line: &str) -> Option<Option<SocketAddr>> {
+        let mut fields = line.strip_suffix("\r\n")?.split(' ');
+        let family = fields.next()?;
+        if family == "UNKNOWN" {
+            return Some(None);
+        }
+        let (Some(source), Some(_), Some(source_port), Some(_), None) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        ) else {
+            return None;
+        };
+        let source: IpAddr = match family {
+            "TCP4" => source.parse::<Ipv4Addr>().ok()?.into(),
+            "TCP6" => source.parse::<Ipv6Addr>().ok()?.into(),
+            _ => return None,
+        };
+        Some(Some(SocketAddr::new(source, source_port.parse().ok()?)))
+    }
+
+    let mut prefix = [0; 6];
+    read_exact(reader, &mut prefix)?;
+    if &prefix == b"PROXY " {
+        let mut line = vec![];
+        reader
+            .take(MAX_V1_LENGTH - prefix.len() as u64)
+            .read_until(b'\n', &mut line)?;
+        let line = String::from_utf8(line).map_err(|_| ParseError::ProxyHeader)?;
+        return parse_v1(&line).ok_or(ParseError::ProxyHeader.into());
+    }
+
+    let mut signature = [0; 12];
+    signature[..6].copy_from_slice(&prefix);
+    read_exact(reader, &mut signature[6..])?;
+    if &signature != SIGNATURE {
+        return Err(ParseError::ProxyHeader.into());
+    }
+    let mut header = [0; 4];
+    read_exact(reader, &mut header)?;
+    let [version_command, family, length @ ..] = header;
+    let mut addresses = vec![0; u16::from_be_bytes(length) as usize];
+    read_exact(reader, &mut addresses)?;
+    if version_command >> 4 != 2 {
+        return Err(ParseError::ProxyHeader.into());
+    }
+    match (version_command & 0xf, family >> 4) {
+        // a `LOCAL` connection of the load balancer itself, or an address family other than IPv4 and IPv6
+        (0, _) | (1, 0 | 3) => Ok(None),
+        (1, 1) if addresses.len() >= 12 => {
+            let source: [u8; 4] = addresses[..4]
+                .try_into()
+                .expect("The slice has a length of 4.");
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Ok(Some(SocketAddr::new(Ipv4Addr::from(source).into(), port)))
+        }
+        (1, 2) if addresses.len() >= 36 => {
+            let source: [u8; 16] = addresses[..16]
+                .try_into()
+                .expect("The slice has a length of 16.");
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(source).into(), port)))
+        }
+        _ => Err(ParseError::ProxyHeader.into()),
+    }
+}
+
+/// Bind a TcpListener to the given address. With `reuse_port`, the `SO_REUSEPORT` option gets set before binding, so other sockets can
+/// bind the same port.
+fn bind_tcp_listener(addr: SocketAddr, reuse_port: bool) -> io::Result<TcpListener> {
+    #[cfg(all(unix, not(target_os = "espidf")))]
+    if reuse_port {
+        use socket2::{
+            Domain,
+            Protocol,
+            Socket,
+            Type,
+        };
+
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        // like the standard library does for its listeners
+        socket.set_reuse_address(true)?;
+        socket.set_reuse_port(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(128)?;
+        return Ok(socket.into());
+    }
+    #[cfg(not(all(unix, not(target_os = "espidf"))))]
+    let _ = reuse_port;
+    TcpListener::bind(addr)
+}
+
+/// Take over the sockets passed by systemd, as described in [`HttpServer::from_systemd`].
+#[cfg(all(unix, not(target_os = "espidf")))]
+fn activated_listeners() -> Result<Vec<TcpListener>, crate::error::ActivationError> {
+    use std::{
+        env,
+        os::fd::{
+            BorrowedFd,
+            FromRawFd,
+            RawFd,
+        },
+        process,
+    };
+
+    use socket2::{
+        SockRef,
+        Type,
+    };
+
+    use crate::error::ActivationError;
+
+    /// The first file descriptor passed by systemd, since `0` to `2` are the standard streams.
+    const LISTEN_FDS_START: RawFd = 3;
+    /// Whether the sockets were already taken over, which must only happen once, since they would get closed twice otherwise.
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+
+    let (Ok(pid), Ok(fds)) = (env::var("LISTEN_PID"), env::var("LISTEN_FDS")) else {
+        return Err(ActivationError::NotActivated);
+    };
+    let pid: u32 = pid
+        .parse()
+        .map_err(|_| ActivationError::InvalidVariable("LISTEN_PID"))?;
+    if pid != process::id() {
+        return Err(ActivationError::OtherProcess(pid));
+    }
+    let fds: RawFd = fds
+        .parse()
+        .map_err(|_| ActivationError::InvalidVariable("LISTEN_FDS"))?;
+    if fds <= 0 {
+        return Err(ActivationError::NoSockets);
+    }
+    if TAKEN.load(Ordering::SeqCst) {
+        return Err(ActivationError::AlreadyTaken);
+    }
+
+    let fds = LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(fds);
+    for fd in fds.clone() {
+        // SAFETY: the file descriptor only gets borrowed for the checks, which fail if it is not open
+        let socket = unsafe { BorrowedFd::borrow_raw(fd) };
+        let socket = SockRef::from(&socket);
+        let tcp = socket.r#type().is_ok_and(|kind| kind == Type::STREAM)
+            && socket
+                .local_addr()
+                .is_ok_and(|addr| addr.as_socket().is_some());
+        if !tcp || socket.set_cloexec(true).is_err() {
+            return Err(ActivationError::NotTcp(fd));
+        }
+    }
+    if TAKEN.swap(true, Ordering::SeqCst) {
+        return Err(ActivationError::AlreadyTaken);
+    }
+    Ok(fds
+        .map(|fd| {
+            // SAFETY: systemd passed the ownership of the file descriptor to this process, which only takes it over once
+            unsafe { TcpListener::from_raw_fd(fd) }
+        })
+        .collect())
+}
+
+/// Convert the given duration to nanoseconds, saturating at [`u64::MAX`], which is more than 500 years.
+fn duration_to_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Collect the body of the given [`Response`]. \
+/// Responses with bodies larger than the configured limit get replaced with an empty `500 Internal Server Error`, and empty
+/// `404 Not Found` responses get the configured 404 page. The latter get logged with the given method and path of their request,
+/// if any.
+async fn buffer_response<B>(
+    response: Response<B>,
+    unmatched_route: Option<&str>,
+    config: &HandlerConfig,
+) -> Result<Response<Vec<u8>>, Error>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    let name = &config.name;
+    let (mut parts, mut body_stream) = response.into_parts();
+
+    let max_response_body = config.max_response_body.unwrap_or(usize::MAX);
+    let mut body = vec![];
+    // the beginning of a truncated body has to be read even if its size is known to exceed the limit
+    let mut body_size = if config.truncate_large_responses {
+        0
+    } else {
+        body_stream.size_hint().lower() as usize
+    };
+    while body_size <= max_response_body {
+        match body_stream.data().await {
+            Some(Ok(chunk)) => {
+                body.extend_from_slice(&chunk);
+                body_size = body.len();
+            }
+            Some(Err(_)) => return Err(SerializeError::Body.into()),
+            None => break,
+        }
+    }
+    if body_size > max_response_body && config.truncate_large_responses {
+        warn!(
+            name,
+            "A route responded with a body of at least {body_size} bytes, which exceeds the limit of {max_response_body} bytes. It was truncated to the limit."
+        );
+        body.truncate(max_response_body);
+        parts.headers.append(
+            WARNING,
+            HeaderValue::from_static("214 - \"Transformation Applied\""),
+        );
+    } else if body_size > max_response_body {
+        error!(
+            name,
+            "A route responded with a body of at least {body_size} bytes, which exceeds the limit of {max_response_body} bytes. A `500 Internal Server Error` will be sent instead."
+        );
+        parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+        parts.headers.clear();
+        body.clear();
+    }
+    if !sanitize_headers(&mut parts.headers, name) {
+        parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+        parts.headers.clear();
+        body.clear();
+    }
+
+    if parts.status == StatusCode::NOT_FOUND && body.is_empty() {
+        if let Some(unmatched_route) = unmatched_route {
+            // unlike `debug!`, this is not left out of release builds, where `log_unmatched_routes` is needed the most
+            log::debug!(target: name, "No route matched the request `{unmatched_route}`.");
+        }
+        if let Some((not_found_body, content_type)) = &config.not_found_page {
+            parts.headers.insert(CONTENT_TYPE, content_type.clone());
+            body = not_found_body.clone();
+        }
+    }
+
+    // the client needs to know where this response ends to be able to read the next one
+    if !(parts.status.is_informational()
+        || parts.status == StatusCode::NO_CONTENT
+        || parts.status == StatusCode::NOT_MODIFIED)
+    {
+        parts.headers.insert(CONTENT_LENGTH, body.len().into());
+    }
+
+    Ok(Response::from_parts(parts, body))
+}
+
+/// Get the length of the body declared by the `Content-Length` header of a response. `None` is returned if there is no valid one.
+fn declared_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Convert a [`Response`] to a vec of bytes.
+fn response_to_bytes(
+    response: Response<Vec<u8>>,
+    buffer_pool: Option<&Arc<BufferPool>>,
+) -> Result<ResponseBuffer, SerializeError> {
+    let (parts, body) = response.into_parts();
+    let reason = parts
+        .status
+        .canonical_reason()
+        .ok_or(SerializeError::Status(parts.status.as_u16()))?;
+    let headers_length: usize = parts
+        .headers
+        .iter()
+        .map(|(header_name, header_value)| {
+            // `name: value\r\n`
+            header_name.as_str().len() + header_value.len() + 4
+        })
+        .sum();
+    // `HTTP/1.1 200 reason\r\n`, the headers, and the empty line before the body
+    let capacity = 15 + reason.len() + headers_length + 2 + body.len();
+    let mut http_response = BufferPool::take_from(buffer_pool, capacity);
+
+    // status line
+    match parts.version {
+        Version::HTTP_10 => http_response.extend_from_slice(b"HTTP/1.0"),
+        Version::HTTP_11 => http_response.extend_from_slice(b"HTTP/1.1"),
+        version => http_response.extend_from_slice(format!("{version:?}").as_bytes()),
+    }
+    http_response.push(b' ');
+    http_response.extend_from_slice(parts.status.as_str().as_bytes());
+    http_response.push(b' ');
+    http_response.extend_from_slice(reason.as_bytes());
+    http_response.extend_from_slice(b"\r\n");
+
+    // headers
+    for (header_name, header_value) in &parts.headers {
+        http_response.extend_from_slice(header_name.as_str().as_bytes());
+        http_response.extend_from_slice(b": ");
+        http_response.extend_from_slice(header_value.as_bytes());
+        http_response.extend_from_slice(b"\r\n");
+    }
+
+    // body
+    http_response.extend_from_slice(b"\r\n");
+    http_response.extend_from_slice(&body);
+
+    Ok(http_response)
+}
+
+/// Remove every header value containing bytes not allowed in it, like a line break, which would let the value end the header and
+/// start a new one. `HeaderValue` rejects these bytes, but a route could still create such a value with unsafe code, and sending it
+/// would allow a client to inject headers or a whole response. \
+/// Removed headers get logged. `false` is returned if the removed header is one of the [`CRITICAL_HEADERS`], in which case the
+/// response has to be replaced.
+fn sanitize_headers(headers: &mut HeaderMap, name: &str) -> bool {
+    /// Headers whose removal could change the meaning of the response.
+    const CRITICAL_HEADERS: [HeaderName; 2] = [LOCATION, SET_COOKIE];
+    /// Check if the given value only consists of visible characters, spaces and tabs.
+    fn is_valid(value: &HeaderValue) -> bool {
+        value
+            .as_bytes()
+            .iter()
+            .all(|&byte| byte >= b' ' && byte != 0x7f || byte == b'\t')
+    }
+
+    let mut invalid: Vec<HeaderName> = headers
+        .iter()
+        .filter(|(_, value)| !is_valid(value))
+        .map(|(header_name, _)| header_name.clone())
+        .collect();
+    // the values of a header follow each other
+    invalid.dedup();
+
+    let mut valid = true;
+    for header_name in invalid {
+        let values: Vec<HeaderValue> = headers
+            .get_all(&header_name)
+            .iter()
+            .filter(|value| is_valid(value))
+            .cloned()
+            .collect();
+        headers.remove(&header_name);
+        for value in values {
+            headers.append(&header_name, value);
+        }
+
+        if CRITICAL_HEADERS.contains(&header_name) {
+            error!(
+                name,
+                "A route responded with a `{header_name}` header containing characters not allowed in a header value, like a line break. A `500 Internal Server Error` will be sent instead."
+            );
+            valid = false;
+        } else {
+            warn!(
+                name,
+                "A route responded with a `{header_name}` header containing characters not allowed in a header value, like a line break. It was removed."
+            );
+        }
+    }
+    valid
+}
+
+/// Read a single line without its line break, but at most the given number of bytes. An empty string is returned if the reader reached
+/// its end, and `None` if the line is longer than the limit.
+fn read_line(reader: &mut impl BufRead, limit: u64) -> io::Result<Option<String>> {
+    let mut line = vec![];
+    // the line break does not count against the limit
+    reader
+        .take(limit.saturating_add(2))
+        .read_until(b'\n', &mut line)?;
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+    if line.len() as u64 > limit {
+        return Ok(None);
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| io::Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+}
+
+/// Read header lines from the given reader until an empty line, as sent after the request line or after the last chunk of a chunked
+/// body. \
+/// The lines get collected first, so the [`HeaderMap`] gets allocated only once.
+fn read_headers(
+    reader: &mut impl BufRead,
+    max_header_value_bytes: usize,
+    max_headers: usize,
+) -> Result<HeaderMap, Error> {
+    let max_line = (max_header_value_bytes as u64).saturating_add(MAX_HEADER_NAME_BYTES);
+    let mut header_lines = Vec::with_capacity(512);
+    let mut header_count = 0;
+    loop {
+        let start = header_lines.len();
+        // the line break does not count against the limit
+        reader
+            .take(max_line.saturating_add(2))
+            .read_until(b'\n', &mut header_lines)?;
+        let mut end = header_lines.len();
+        if header_lines[start..end].ends_with(b"\n") {
+            end -= 1;
+            if header_lines[start..end].ends_with(b"\r") {
+                end -= 1;
+            }
+        }
+        if (end - start) as u64 > max_line {
+            return Err(ParseError::HeaderTooLarge.into());
+        }
+        header_lines.truncate(end);
+        if start == end {
+            break;
+        }
+        header_count += 1;
+        if header_count > max_headers {
+            return Err(ParseError::TooManyHeaders.into());
+        }
+        header_lines.push(b'\n');
+    }
+
+    let mut headers = HeaderMap::with_capacity(header_count);
+    for line in header_lines.split(|byte| *byte == b'\n') {
+        if line.is_empty() {
+            continue;
+        }
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+            .into());
+        };
+        let Some((header_name, header_value)) = line.split_once(':') else {
+            return Err(ParseError::Header.into());
+        };
+        if header_value.trim().len() > max_header_value_bytes {
+            return Err(ParseError::HeaderTooLarge.into());
+        }
+        let (Ok(header_name), Ok(header_value)) = (
+            HeaderName::from_bytes(header_name.as_bytes()),
+            HeaderValue::from_str(header_value.trim()),
+        ) else {
+            return Err(ParseError::Header.into());
+        };
+        headers.append(header_name, header_value);
+    }
+    Ok(headers)
+}
+
+/// Answer the given request with the given service. With [`HttpServer::cancel_on_disconnect`], the route gets cancelled once the client
+/// closed the connection, in which case `None` is returned.
+async fn call_service<S, B>(
+    service: &mut S,
+    request: Request<Body>,
+    reader: &Mutex<BufReader<Box<dyn Stream>>>,
+    config: &HandlerConfig,
+) -> Option<Response<B>>
+where
+    S: Service<Request<Body>, Response = Response<B>, Error = Infallible>,
+{
+    let Some(interval) = config.disconnect_check else {
+        // the error is `Infallible`, since routers turn every error into a response
+        return Some(
+            service
+                .call(request)
+                .await
+                .unwrap_or_else(|_| unreachable!()),
+        );
+    };
+
+    let route = format!("{} {}", request.method(), request.uri().path());
+    let mut call = pin!(service.call(request));
+    let mut disconnect = pin!(async {
+        loop {
+            sleep(interval).await;
+            // the reader is held while the route reads the body, which shows that the client is still there anyway
+            if let Ok(reader) = reader.try_lock() {
+                if reader.get_ref().peer_closed().unwrap_or(true) {
+                    return;
+                }
+            }
+        }
+    });
+    let response = poll_fn(|context| match call.as_mut().poll(context) {
+        Poll::Ready(response) => Poll::Ready(Some(response)),
+        Poll::Pending => disconnect.as_mut().poll(context).map(|()| None),
+    })
+    .await;
+
+    match response {
+        // the error is `Infallible`, since routers turn every error into a response
+        Some(response) => Some(response.unwrap_or_else(|_| unreachable!())),
+        None => {
+            info!(
+                config.name,
+                "The client of `{route}` closed the connection before the route responded. The route was cancelled."
+            );
+            None
+        }
+    }
+}
+
+/// Run the given read of a connection, which blocks until the client sent something. \
+/// A connection waiting for its next request would otherwise block its worker thread for as long as the client stays idle. On a
+/// multi-threaded runtime, the worker hands its other tasks and the timers and I/O it may be driving to another thread first, so they do
+/// not stall in the meantime.
+fn blocking<T>(read: impl FnOnce() -> T) -> T {
+    match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+        Ok(RuntimeFlavor::MultiThread) => block_in_place(read),
+        _ => read(),
+    }
+}
+
+/// Lock the reader of a connection, which is shared between the [`HttpServer`] and the body of the current request.
+fn lock_reader(
+    reader: &Mutex<BufReader<Box<dyn Stream>>>,
+) -> io::Result<MutexGuard<'_, BufReader<Box<dyn Stream>>>> {
+    reader
+        .lock()
+        .map_err(|_| io::Error::other("The reader of the connection is poisoned."))
+}
+
+/// How the end of the body of a request is found, which gets chosen by its headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BodyReadStrategy {
+    /// The body has the given number of bytes, as told by its `Content-Length`. Requests of HTTP/1.1 clients without a body have a
+    /// length of `0`.
+    Length(u64),
+    /// The body is sent in chunks with `Transfer-Encoding: chunked`, which end with an empty chunk and the trailers.
+    Chunked,
+    /// The body of an HTTP/1.0 client without a `Content-Length` ends with the connection, so it cannot be kept open afterwards. Requests
+    /// whose method does not define a body, like `GET`, have a length of `0` instead.
+    UntilClose,
+}
+
+/// The body of a request, which only gets read from the connection once the route asks for it.
+struct BodyStream {
+    /// The reader of the connection the request was sent over.
+    reader: Arc<Mutex<BufReader<Box<dyn Stream>>>>,
+    /// The number of bytes of the body that have not been read yet.
+    remaining: Arc<AtomicU64>,
+    /// Whether the body ends with the connection instead of after the remaining bytes, which then start at [`u64::MAX`].
+    until_close: bool,
+}
+impl futures_core::Stream for BodyStream {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let remaining = self.remaining.load(Ordering::SeqCst);
+        if remaining == 0 {
+            return Poll::Ready(None);
+        }
+
+        let mut reader = match lock_reader(&self.reader) {
+            Ok(reader) => reader,
+            Err(error) => return Poll::Ready(Some(Err(error))),
+        };
+        let chunk = loop {
+            match reader.fill_buf() {
+                Ok([]) if self.until_close => {
+                    self.remaining.store(0, Ordering::SeqCst);
+                    return Poll::Ready(None);
+                }
+                Ok([]) => return Poll::Ready(Some(Err(ErrorKind::UnexpectedEof.into()))),
+                Ok(buffer) => {
+                    let length = cmp::min(buffer.len() as u64, remaining) as usize;
+                    break buffer[..length].to_vec();
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        };
+        reader.consume(chunk.len());
+        self.remaining
+            .fetch_sub(chunk.len() as u64, Ordering::SeqCst);
+
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+/// Read and discard the rest of a request body the route did not read. Only up to [`MAX_DRAINED_BODY_BYTES`] get read, since receiving a
+/// larger body would take longer than letting the client open a new connection.
+async fn drain_body(mut body: Body) {
+    let mut drained = 0;
+    while let Some(Ok(chunk)) = body.data().await {
+        drained += chunk.len();
+        if drained > MAX_DRAINED_BODY_BYTES {
+            return;
+        }
+    }
+}
+
+/// The body of a request sent with `Transfer-Encoding: chunked`, which only gets read from the connection and decoded once the route asks
+/// for it.
+struct ChunkedBodyStream {
+    /// The reader of the connection the request was sent over.
+    reader: Arc<Mutex<BufReader<Box<dyn Stream>>>>,
+    /// The number of bytes of the current chunk that have not been read yet. `0` if the size of the next chunk has to be read.
+    chunk_remaining: Arc<AtomicU64>,
+    /// Whether the last chunk and the trailers were read.
+    finished: Arc<AtomicBool>,
+    /// The trailers of the request, which get set once they were read.
+    trailers: Trailers,
+    /// The settings limiting the size of the trailers.
+    config: Arc<HandlerConfig>,
+}
+impl ChunkedBodyStream {
+    /// Read the next piece of the body. An empty vector is returned once the last chunk and the trailers were read.
+    fn read_next(&mut self, reader: &mut impl BufRead) -> Result<Vec<u8>, Error> {
+        let chunk_remaining = self.chunk_remaining.load(Ordering::SeqCst);
+        if chunk_remaining == 0 {
+            let Some(size_line) = read_line(reader, MAX_CHUNK_SIZE_LINE_BYTES)? else {
+                return Err(ParseError::Chunk.into());
+            };
+            // chunk extensions are not used by anything, so they get ignored
+            let size = size_line.split(';').next().unwrap_or_default().trim();
+            if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return Err(ParseError::Chunk.into());
+            }
+            let Ok(size) = u64::from_str_radix(size, 16) else {
+                return Err(ParseError::Chunk.into());
+            };
+            if size == 0 {
+                let trailers = read_headers(
+                    reader,
+                    self.config.max_header_value_bytes,
+                    self.config.max_request_headers,
+                )?;
+                if let Ok(mut request_trailers) = self.trailers.0.lock() {
+                    *request_trailers = Some(trailers);
+                }
+                self.finished.store(true, Ordering::SeqCst);
+                return Ok(vec![]);
+            }
+            self.chunk_remaining.store(size, Ordering::SeqCst);
+        }
+        let chunk_remaining = self.chunk_remaining.load(Ordering::SeqCst);
+
+        let chunk = loop {
+            match reader.fill_buf() {
+                Ok([]) => return Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
+                Ok(buffer) => {
+                    let length = cmp::min(buffer.len() as u64, chunk_remaining) as usize;
+                    break buffer[..length].to_vec();
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            }
+        };
+        reader.consume(chunk.len());
+        let chunk_remaining = chunk_remaining - chunk.len() as u64;
+        self.chunk_remaining
+            .store(chunk_remaining, Ordering::SeqCst);
+        // every chunk ends with a line break
+        if chunk_remaining == 0 && read_line(reader, 0)?.is_none() {
+            return Err(ParseError::Chunk.into());
+        }
+        Ok(chunk)
+    }
+}
+impl futures_core::Stream for ChunkedBodyStream {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
+
+        let this = self.get_mut();
+        let reader = this.reader.clone();
+        let mut reader = match lock_reader(&reader) {
+            Ok(reader) => reader,
+            Err(error) => return Poll::Ready(Some(Err(error))),
+        };
+        match this.read_next(&mut *reader) {
+            Ok(chunk) if chunk.is_empty() => Poll::Ready(None),
+            Ok(chunk) => Poll::Ready(Some(Ok(chunk))),
+            Err(error) => Poll::Ready(Some(Err(error.into()))),
+        }
     }
 }
@@ -8,8 +8,52 @@
 )]
 
 pub use axum;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub use error::Error;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub use http_server::ConnectionInfo;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub use http_server::EarlyHints;
+#[doc(hidden)]
+pub use macros::__private;
+pub use macros::RouteInfo;
+pub use mime;
 
+pub mod build;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod error;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod esp;
+pub mod ext;
 #[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
 #[cfg(feature = "esp")]
 pub mod http_server;
 mod macros;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod metrics;
+pub mod middleware;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod pool;
+pub mod response;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod rpc_server;
+#[cfg_attr(docsrs, doc(cfg(feature = "security")))]
+#[cfg(feature = "security")]
+pub mod security;
+#[cfg_attr(docsrs, doc(cfg(feature = "esp")))]
+#[cfg(feature = "esp")]
+pub mod testing;
+#[cfg_attr(docsrs, doc(cfg(feature = "trace-context")))]
+#[cfg(feature = "trace-context")]
+pub mod trace;
+#[cfg_attr(docsrs, doc(cfg(feature = "webdav")))]
+#[cfg(feature = "webdav")]
+pub mod webdav;
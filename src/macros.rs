@@ -1,58 +1,1335 @@
-//! This module exports the [`router`](crate::router) macro used to simplify the route setup of [`axum Routers`](axum::Router)
+//! This module exports the [`router`](crate::router) macro used to simplify the route setup of [`axum Routers`](axum::Router), and the
+//! [`middleware_stack`](crate::middleware_stack) macro composing several layers into one
 
 /// # Do not use this macro!
 /// # Use the [`router`] macro instead.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __router_internally {
-    // Used for actual routes
+    // Used to apply the flags of a router one after another
+    {
+        @flags [
+            [ $( $flag:tt ) * ]
+            $( $rest:tt ) *
+        ]
+        $axum:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_flag! { [ $( $flag ) * ] $axum $group_id $entries }
+        $crate::__router_internally! { @flags [ $( $rest ) * ] $axum $group_id $entries }
+    };
+    {
+        @flags []
+        $axum:tt $group_id:ident $entries:tt
+    } => {};
+    // Used to parse the entries of a router one after another into the form used by all other arms:
+    // `[ $attributes route $route $request_type { $( , $argument ) * } ]`, `[ $attributes group $group ]`,
+    // `[ $attributes root_group $group ]`, `[ $attributes resource $name [ $( $action ) * ] ]`, or `[ $attributes merge [ $path ] ]`
+    {
+        @parse $flags:tt $group_id:ident $entries:tt [ $( $attribute:tt ) * ]
+        #[skip_smoke_test]
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id $entries [ $( $attribute ) * #[skip_smoke_test] ]
+            $( $rest ) *
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident $entries:tt [ $( $attribute:tt ) * ]
+        #[doc = $doc:literal]
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id $entries [ $( $attribute ) * #[doc = $doc] ]
+            $( $rest ) *
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident $entries:tt $attributes:tt
+        #[ $( $attribute:tt ) * ]
+        $( $rest:tt ) *
+    } => {
+        compile_error!(concat!("goohttp: unknown route attribute `#[", stringify!($( $attribute ) *), "]`"));
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] []
+        ;
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! { @parse $flags $group_id [ $( $entry ) * ] [] $( $rest ) * }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        use $( $segment:ident ) :: +
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [ $( $entry ) * [ $attributes merge [ $( $segment ) :: + ] ] ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        resource $name:ident only ( $( $action:ident ), * $( , ) ? )
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [ $( $entry ) * [ $attributes resource $name [ $( $action ) * ] ] ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        resource $name:ident
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [ $( $entry ) * [ $attributes resource $name [ index show create update destroy ] ] ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        $route:ident,
+        $request_type:ident
+        $(
+            ,
+            $argument:tt
+            $( = $value:expr ) ?
+        ) *
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [
+                $( $entry ) *
+                [ $attributes route $route $request_type { $( , $argument $( = $value ) ? ) * } ]
+            ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        $group:ident ( root )
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [ $( $entry ) * [ $attributes root_group $group ] ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident [ $( $entry:tt ) * ] $attributes:tt
+        $group:ident
+        $( ; $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @parse $flags $group_id [ $( $entry ) * [ $attributes group $group ] ] []
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @parse $flags:tt $group_id:ident $entries:tt []
+    } => {
+        $crate::__router_internally! { @generate $flags $group_id $entries }
+    };
+    {
+        @parse $flags:tt $group_id:ident $entries:tt $attributes:tt
+        $( $rest:tt ) *
+    } => {
+        compile_error!(concat!("goohttp: could not parse the route entry `", stringify!($( $rest ) *), "`"));
+    };
+    // Used to find the path of the axum crate the router should be built with, which can be changed with the `use_axum` flag, and the
+    // extensions of the router, which are added with the `extension` attribute
+    {
+        @generate $flags:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_internally! { @axum [ $crate::axum ] [] $flags $flags $group_id $entries }
+    };
+    {
+        @axum $axum:tt $extensions:tt [ [ use_axum = $( $path:tt ) + ] $( $rest:tt ) * ] $flags:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_internally! { @axum [ $( $path ) + ] $extensions [ $( $rest ) * ] $flags $group_id $entries }
+    };
+    {
+        @axum $axum:tt [ $( $extension:ty, ) * ] [ [ extension ( $( $new_extension:ty ), + $( , ) ? ) ] $( $rest:tt ) * ]
+        $flags:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_internally! {
+            @axum $axum [ $( $extension, ) * $( $new_extension, ) + ] [ $( $rest ) * ] $flags $group_id $entries
+        }
+    };
+    {
+        @axum $axum:tt $extensions:tt [ $flag:tt $( $rest:tt ) * ] $flags:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_internally! { @axum $axum $extensions [ $( $rest ) * ] $flags $group_id $entries }
+    };
+    {
+        @axum $axum:tt $extensions:tt [] $flags:tt $group_id:ident $entries:tt
+    } => {
+        $crate::__router_internally! { @generated $axum $extensions $flags $group_id $entries }
+    };
+    // Used to refer to an item of the axum crate the router is built with
+    {
+        @in_axum [ $( $axum:tt ) * ] $( $item:tt ) *
+    } => {
+        $( $axum ) *::$( $item ) *
+    };
+    // Used to generate everything declared by a router
+    {
+        @generated $axum:tt $extensions:tt $flags:tt $group_id:ident [ $( $entry:tt ) * ]
+    } => {
+        $ (
+            $crate::__router_internally! { @module $entry }
+        ) *
+
+        $crate::__router_internally! {
+            @extensions $axum $group_id [ $( $entry ) * ] $extensions []
+            [ extension_1 extension_2 extension_3 extension_4 extension_5 extension_6 extension_7 extension_8 ]
+        }
+
+        /// Get every route of this group, including the ones of the groups nested in it.
+        pub fn router_routes() -> Vec<$crate::RouteInfo> {
+            let mut routes = Vec::new();
+            $ (
+                $crate::__router_internally! { @routes routes; $entry }
+            ) *
+            routes
+        }
+
+        /// The paths of the routes of this group.
+        pub mod urls {
+            $ (
+                $crate::__router_internally! { @url $entry }
+            ) *
+        }
+
+        $crate::__router_internally! {
+            @flags $flags
+            $axum $group_id { $( $entry ) * }
+        }
+    };
+    // Used to generate the router function, and the `with_extensions` function if the router has extensions. Each extension gets one of
+    // the given parameter names.
+    {
+        @extensions $axum:tt $group_id:ident $entries:tt [] [] $parameter_names:tt
+    } => {
+        $crate::__router_internally! { @router_function $axum $group_id $entries }
+    };
+    {
+        @extensions $axum:tt $group_id:ident $entries:tt [] [ $( $parameter:ident : $extension:ty, ) + ] $parameter_names:tt
+    } => {
+        $crate::__router_internally! { @router_function $axum __goohttp_router $entries }
+
+        pub fn $group_id() -> $crate::__router_internally!(@in_axum $axum Router) {
+            with_extensions($( <$extension as ::std::default::Default>::default() ), +)
+        }
+
+        /// Build the router of this group with the given values as its extensions, instead of their default values.
+        pub fn with_extensions($( $parameter: $extension ), +) -> $crate::__router_internally!(@in_axum $axum Router) {
+            __goohttp_router()
+                $( .layer($crate::__router_internally!(@in_axum $axum Extension)($parameter)) ) +
+        }
+    };
+    {
+        @extensions $axum:tt $group_id:ident $entries:tt [ $extension:ty, $( $rest:ty, ) * ] [ $( $parameter:tt ) * ]
+        [ $parameter_name:ident $( $parameter_names:ident ) * ]
+    } => {
+        $crate::__router_internally! {
+            @extensions $axum $group_id $entries [ $( $rest, ) * ] [ $( $parameter ) * $parameter_name : $extension, ]
+            [ $( $parameter_names ) * ]
+        }
+    };
+    {
+        @extensions $axum:tt $group_id:ident $entries:tt $extensions:tt $parameters:tt []
+    } => {
+        compile_error!("goohttp: a router can have up to 8 extensions");
+    };
+    {
+        @router_function $axum:tt $function:ident [ $( $entry:tt ) * ]
+    } => {
+        pub fn $function() -> $crate::__router_internally!(@in_axum $axum Router) {
+            let mut router = $crate::__router_internally!(@in_axum $axum Router::new)();
+            $ (
+                // Dynamically generate either an actual route or a group of routes using the hidden patterns of this macro.
+                router = $crate::__router_internally! { @register router $axum; $entry };
+            ) *
+            router
+        }
+    };
+    // Used to declare the module behind an entry
+    {
+        @module [ $attributes:tt route $route:ident $( $tail:tt ) * ]
+    } => {
+        mod $route;
+    };
+    {
+        @module [ $attributes:tt group $group:ident ]
+    } => {
+        mod $group;
+    };
+    {
+        @module [ $attributes:tt root_group $group:ident ]
+    } => {
+        mod $group;
+    };
+    {
+        @module [ $attributes:tt resource $name:ident $actions:tt ]
+    } => {
+        mod $name;
+    };
+    {
+        @module [ $attributes:tt merge $path:tt ]
+    } => {};
+    // Used to add an entry to the router
+    {
+        @register $router:ident $axum:tt; [ $attributes:tt route $route:ident $request_type:ident { $( $argument:tt ) * } ]
+    } => {
+        $crate::__router_internally! { $router $axum; $route, $request_type $( $argument ) * }
+    };
+    {
+        @register $router:ident $axum:tt; [ $attributes:tt group $group:ident ]
+    } => {
+        $crate::__router_internally! { $router $axum; $group }
+    };
+    {
+        @register $router:ident $axum:tt; [ $attributes:tt root_group $group:ident ]
+    } => {
+        $router.merge($group::$group())
+    };
+    {
+        @register $router:ident $axum:tt; [ $attributes:tt merge [ $( $path:tt ) + ] ]
+    } => {
+        $router.merge($( $path ) + ())
+    };
+    {
+        @register $router:ident $axum:tt; [ $attributes:tt resource $name:ident [ $( $action:ident ) * ] ]
+    } => {
+        {
+            let mut resource_router = $router;
+            $ (
+                resource_router = $crate::__router_internally! { @resource resource_router $axum; $name $action };
+            ) *
+            resource_router
+        }
+    };
+    // Used for the actions of resources
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident index
+    } => {
+        $router.route(&$crate::__private::route_path(std::stringify!($name), &[]), $( $axum ) *::routing::get($name::index))
+    };
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident show
+    } => {
+        $router.route(&$crate::__private::route_path(std::stringify!($name), &[":id"]), $( $axum ) *::routing::get($name::show))
+    };
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident create
+    } => {
+        $router.route(&$crate::__private::route_path(std::stringify!($name), &[]), $( $axum ) *::routing::post($name::create))
+    };
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident update
+    } => {
+        $router.route(&$crate::__private::route_path(std::stringify!($name), &[":id"]), $( $axum ) *::routing::put($name::update))
+    };
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident destroy
+    } => {
+        $router.route(&$crate::__private::route_path(std::stringify!($name), &[":id"]), $( $axum ) *::routing::delete($name::destroy))
+    };
+    {
+        @resource $router:ident [ $( $axum:tt ) * ]; $name:ident $action:ident
+    } => {
+        compile_error!(concat!(
+            "goohttp: unknown action `",
+            stringify!($action),
+            "` of the resource `",
+            stringify!($name),
+            "`, expected one of `index`, `show`, `create`, `update`, or `destroy`"
+        ))
+    };
+    // Used for the paths of entries
+    {
+        @url [ $attributes:tt route $route:ident $request_type:ident { $( $argument:tt ) * } ]
+    } => {
+        $crate::__router_internally! { @url $route, $request_type $( $argument ) * }
+    };
+    {
+        @url [ $attributes:tt group $group:ident ]
+    } => {
+        $crate::__router_internally! { @url $group }
+    };
+    {
+        @url [ $attributes:tt root_group $group:ident ]
+    } => {
+        #[doc = concat!("Get the given path of the `", std::stringify!($group), "` group, which is mounted at the root of this group.")]
+        pub fn $group(path: &str) -> String {
+            if path.is_empty() {
+                "/".to_string()
+            } else {
+                path.to_string()
+            }
+        }
+    };
+    {
+        @url [ $attributes:tt merge $path:tt ]
+    } => {};
+    {
+        @url [ $attributes:tt resource $name:ident [ $( $action:ident ) * ] ]
+    } => {
+        #[doc = concat!("The paths of the actions of the `", std::stringify!($name), "` resource.")]
+        pub mod $name {
+            $ (
+                $crate::__router_internally! { @resource_url $name $action }
+            ) *
+        }
+    };
+    {
+        @resource_url $name:ident index
+    } => {
+        #[allow(non_upper_case_globals)]
+        #[doc = "The path of the `index` action."]
+        pub const index: &str = concat!("/", std::stringify!($name));
+    };
+    {
+        @resource_url $name:ident create
+    } => {
+        #[allow(non_upper_case_globals)]
+        #[doc = "The path of the `create` action."]
+        pub const create: &str = concat!("/", std::stringify!($name));
+    };
+    {
+        @resource_url $name:ident $action:ident
+    } => {
+        #[doc = concat!("Build the path of the `", std::stringify!($action), "` action with the given, percent-encoded id.")]
+        pub fn $action(parameters: [&str; 1]) -> String {
+            $crate::__private::build_url(std::stringify!($name), &[":id"], &parameters)
+        }
+    };
+    // Used to split the arguments of a route into its parameters and its options before handing them to the given arm
+    {
+        @split { $( $arm:tt ) * }
+        [ $( $parameter:literal ), * ]
+        [ $( $option:tt ) * ];
+        $name:ident = $value:expr
+        $( , $( $rest:tt ) * ) ?
+    } => {
+        // checked before the parameters, since a failed match of a `literal` fragment would end the macro
+        $crate::__router_internally! {
+            @split { $( $arm ) * }
+            [ $( $parameter ), * ]
+            [ $( $option ) * ( $name $value ) ];
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @split { $( $arm:tt ) * }
+        [ $( $parameter:literal ), * ]
+        [ $( $option:tt ) * ];
+        $next:literal
+        $( , $( $rest:tt ) * ) ?
+    } => {
+        $crate::__router_internally! {
+            @split { $( $arm ) * }
+            [ $( $parameter, ) * $next ]
+            [ $( $option ) * ];
+            $( $( $rest ) * ) ?
+        }
+    };
+    {
+        @split { $( $arm:tt ) * }
+        $parameters:tt
+        $options:tt;
+    } => {
+        $crate::__router_internally! { $( $arm ) * $parameters $options }
+    };
+    // Used to wrap the method router of a route in its options. The first option ends up as the outermost layer.
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( guard $guard:expr )
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! { @options [ $( $axum ) * ] $method_router; $( $rest ) * }.route_layer(
+            $( $axum ) *::middleware::from_fn(
+                // The type of `next` gets inferred from the call below, since it differs between the versions of axum.
+                |request: $( $axum ) *::http::Request<$( $axum ) *::body::Body>, next| async move {
+                    match $guard(&request).await {
+                        Ok(()) => $( $axum ) *::middleware::Next::run(next, request).await,
+                        Err(status) => $( $axum ) *::response::IntoResponse::into_response(status),
+                    }
+                },
+            ),
+        )
+    };
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( accept $content_types:expr )
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! { @options [ $( $axum ) * ] $method_router; $( $rest ) * }.route_layer(
+            $( $axum ) *::middleware::from_fn(
+                |request: $( $axum ) *::http::Request<$( $axum ) *::body::Body>, next| async move {
+                    let content_type = request
+                        .headers()
+                        .get($( $axum ) *::http::header::CONTENT_TYPE)
+                        .and_then(|content_type| content_type.to_str().ok());
+                    if $crate::__private::accepts_content_type(content_type, &$content_types) {
+                        $( $axum ) *::middleware::Next::run(next, request).await
+                    } else {
+                        $( $axum ) *::response::IntoResponse::into_response(
+                            $( $axum ) *::http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        )
+                    }
+                },
+            ),
+        )
+    };
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( init $init:expr )
+        $( $rest:tt ) *
+    } => {{
+        let initialized = ::std::sync::Arc::new($crate::__private::InitOnce::new());
+        $crate::__router_internally! { @options [ $( $axum ) * ] $method_router; $( $rest ) * }.route_layer(
+            $( $axum ) *::middleware::from_fn(
+                move |mut request: $( $axum ) *::http::Request<$( $axum ) *::body::Body>, next| {
+                    let initialized = initialized.clone();
+                    async move {
+                        let value = initialized.get_or_init($init).await;
+                        request.extensions_mut().insert(value);
+                        $( $axum ) *::middleware::Next::run(next, request).await
+                    }
+                },
+            ),
+        )
+    }};
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( limit $limit:expr )
+        $( $rest:tt ) *
+    } => {
+        $crate::__router_internally! { @options [ $( $axum ) * ] $method_router; $( $rest ) * }.layer(
+            $( $axum ) *::extract::DefaultBodyLimit::max($limit)
+        )
+    };
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( timeout $timeout:expr )
+        $( $rest:tt ) *
+    } => {{
+        // evaluated while compiling, so an invalid timeout fails the build
+        const TIMEOUT: ::std::time::Duration = $crate::__private::route_timeout(stringify!($timeout));
+        $crate::__router_internally! { @options [ $( $axum ) * ] $method_router; $( $rest ) * }.route_layer(
+            $( $axum ) *::middleware::from_fn(
+                |request: $( $axum ) *::http::Request<$( $axum ) *::body::Body>, next| async move {
+                    match ::tokio::time::timeout(TIMEOUT, $( $axum ) *::middleware::Next::run(next, request)).await {
+                        Ok(response) => response,
+                        Err(_) => $( $axum ) *::response::IntoResponse::into_response(
+                            $( $axum ) *::http::StatusCode::SERVICE_UNAVAILABLE,
+                        ),
+                    }
+                },
+            ),
+        )
+    }};
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+    } => {
+        $method_router
+    };
+    {
+        @options [ $( $axum:tt ) * ] $method_router:expr;
+        ( $name:ident $value:expr )
+        $( $rest:tt ) *
+    } => {
+        compile_error!(concat!("goohttp: unknown route option `", stringify!($name), "`"))
+    };
+    // Used for the path constants of routes without parameters
+    {
+        @url
+        index,
+        $request_type:ident
+        [] $options:tt
+    } => {
+        #[allow(non_upper_case_globals)]
+        #[doc = "The path of the `index` route."]
+        pub const index: &str = "/";
+    };
+    {
+        @url
+        remaining,
+        $request_type:ident
+        [] $options:tt
+    } => {
+        #[doc = "Build the path of the `remaining` route. Slashes in the given value are kept."]
+        pub fn remaining(parameters: [&str; 1]) -> String {
+            $crate::__private::build_url("remaining", &[], &parameters)
+        }
+    };
+    {
+        @url
+        $route:ident,
+        $request_type:ident
+        [] $options:tt
+    } => {
+        #[allow(non_upper_case_globals)]
+        #[doc = concat!("The path of the `", std::stringify!($route), "` route.")]
+        pub const $route: &str = concat!("/", std::stringify!($route));
+    };
+    // Used for the path builders of routes with parameters
+    {
+        @url
+        $route:ident,
+        $request_type:ident
+        [ $( $parameter:literal ), + ] $options:tt
+    } => {
+        #[doc = concat!("Build the path of the `", std::stringify!($route), "` route with its parameters percent-encoded in the order they were declared.")]
+        pub fn $route(
+            parameters: [&str; $crate::__private::parameter_count(std::stringify!($route), &[ $( $parameter ), + ])],
+        ) -> String {
+            $crate::__private::build_url(std::stringify!($route), &[ $( $parameter ), + ], &parameters)
+        }
+    };
     {
-        $router:ident;
+        @url
         $route:ident,
         $request_type:ident
         $(
             ,
-            $parameter:literal
+            $argument:tt
+            $( = $value:expr ) ?
         ) *
+    } => {
+        $crate::__router_internally! {
+            @split { @url $route, $request_type } [] [];
+            $( $argument $( = $value ) ? ), *
+        }
+    };
+    // Used for the path builders of route groups
+    {
+        @url
+        $group:ident
+    } => {
+        #[doc = concat!("Prefix the given path of the `", std::stringify!($group), "` group with the path this group is nested at.")]
+        pub fn $group(path: &str) -> String {
+            format!("/{}{}", std::stringify!($group), path)
+        }
+    };
+    // Used for actual routes
+    {
+        @route
+        $router:ident [ $( $axum:tt ) * ];
+        $route:ident,
+        $request_type:ident
+        [ $( $parameter:literal ), * ] [ $( $option:tt ) * ]
     } => {
         $router.route(
-            & {
-                let mut route;
-                if std::stringify!($route) == "index" {
-                    route = "/".to_string();
-                } else if std::stringify!($route) == "remaining" {
-                    route = "/*remaining".to_string();
-                } else {
-                    route = format!("/{}", std::stringify!($route));
+            &$crate::__private::route_path(std::stringify!($route), &[ $( $parameter ), * ]),
+            $crate::__router_internally! {
+                @options [ $( $axum ) * ] $( $axum ) *::routing::$request_type($route::$route);
+                $( $option ) *
+            }
+        )
+    };
+    {
+        $router:ident $axum:tt;
+        $route:ident,
+        $request_type:ident
+        $(
+            ,
+            $argument:tt
+            $( = $value:expr ) ?
+        ) *
+    } => {
+        $crate::__router_internally! {
+            @split { @route $router $axum; $route, $request_type } [] [];
+            $( $argument $( = $value ) ? ), *
+        }
+    };
+    // Used for route groups
+    {
+        $router:ident $axum:tt;
+        $group:ident
+    } => {
+        $router.nest(&$crate::__private::nest_path(std::stringify!($group)), $group::$group())
+    };
+    // Used for the route table of a router
+    {
+        @routes $routes:ident; [ $attributes:tt route $route:ident $request_type:ident { $( $argument:tt ) * } ]
+    } => {
+        $crate::__router_internally! { @routes $routes $attributes; $route, $request_type $( $argument ) * }
+    };
+    {
+        @routes $routes:ident $attributes:tt;
+        $route:ident,
+        $request_type:ident
+        $(
+            ,
+            $argument:tt
+            $( = $value:expr ) ?
+        ) *
+    } => {
+        $crate::__router_internally! {
+            @split { @route_info $routes $attributes; $route, $request_type } [] [];
+            $( $argument $( = $value ) ? ), *
+        }
+    };
+    {
+        @route_info $routes:ident $attributes:tt;
+        $route:ident,
+        $request_type:ident
+        [ $( $parameter:literal ), * ] $options:tt
+    } => {
+        $routes.push($crate::__private::route_info(
+            std::stringify!($request_type),
+            $crate::__private::route_path(std::stringify!($route), &[ $( $parameter ), * ]),
+            $crate::__router_internally!(@smoke_test $attributes),
+            $crate::__router_internally!(@description $attributes),
+        ));
+    };
+    {
+        @routes $routes:ident; [ $attributes:tt group $group:ident ]
+    } => {
+        let prefix = $crate::__private::nest_path(std::stringify!($group));
+        for mut route in $group::router_routes() {
+            route.path = if route.path == "/" {
+                prefix.clone()
+            } else {
+                format!("{prefix}{}", route.path)
+            };
+            route.smoke_test &= $crate::__router_internally!(@smoke_test $attributes);
+            $routes.push(route);
+        }
+    };
+    {
+        @routes $routes:ident; [ $attributes:tt root_group $group:ident ]
+    } => {
+        for mut route in $group::router_routes() {
+            route.smoke_test &= $crate::__router_internally!(@smoke_test $attributes);
+            $routes.push(route);
+        }
+    };
+    {
+        @routes $routes:ident; [ $attributes:tt merge $path:tt ]
+    } => {};
+    {
+        @routes $routes:ident; [ $attributes:tt resource $name:ident [ $( $action:ident ) * ] ]
+    } => {
+        $ (
+            $crate::__router_internally! { @resource_info $routes $attributes; $name $action }
+        ) *
+    };
+    {
+        @resource_info $routes:ident $attributes:tt; $name:ident $action:ident
+    } => {
+        if let Some((method, parameters)) = $crate::__private::resource_action(std::stringify!($action)) {
+            $routes.push($crate::__private::route_info(
+                method,
+                $crate::__private::route_path(std::stringify!($name), parameters),
+                $crate::__router_internally!(@smoke_test $attributes),
+                $crate::__router_internally!(@description $attributes),
+            ));
+        }
+    };
+    // Used to check whether the smoke tests should call a route
+    {
+        @smoke_test [ #[skip_smoke_test] $( $rest:tt ) * ]
+    } => {
+        false
+    };
+    {
+        @smoke_test [ $attribute:tt $( $rest:tt ) * ]
+    } => {
+        $crate::__router_internally!(@smoke_test [ $( $rest ) * ])
+    };
+    {
+        @smoke_test []
+    } => {
+        true
+    };
+    // Used to collect the doc comments of a route into its description
+    {
+        @description [ #[doc = $doc:literal] $( $rest:tt ) * ] $( $lines:literal ) *
+    } => {
+        $crate::__router_internally!(@description [ $( $rest ) * ] $( $lines ) * $doc)
+    };
+    {
+        @description [ # $attribute:tt $( $rest:tt ) * ] $( $lines:literal ) *
+    } => {
+        $crate::__router_internally!(@description [ $( $rest ) * ] $( $lines ) *)
+    };
+    {
+        @description [] $( $lines:literal ) *
+    } => {
+        $crate::__private::description(&[ $( $lines ), * ])
+    };
+}
+
+/// # Do not use this macro!
+/// # Use the [`router`] macro instead.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __router_flag {
+    // Strict mode: a group without any routes is most likely a leftover of a refactor
+    {
+        [strict]
+        $axum:tt $group_id:ident {}
+    } => {
+        compile_error!(concat!(
+            "goohttp strict mode: the route group `",
+            stringify!($group_id),
+            "` does not declare any routes"
+        ));
+    };
+    // Strict mode: every module needs to export a function with the same name
+    {
+        [strict]
+        $axum:tt $group_id:ident { $( $entry:tt ) * }
+    } => {
+        #[allow(dead_code, non_camel_case_types)]
+        fn __goohttp_strict() {
+            use $crate::__private::StrictFallback as _;
+            $ (
+                $crate::__router_flag! { @strict $entry }
+            ) *
+        }
+    };
+    {
+        @strict [ $attributes:tt route $route:ident $( $tail:tt ) * ]
+    } => {
+        $crate::__router_flag! { @strict_check $route $route assert_route_function }
+    };
+    {
+        @strict [ $attributes:tt group $group:ident ]
+    } => {
+        $crate::__router_flag! { @strict_check $group $group assert_route_function }
+    };
+    {
+        @strict [ $attributes:tt root_group $group:ident ]
+    } => {
+        $crate::__router_flag! { @strict_check $group $group assert_route_function }
+    };
+    // the compiler already checks that the functions of merged routers exist
+    {
+        @strict [ $attributes:tt merge $path:tt ]
+    } => {};
+    {
+        @strict [ $attributes:tt resource $name:ident [ $( $action:ident ) * ] ]
+    } => {
+        $ (
+            $crate::__router_flag! { @strict_check $name $action assert_resource_action }
+        ) *
+    };
+    {
+        @strict_check $module:ident $function:ident $assert:ident
+    } => {
+        {
+            // This fallback only gets picked if the glob import below does not provide a function with this name.
+            struct $function;
+            impl $function {
+                fn __goohttp_strict(&self) -> Self {
+                    $function
+                }
+            }
+            {
+                #[allow(unused_imports)]
+                use self::$module::*;
+                $crate::__private::$assert((&$function).__goohttp_strict());
+            }
+        }
+    };
+    // With tests: generate a smoke test for every route group
+    {
+        [with_tests]
+        $axum:tt $group_id:ident { $( $entry:tt ) * }
+    } => {
+        #[cfg(test)]
+        mod __goohttp_tests {
+            $ (
+                $crate::__router_flag! { @with_tests $axum $group_id $entry }
+            ) *
+        }
+    };
+    {
+        @with_tests
+        [ $( $axum:tt ) * ] $group_id:ident [ $attributes:tt group $group:ident ]
+    } => {
+        #[test]
+        fn $group() {
+            use $crate::__private::Service as _;
+
+            let path = super::urls::$group("");
+            let request = $( $axum ) *::http::Request::get(&path)
+                .body($( $axum ) *::body::Body::empty())
+                .expect("A request built from a route path should always be valid.");
+            let response = $crate::__private::block_on(super::$group_id().call(request))
+                .expect("The axum router is infallible.");
+
+            assert!(
+                !response.status().is_server_error(),
+                "`GET {path}` was answered with `{}`.",
+                response.status()
+            );
+        }
+    };
+    {
+        @with_tests
+        $axum:tt $group_id:ident [ $attributes:tt root_group $group:ident ]
+    } => {
+        $crate::__router_flag! { @with_tests $axum $group_id [ $attributes group $group ] }
+    };
+    {
+        @with_tests
+        $axum:tt $group_id:ident $entry:tt
+    } => {};
+    // Public handlers: re-export the router function of every nested group
+    {
+        [pub_handlers]
+        $axum:tt $group_id:ident { $( $entry:tt ) * }
+    } => {
+        $ (
+            $crate::__router_flag! { @pub_handlers $entry }
+        ) *
+    };
+    {
+        @pub_handlers [ $attributes:tt group $group:ident ]
+    } => {
+        pub use self::$group::$group;
+    };
+    {
+        @pub_handlers [ $attributes:tt root_group $group:ident ]
+    } => {
+        pub use self::$group::$group;
+    };
+    {
+        @pub_handlers $entry:tt
+    } => {};
+    // Generated smoke tests: call every route of the route table and fail on server errors
+    {
+        [generate_smoke_tests]
+        [ $( $axum:tt ) * ] $group_id:ident $entries:tt
+    } => {
+        #[cfg(test)]
+        mod __goohttp_smoke_tests {
+            #[tokio::test]
+            async fn smoke_tests() {
+                use $crate::__private::Service as _;
+
+                for route in super::router_routes() {
+                    if !route.smoke_test {
+                        continue;
+                    }
+
+                    let path = $crate::__private::smoke_test_path(&route.path);
+                    let request = $( $axum ) *::http::Request::builder()
+                        .method(route.method.as_str())
+                        .uri(&path)
+                        .body($( $axum ) *::body::Body::empty())
+                        .expect("A request built from a route path should always be valid.");
+                    let response = super::$group_id()
+                        .call(request)
+                        .await
+                        .expect("The axum router is infallible.");
+
+                    assert!(
+                        !response.status().is_server_error(),
+                        "`{} {path}` was answered with `{}`.",
+                        route.method,
+                        response.status()
+                    );
+                }
+            }
+        }
+    };
+    // The path of axum already got applied while generating the router
+    {
+        [ use_axum = $( $path:tt ) + ]
+        $axum:tt $group_id:ident $entries:tt
+    } => {};
+    // The extensions already got applied while generating the router
+    {
+        [ extension $arguments:tt ]
+        $axum:tt $group_id:ident $entries:tt
+    } => {};
+    {
+        [ $( $flag:tt ) * ]
+        $axum:tt $group_id:ident $entries:tt
+    } => {
+        compile_error!(concat!(
+            "goohttp: unknown router flag `",
+            stringify!($( $flag ) *),
+            "`"
+        ));
+    };
+}
+
+/// A route declared with the [`router`](crate::router) macro, as listed by the `router_routes` function it generates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteInfo {
+    /// The method of this route in upper case, like `GET`.
+    pub method: String,
+    /// The path of this route, including the paths of the groups it is nested in. Parameters are written like in axum, like
+    /// `/api/say_hello/:caller`.
+    pub path: String,
+    /// Whether the smoke tests of the `generate_smoke_tests` flag call this route, which is not the case if it or one of its groups is
+    /// marked with `#[skip_smoke_test]`.
+    pub smoke_test: bool,
+    /// The doc comment of this route, with the lines of longer comments separated by newlines. `None` is returned for routes without
+    /// one.
+    pub description: Option<String>,
+}
+
+/// Items used by the code the macros of this crate generate.
+#[doc(hidden)]
+pub mod __private {
+    use std::{
+        future::{
+            poll_fn,
+            Future,
+        },
+        mem,
+        pin::pin,
+        sync::{
+            Arc,
+            Mutex,
+            PoisonError,
+        },
+        task::{
+            Context,
+            Poll,
+            Wake,
+            Waker,
+        },
+        thread::{
+            self,
+            Thread,
+        },
+        time::Duration,
+    };
+
+    pub use tower_layer::{
+        Identity,
+        Stack,
+    };
+    pub use tower_service::Service;
+
+    use super::RouteInfo;
+
+    /// Implemented for every type, so that the [`router`](crate::router) macro in strict mode can tell real route functions apart from its
+    /// fallback.
+    pub trait StrictFallback {
+        /// Real route functions resolve to this method, while the fallback of the strict mode shadows it with an inherent method.
+        fn __goohttp_strict(&self) -> StrictPresent {
+            StrictPresent
+        }
+    }
+    impl<T: ?Sized> StrictFallback for T {}
+
+    /// Returned by [`StrictFallback`] for modules exporting their route function.
+    pub struct StrictPresent;
+
+    /// Only implemented for [`StrictPresent`].
+    #[diagnostic::on_unimplemented(
+        message = "goohttp strict mode: the module behind `{Self}` does not export a `pub fn` or `pub async fn` with the same name",
+        label = "route function not found"
+    )]
+    pub trait RouteFunction {}
+    impl RouteFunction for StrictPresent {}
+
+    /// Fails to compile if the given value is not [`StrictPresent`].
+    pub fn assert_route_function<T: RouteFunction>(_: T) {}
+
+    /// Only implemented for [`StrictPresent`].
+    #[diagnostic::on_unimplemented(
+        message = "goohttp strict mode: the module of the resource does not export the action `{Self}`",
+        label = "resource action not found",
+        note = "the actions of a resource are expected as `pub async fn index()`, `pub async fn show(Path(id): Path<String>)`, `pub async fn create(body)`, `pub async fn update(Path(id): Path<String>, body)`, and `pub async fn destroy(Path(id): Path<String>)`"
+    )]
+    pub trait ResourceAction {}
+    impl ResourceAction for StrictPresent {}
+
+    /// Fails to compile if the given value is not [`StrictPresent`].
+    pub fn assert_resource_action<T: ResourceAction>(_: T) {}
+
+    /// Wakes up the thread waiting in [`block_on`].
+    struct ThreadWaker(Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Drive the given future to completion on the current thread.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    /// Holds the value created by the `init` option of a route, which gets created by the first request reaching the route.
+    pub struct InitOnce<T> {
+        /// Whether the value got created yet.
+        state: Mutex<InitState<T>>,
+    }
+    /// The state of an [`InitOnce`].
+    enum InitState<T> {
+        /// No request started to create the value yet.
+        Empty,
+        /// A request is creating the value. The requests waiting for it get woken up with the given wakers once it is done.
+        Running(Vec<Waker>),
+        /// The value got created.
+        Ready(T),
+    }
+    impl<T: Clone> InitOnce<T> {
+        /// Create an empty InitOnce.
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Self {
+                state: Mutex::new(InitState::Empty),
+            }
+        }
+
+        /// Get the value, creating it with the given function if no request did so yet. Requests arriving while the value gets created wait
+        /// for it instead of calling the function again.
+        pub async fn get_or_init<F: Future<Output = T>>(&self, init: impl FnOnce() -> F) -> T {
+            let ready = poll_fn(|context| {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                match &mut *state {
+                    InitState::Ready(value) => Poll::Ready(Some(value.clone())),
+                    InitState::Running(wakers) => {
+                        wakers.push(context.waker().clone());
+                        Poll::Pending
+                    }
+                    InitState::Empty => {
+                        *state = InitState::Running(vec![]);
+                        Poll::Ready(None)
+                    }
                 }
+            });
+            if let Some(value) = ready.await {
+                return value;
+            }
 
-                $ (
-                    route.push_str(&format!("/{}", $parameter));
-                ) ?
+            // lets another request create the value if this one gets cancelled
+            let reset = InitReset(self);
+            let value = init().await;
+            mem::forget(reset);
+            self.finish(InitState::Ready(value.clone()));
+            value
+        }
 
-                route
-            },
-            $request_type($route::$route)
-        )
-    };
-    // Used for route groups
-    {
-        $router:ident;
-        $group:ident
-    } => {
-        $router.nest(
-            & {
-                let route;
-                if std::stringify!($group) == "remaining" {
-                    route = "/*remaining".to_string();
+        /// Replace the state of the value and wake up the requests waiting for it.
+        fn finish(&self, state: InitState<T>) {
+            let previous = mem::replace(
+                &mut *self.state.lock().unwrap_or_else(PoisonError::into_inner),
+                state,
+            );
+            if let InitState::Running(wakers) = previous {
+                wakers.into_iter().for_each(Waker::wake);
+            }
+        }
+    }
+    /// Resets an [`InitOnce`] whose value did not get created, once it is dropped.
+    struct InitReset<'a, T: Clone>(&'a InitOnce<T>);
+    impl<T: Clone> Drop for InitReset<'_, T> {
+        fn drop(&mut self) {
+            self.0.finish(InitState::Empty);
+        }
+    }
+
+    /// Check whether the given value of a `Content-Type` header names one of the given media types. Parameters like `charset` and the case of
+    /// the media type are ignored.
+    pub fn accepts_content_type(content_type: Option<&str>, accepted: &[&str]) -> bool {
+        let Some(content_type) = content_type else {
+            return false;
+        };
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        accepted
+            .iter()
+            .any(|accepted| accepted.eq_ignore_ascii_case(media_type))
+    }
+
+    /// Parse the value of the `timeout` option of a route, like `500ms` or `2s`. \
+    /// This gets called while compiling, so its panic becomes a compile error.
+    pub const fn route_timeout(timeout: &str) -> Duration {
+        const INVALID: &str = "goohttp: the timeout of a route has to be a number followed by `ms` or `s`, like `500ms`";
+
+        let bytes = timeout.as_bytes();
+        let mut value: u64 = 0;
+        let mut index = 0;
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            value = match value.checked_mul(10) {
+                Some(value) => value + (bytes[index] - b'0') as u64,
+                None => panic!("{}", INVALID),
+            };
+            index += 1;
+        }
+        if index == 0 {
+            panic!("{}", INVALID);
+        }
+        match bytes.len() - index {
+            2 if bytes[index] == b'm' && bytes[index + 1] == b's' => Duration::from_millis(value),
+            1 if bytes[index] == b's' => Duration::from_secs(value),
+            _ => panic!("{}", INVALID),
+        }
+    }
+
+    /// Create the [`RouteInfo`] of a route with the given method, like `get`.
+    pub fn route_info(
+        method: &str,
+        path: String,
+        smoke_test: bool,
+        description: Option<String>,
+    ) -> RouteInfo {
+        RouteInfo {
+            method: method.to_uppercase(),
+            path,
+            smoke_test,
+            description,
+        }
+    }
+
+    /// Join the lines of the doc comment of a route into its description. `None` is returned if the comment is empty.
+    pub fn description(lines: &[&str]) -> Option<String> {
+        let description = lines
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let description = description.trim();
+        (!description.is_empty()).then(|| description.to_string())
+    }
+
+    /// Get the method and parameters of the given action of a resource.
+    pub fn resource_action(action: &str) -> Option<(&'static str, &'static [&'static str])> {
+        match action {
+            "index" => Some(("get", &[])),
+            "show" => Some(("get", &[":id"])),
+            "create" => Some(("post", &[])),
+            "update" => Some(("put", &[":id"])),
+            "destroy" => Some(("delete", &[":id"])),
+            _ => None,
+        }
+    }
+
+    /// Get the path the [`router`](crate::router) macro nests the route group with the given name at.
+    pub fn nest_path(group: &str) -> String {
+        if group == "remaining" {
+            "/*remaining".to_string()
+        } else {
+            format!("/{group}")
+        }
+    }
+
+    /// Fill the parameters of the given route path with placeholder values.
+    pub fn smoke_test_path(path: &str) -> String {
+        path.split('/')
+            .map(|segment| {
+                if segment.starts_with(':') || segment.starts_with('*') {
+                    "1"
                 } else {
-                    route = format!("/{}", std::stringify!($group));
+                    segment
                 }
-                route
-            },
-            $group::$group()
-        )
-    };
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Get the path the [`router`](crate::router) macro registers a route with the given name and parameters at.
+    pub fn route_path(route: &str, parameters: &[&str]) -> String {
+        let mut path = match route {
+            "index" => String::new(),
+            "remaining" => "/*remaining".to_string(),
+            _ => format!("/{route}"),
+        };
+        for parameter in parameters {
+            path.push('/');
+            path.push_str(parameter);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+
+    /// Count the parameters of a route. Besides the `:name` and `*name` segments, the `remaining` route has one implicit parameter.
+    pub const fn parameter_count(route: &str, parameters: &[&str]) -> usize {
+        let route = route.as_bytes();
+        let remaining = b"remaining";
+        let mut count = 0;
+
+        if route.len() == remaining.len() {
+            let mut i = 0;
+            while i < route.len() && route[i] == remaining[i] {
+                i += 1;
+            }
+            if i == route.len() {
+                count += 1;
+            }
+        }
+
+        let mut i = 0;
+        while i < parameters.len() {
+            let parameter = parameters[i].as_bytes();
+            if !parameter.is_empty() && (parameter[0] == b':' || parameter[0] == b'*') {
+                count += 1;
+            }
+            i += 1;
+        }
+        count
+    }
+
+    /// Build the path of a route by filling its parameters with the given, percent-encoded values.
+    pub fn build_url(route: &str, parameters: &[&str], values: &[&str]) -> String {
+        let mut values = values.iter();
+        let mut url = String::new();
+
+        for segment in route_path(route, parameters).split('/').skip(1) {
+            url.push('/');
+            if segment.starts_with(':') {
+                if let Some(value) = values.next() {
+                    encode_path_segment(&mut url, value);
+                }
+            } else if segment.starts_with('*') {
+                if let Some(value) = values.next() {
+                    let mut value_segments = value.trim_start_matches('/').split('/');
+                    if let Some(value_segment) = value_segments.next() {
+                        encode_path_segment(&mut url, value_segment);
+                    }
+                    for value_segment in value_segments {
+                        url.push('/');
+                        encode_path_segment(&mut url, value_segment);
+                    }
+                }
+            } else {
+                url.push_str(segment);
+            }
+        }
+
+        if url.is_empty() {
+            url.push('/');
+        }
+        url
+    }
+
+    /// Append the given value to the given url while percent-encoding everything except unreserved characters.
+    pub(crate) fn encode_path_segment(url: &mut String, value: &str) {
+        for byte in value.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                url.push(byte as char);
+            } else {
+                url.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
 }
 
 /// An easier way to create an [`axum router`](axum::Router).
@@ -78,7 +1355,13 @@ macro_rules! __router_internally {
 /// ```
 /// use frontend::serve_frontend;
 ///
+/// # #[cfg(any())]
 /// mod frontend;
+/// # mod frontend {
+/// #     goohttp::router! {
+/// #         serve_frontend {}
+/// #     }
+/// # }
 ///
 /// fn main() {
 ///     let frontend_router = serve_frontend(); // this function got generated by this macro
@@ -90,6 +1373,9 @@ macro_rules! __router_internally {
 /// Next, we define our root router: \
 /// `src/frontend/mod.rs`
 /// ```
+/// # // the route modules of the integration tests follow this layout
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
 /// use goohttp::router;
 ///
 /// // First we define the route `/` which will be accessible via the `get` method
@@ -102,8 +1388,13 @@ macro_rules! __router_internally {
 ///         api
 ///     }
 /// }
+/// # }
+/// # fn main() {}
 /// ```
 ///
+/// Routes using the `get` method also answer `HEAD` requests. Their responses get the headers of the `GET` response, like its
+/// `Content-Length`, but no body, which the [`HttpServer`](crate::http_server::HttpServer) does not send either.
+///
 /// Leaving the above code as it is will result in a compiler error because the modules and their associated functions index and api could not be found. So we need to create
 /// these modules as well. \
 /// First, we define our route index: \
@@ -132,6 +1423,8 @@ macro_rules! __router_internally {
 /// Now all we need to do is define our router group at `/api`: \
 /// `src/frontend/api/mod.rs`
 /// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
 /// use goohttp::router;
 ///
 /// // Our api will have two routes both taking some arguments, as indicated by the additional
@@ -148,6 +1441,8 @@ macro_rules! __router_internally {
 ///         say_hello_caller_sender, get, ":caller", ":sender"
 ///     }
 /// }
+/// # }
+/// # fn main() {}
 /// ```
 ///
 /// All that remains is to define the two remaining routes: \
@@ -184,50 +1479,491 @@ macro_rules! __router_internally {
 /// - `/api/say_hello_caller_sender/{any argument here}/{any argument here}`
 ///
 /// For more details on how routes work, see [axum's description](https://docs.rs/axum/latest/axum/routing/struct.Router.html#method.route).
+///
+/// If the file of a route is missing, the compiler only points at the macro call. [`verify_route_modules`](crate::build::verify_route_modules)
+/// can check the files in a build script instead, naming every one that is missing.
+///
+/// # Root groups
+///
+/// Marking a route group with `(root)` mounts its routes at the root of the router instead of nesting them at the name of the group, while
+/// the other groups keep their prefix:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
+/// use goohttp::router;
+///
+/// router! {
+///     serve_frontend {
+///         index, get;
+///         // `/about` and `/settings` instead of `/pages/about` and `/pages/settings`
+///         pages (root);
+///         api
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// If a root group and its parent both declare the same path, like `/`, their routes get combined as long as they use different methods.
+/// Declaring the same method for the same path twice makes the router function panic, just like axum does for routes added twice.
+///
+/// # Combining routers
+///
+/// Routers built elsewhere, like by other invocations of this macro, can be merged into a router with `use path::to::function` entries.
+/// The function gets called and its router merged at the root, without any prefix:
+/// ```
+/// # use goohttp::axum::Router;
+/// # mod website {
+/// #     pub fn website() -> super::Router {
+/// #         super::Router::new()
+/// #     }
+/// # }
+/// # mod api {
+/// #     pub fn api_router() -> super::Router {
+/// #         super::Router::new()
+/// #     }
+/// # }
+/// # mod admin {
+/// #     pub fn admin_router() -> super::Router {
+/// #         super::Router::new()
+/// #     }
+/// # }
+/// use goohttp::router;
+///
+/// router! {
+///     combined_app {
+///         use website::website;
+///         use api::api_router;
+///         use admin::admin_router
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// Since the macro cannot see the routes of a merged router, they do not appear in the `urls` module or the route table of the combined
+/// router.
+///
+/// # Building URLs
+///
+/// Next to the router function, this macro generates a `urls` module containing the path of every declared route. Routes without
+/// parameters get a constant, while routes with parameters get a function taking their values in the order they were declared. These
+/// values get percent-encoded, so they will reach the route unchanged. Route groups get a function prefixing the paths of their own `urls`
+/// module:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
+/// # goohttp::router! {
+/// #     serve_frontend {
+/// #         index, get;
+/// #         api
+/// #     }
+/// # }
+/// # pub fn main() {
+/// assert_eq!(urls::index, "/");
+/// assert_eq!(
+///     urls::api(&api::urls::say_hello(["Gooxey's ESP/32"])),
+///     "/api/say_hello/Gooxey%27s%20ESP%2F32"
+/// );
+/// # }
+/// # }
+/// # fn main() {
+/// #     frontend::main();
+/// # }
+/// ```
+///
+/// # Strict mode
+///
+/// By default, this macro is lenient: a group without any routes compiles into an empty router, and a module missing its route function
+/// only fails where the router gets assembled. Adding the `#![strict]` flag at the start of the macro turns these cases into targeted
+/// compiler errors naming the offending group or route:
+/// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     #![strict]
+///     api {
+///         // `say_hello.rs` has to export a `pub fn say_hello` or `pub async fn say_hello`
+///         say_hello, get, ":caller"
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Public handlers
+///
+/// The modules of routes and groups are private to the module of the macro. Adding the `#![pub_handlers]` flag re-exports the router
+/// function of every nested group next to the router function of the macro, e.g. `pub use api::api;`, so other modules can build the
+/// router of a single group without reaching through the module path:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
+/// use goohttp::router;
+///
+/// router! {
+///     #![pub_handlers]
+///     serve_frontend {
+///         index, get;
+///         api
+///     }
+/// }
+/// # }
+/// # fn main() {
+///
+/// // e.g. in `src/main.rs`, to serve only the API on a second port
+/// let api_router = frontend::api();
+/// # }
+/// ```
+///
+/// # Resources
+///
+/// Groups of routes creating, reading, updating, and deleting the same kind of item can be declared in one line with `resource name`.
+/// This expects a module `name` exporting the functions behind the following routes:
+///
+/// | Function  | Route                 |
+/// |-----------|-----------------------|
+/// | `index`   | `GET /name`           |
+/// | `show`    | `GET /name/:id`       |
+/// | `create`  | `POST /name`          |
+/// | `update`  | `PUT /name/:id`       |
+/// | `destroy` | `DELETE /name/:id`    |
+///
+/// Adding `only(...)` limits the resource to the listed functions. The paths of these routes are available in `urls::name`:
+/// ```
+/// # #[path = "../tests/router_macro/docs"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         resource mcservers;
+///         // `logs.rs` only needs to export `index` and `show`
+///         resource logs only(index, show)
+///     }
+/// }
+/// # pub fn main() {
+///
+/// assert_eq!(urls::mcservers::update(["lobby"]), "/mcservers/lobby");
+/// # }
+/// # }
+/// # fn main() {
+/// #     api::main();
+/// # }
+/// ```
+///
+/// # Guards
+///
+/// Routes can be guarded by adding `guard = my_guard` after their parameters, where `my_guard` is an
+/// `async fn(&Request<Body>) -> Result<(), StatusCode>`. A guard runs before the route function and answers the request with the returned
+/// status code if it fails. Multiple guards get checked in the order they were declared:
+/// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
+/// use goohttp::{
+///     axum::{
+///         body::Body,
+///         http::{
+///             Request,
+///             StatusCode,
+///         },
+///     },
+///     router,
+/// };
+///
+/// router! {
+///     api {
+///         factory_reset, post, guard = authenticated, guard = admin_only
+///     }
+/// }
+///
+/// async fn authenticated(request: &Request<Body>) -> Result<(), StatusCode> {
+///     // ...
+/// #     Ok(())
+/// }
+///
+/// async fn admin_only(request: &Request<Body>) -> Result<(), StatusCode> {
+///     // ...
+/// #     Ok(())
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Body limits
+///
+/// The size of the request bodies a route accepts can be limited by adding `limit = bytes` after its parameters. This limit overrides the
+/// default one of the router, like the one set by the [`HttpServer`](crate::http_server::HttpServer), and gets enforced by extractors like
+/// `String`, `Bytes`, or `Json`, which reject larger bodies with `413 Payload Too Large`:
+/// ```
+/// # #[path = "../tests/router_macro/docs"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         // firmware images are a lot larger than the requests of other routes
+///         upload_firmware, post, limit = 4 * 1024 * 1024
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Timeouts
+///
+/// Routes which have to answer quickly, or which are known to be slow, can get a timeout of their own by adding `timeout = 500ms` or
+/// `timeout = 2s` after their parameters. A route not answering in time gets cancelled, and the request gets answered with
+/// `503 Service Unavailable` instead. Invalid timeouts fail the build. The timeout is kept by `tokio`, which the crate using this macro
+/// has to depend on with its `time` feature:
+/// ```
+/// # #[path = "../tests/router_macro/docs"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         get_sensor, get, timeout = 500ms;
+///         export_history, get, timeout = 10s
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Content types
+///
+/// Routes only accepting bodies of certain media types can list them by adding `accept = [...]` after their parameters. Requests with a
+/// different `Content-Type`, or none at all, get rejected with `415 Unsupported Media Type` before the body is read:
+/// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         update_settings, post, accept = ["application/json"]
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Initialization
+///
+/// Routes needing some setup, like loading a config file, can get it done by adding `init = || async { ... }` after their parameters. The
+/// closure gets called by the first request reaching the route, and every request gets a clone of its result, which the route can extract
+/// as an [`Extension`](axum::Extension). Requests arriving while the value gets created wait for it instead of calling the closure again.
+/// Every call of the router function creates the value anew:
+/// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
+/// # use report::ReportHandler;
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         // `report` can extract `Extension<ReportHandler>`
+///         report, get, init = || async { ReportHandler::new().await }
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Extensions
+///
+/// Values every route of a group needs, like a database pool, can be shared as [`Extension`](axum::Extension)s by adding
+/// `#[extension(Type, ...)]` in front of the name of the router. The router function then adds the default value of every listed type,
+/// while the generated `with_extensions` function takes the values to add in the order they were listed. Groups nested in another router
+/// always get the default values:
+/// ```
+/// # #[path = "../tests/router_macro/docs"]
+/// # mod api {
+/// # #[derive(Clone, Default)]
+/// # struct DbPool;
+/// # impl DbPool {
+/// #     fn connect() -> Self {
+/// #         Self
+/// #     }
+/// # }
+/// # #[derive(Clone, Default)]
+/// # struct LogSink;
+/// use goohttp::router;
+///
+/// router! {
+///     #[extension(DbPool, LogSink)]
+///     api {
+///         // `get_user` can extract `Extension<DbPool>` and `Extension<LogSink>`
+///         get_user, get, ":id"
+///     }
+/// }
+/// # pub fn main() {
+///
+/// let router = with_extensions(DbPool::connect(), LogSink::default());
+/// # }
+/// # }
+/// # fn main() {
+/// #     api::main();
+/// # }
+/// ```
+///
+/// # Choosing the axum crate
+///
+/// By default, the generated code uses the version of axum re-exported by this crate. If your crate depends on axum itself, the routers
+/// of both versions cannot be mixed. The `#![use_axum = path]` flag makes the generated code use the axum crate at the given path instead.
+/// Since the path is used inside of the generated modules as well, it has to be absolute. Nothing of axum gets imported into the module of
+/// the macro, so items named like the methods of a route, like `get`, do not collide with it:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod website {
+/// use goohttp::router;
+///
+/// router! {
+///     #![use_axum = ::axum]
+///     website {
+///         index, get
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// # Route table
+///
+/// Next to the router function, this macro generates a `router_routes` function listing the method and path of every route of the group,
+/// including the routes of the groups nested in it, as [`RouteInfo`](crate::RouteInfo)s:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod serve_frontend {
+/// # goohttp::router! {
+/// #     serve_frontend {
+/// #         index, get;
+/// #         api
+/// #     }
+/// # }
+/// # }
+/// # fn main() {
+/// for route in serve_frontend::router_routes() {
+///     println!("{} {}", route.method, route.path); // e.g. `GET /api/say_hello/:caller`
+/// }
+/// # }
+/// ```
+///
+/// Doc comments of routes and resources become the [`description`](crate::RouteInfo::description) of their routes, which allows a device
+/// to describe its own API, e.g. in a route listing all others. Doc comments of groups are ignored:
+/// ```
+/// # #[path = "../tests/router_macro/api"]
+/// # mod api {
+/// use goohttp::router;
+///
+/// router! {
+///     api {
+///         /// Greet the caller.
+///         /// The greeting is in English.
+///         say_hello, get, ":caller"
+///     }
+/// }
+/// # }
+/// # fn main() {
+///
+/// let descriptions: Vec<_> = api::router_routes().into_iter().map(|route| route.description).collect();
+/// assert_eq!(descriptions, [Some("Greet the caller.\nThe greeting is in English.".to_string())]);
+/// # }
+/// ```
+///
+/// # Smoke tests
+///
+/// Adding the `#![generate_smoke_tests]` flag generates a `#[tokio::test]` calling every route of the route table through the router of
+/// this macro, with every parameter set to `1`. It fails if a route panics or answers with a server error. Routes and groups which
+/// cannot be called this way can be excluded by marking them with `#[skip_smoke_test]`:
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
+/// use goohttp::router;
+///
+/// router! {
+///     #![generate_smoke_tests]
+///     serve_frontend {
+///         index, get;
+///         // `crash` panics on purpose
+///         #[skip_smoke_test]
+///         crash, get;
+///         api
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// Adding the `#![with_tests]` flag generates a `#[cfg(test)]` module with one test per route group. Each of these tests sends a `GET`
+/// request for the path the group is nested at through the router of this macro and fails if the router panics or answers with a server
+/// error. Since these tests drive the router on the test thread itself, handlers relying on a tokio runtime cannot be tested this way.
+/// ```
+/// # #[path = "../tests/router_macro"]
+/// # mod frontend {
+/// use goohttp::router;
+///
+/// router! {
+///     #![with_tests]
+///     serve_frontend {
+///         index, get;
+///         // generates the test `__goohttp_tests::api` requesting `/api`
+///         api
+///     }
+/// }
+/// # }
+/// # fn main() {}
+/// ```
 #[macro_export]
 macro_rules! router {
     {
+        $( #![ $( $flag:tt ) * ] ) *
+        $( #[extension $extensions:tt] ) *
         $group_id:ident {
-            $ (
-                $route:ident
-                $ (
-                    ,
-                    $request_type:ident
-                    $(
-                        ,
-                        $parameter:literal
-                    ) *
-                ) ?
-            ); *
-            $( ; )?
-        }
-    } => {
-        use $crate::axum::{
-            Router,
-            routing::*
-        };
-        $ (
-            mod $route;
-        ) *
-
-        pub fn $group_id() -> Router {
-            let mut router = Router::new();
-            $ (
-                // Dynamically generate either an actual route or a group of routes using the hidden patterns of this macro.
-                router = $crate::__router_internally! {
-                    router;
-                    $route
-                    $ (
-                        ,
-                        $request_type
-                        $ (
-                            ,
-                            $parameter
-                        ) *
-                    ) ?
-                };
-            ) *
-            router
+            $( $entries:tt ) *
+        }
+    } => {
+        $crate::__router_internally! {
+            @parse [ $( [ $( $flag ) * ] ) * $( [ extension $extensions ] ) * ] $group_id [] []
+            $( $entries ) *
         }
     };
 }
+
+/// Compose the given [`Layer`](tower_layer::Layer)s into a single one, which can be passed to [`Router::layer`](axum::Router::layer) or
+/// used like any other layer. \
+/// The first layer given is the outermost one: it sees the request first and the response last, just like the first layer added to a
+/// `tower::ServiceBuilder`. Without any layers, the [`Identity`](tower_layer::Identity) layer is returned.
+///
+/// ```
+/// use goohttp::{
+///     axum::{
+///         routing::get,
+///         Router,
+///     },
+///     middleware::vary::VaryLayer,
+///     middleware_stack,
+/// };
+///
+/// let router: Router = Router::new()
+///     .route("/", get(|| async { "index" }))
+///     // the same as `.layer(VaryLayer(vec!["Accept-Language"])).layer(VaryLayer(vec!["Accept-Encoding"]))`
+///     .layer(middleware_stack!(
+///         VaryLayer(vec!["Accept-Encoding"]),
+///         VaryLayer(vec!["Accept-Language"]),
+///     ));
+/// ```
+#[macro_export]
+macro_rules! middleware_stack {
+    () => {
+        $crate::__private::Identity::new()
+    };
+    ( $layer:expr $( , ) ? ) => {
+        $layer
+    };
+    ( $layer:expr, $( $rest:expr ), + $( , ) ? ) => {
+        $crate::__private::Stack::new($crate::middleware_stack!( $( $rest ), + ), $layer)
+    };
+}
@@ -0,0 +1,435 @@
+//! This module provides the [`MetricsBackend`] trait, which allows to collect metrics about the requests and connections of an
+//! [`HttpServer`](crate::http_server::HttpServer) with any metrics system.
+//!
+//! ```
+//! # #[cfg(feature = "prometheus")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # use std::sync::Arc;
+//! # use goohttp::{
+//! #     http_server::HttpServer,
+//! #     metrics::PrometheusMetrics,
+//! # };
+//! let metrics = Arc::new(PrometheusMetrics::new());
+//! let http_server = HttpServer::bind("0.0.0.0:80", None, None)?.metrics_backend(metrics.clone());
+//!
+//! // e.g. in a `/metrics` route
+//! let exposition = metrics.render();
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "prometheus"))]
+//! # fn main() {}
+//! ```
+//!
+//! To break the requests down by their route, the [`RouteTable`] built from the `router_routes` function of the
+//! [`router`](crate::router) macro can be passed to both the HttpServer and the backend:
+//!
+//! ```
+//! # #[path = "../tests/router_macro"]
+//! # mod serve_frontend {
+//! # goohttp::router! {
+//! #     serve_frontend {
+//! #         index, get;
+//! #         api
+//! #     }
+//! # }
+//! # }
+//! # #[cfg(feature = "prometheus")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # use std::sync::Arc;
+//! # use goohttp::{
+//! #     http_server::HttpServer,
+//! #     metrics::{
+//! #         PrometheusMetrics,
+//! #         RouteTable,
+//! #     },
+//! # };
+//! let routes = RouteTable::new(serve_frontend::router_routes());
+//! let metrics = Arc::new(PrometheusMetrics::with_routes(&routes));
+//! let http_server = HttpServer::bind("0.0.0.0:80", None, None)?
+//!     .route_table(routes)
+//!     .metrics_backend(metrics.clone());
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "prometheus"))]
+//! # fn main() {}
+//! ```
+
+#[cfg(feature = "prometheus")]
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{
+            AtomicI64,
+            AtomicU64,
+            Ordering,
+        },
+        Mutex,
+        PoisonError,
+    },
+};
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use http::{
+    Method,
+    StatusCode,
+};
+
+use crate::RouteInfo;
+
+/// Receives the events of an [`HttpServer`](crate::http_server::HttpServer) needed for metrics, like the number of requests, their
+/// duration, and the number of open connections. \
+/// Every method does nothing by default, so a backend only has to implement the events it is interested in. The methods get called by
+/// the tasks answering the connections, so they should return quickly and must not block.
+pub trait MetricsBackend: Send + Sync + 'static {
+    /// A request with the given method and path was received, and is about to be answered.
+    fn on_request_start(&self, method: &Method, path: &str) {
+        let _ = (method, path);
+    }
+
+    /// The response to a request was written. `duration` is the time since the request was received, and `bytes` the size of the body
+    /// of the response. \
+    /// Requests whose response could not be written, e.g. because the client disconnected, do not end with this call.
+    fn on_request_end(&self, status: StatusCode, duration: Duration, bytes: usize) {
+        let _ = (status, duration, bytes);
+    }
+
+    /// The response to a request was written by an HttpServer with a [`route_table`](crate::http_server::HttpServer::route_table). `route`
+    /// is the id of the route matching the request in this [`RouteTable`], or `None` if no route matched. \
+    /// This method gets called right after [`on_request_end`](Self::on_request_end).
+    fn on_route_end(&self, route: Option<usize>, status: StatusCode, duration: Duration) {
+        let _ = (route, status, duration);
+    }
+
+    /// The number of open connections changed by the given amount. It is `1` once a connection was accepted, and `-1` once it was
+    /// closed.
+    fn on_connection_change(&self, delta: i64) {
+        let _ = delta;
+    }
+}
+impl<T: MetricsBackend + ?Sized> MetricsBackend for Arc<T> {
+    fn on_request_start(&self, method: &Method, path: &str) {
+        (**self).on_request_start(method, path);
+    }
+
+    fn on_request_end(&self, status: StatusCode, duration: Duration, bytes: usize) {
+        (**self).on_request_end(status, duration, bytes);
+    }
+
+    fn on_route_end(&self, route: Option<usize>, status: StatusCode, duration: Duration) {
+        (**self).on_route_end(route, status, duration);
+    }
+
+    fn on_connection_change(&self, delta: i64) {
+        (**self).on_connection_change(delta);
+    }
+}
+impl<T: MetricsBackend + ?Sized> MetricsBackend for Box<T> {
+    fn on_request_start(&self, method: &Method, path: &str) {
+        (**self).on_request_start(method, path);
+    }
+
+    fn on_request_end(&self, status: StatusCode, duration: Duration, bytes: usize) {
+        (**self).on_request_end(status, duration, bytes);
+    }
+
+    fn on_route_end(&self, route: Option<usize>, status: StatusCode, duration: Duration) {
+        (**self).on_route_end(route, status, duration);
+    }
+
+    fn on_connection_change(&self, delta: i64) {
+        (**self).on_connection_change(delta);
+    }
+}
+
+/// A [`MetricsBackend`] ignoring every event. This is the backend of every
+/// [`HttpServer`](crate::http_server::HttpServer) without a [`metrics_backend`](crate::http_server::HttpServer::metrics_backend).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopMetrics;
+impl MetricsBackend for NoopMetrics {}
+
+/// The routes of a router, like the ones listed by the `router_routes` function of the [`router`](crate::router) macro, which an
+/// [`HttpServer`](crate::http_server::HttpServer) with a [`route_table`](crate::http_server::HttpServer::route_table) matches every
+/// request against. \
+/// The id of a route is its position in this table, which allows backends to keep the metrics of every route in a fixed array, instead of
+/// a map keyed by the paths of the requests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteTable {
+    /// The routes of this table, ordered by their id.
+    routes: Vec<RouteInfo>,
+}
+impl RouteTable {
+    /// Create a new RouteTable of the given routes.
+    pub fn new(routes: impl IntoIterator<Item = RouteInfo>) -> Self {
+        Self {
+            routes: routes.into_iter().collect(),
+        }
+    }
+
+    /// Get the routes of this table, ordered by their id.
+    pub fn routes(&self) -> &[RouteInfo] {
+        &self.routes
+    }
+
+    /// Get the id of the first route matching the given method and path. `HEAD` requests match `GET` routes as well. \
+    /// Parameters like `:caller` match any single segment of the path, and wildcards like `*rest` match the rest of it.
+    pub fn find(&self, method: &Method, path: &str) -> Option<usize> {
+        let matches_method = |route: &RouteInfo| {
+            route.method == method.as_str() || (method == Method::HEAD && route.method == "GET")
+        };
+        self.routes
+            .iter()
+            .position(|route| matches_method(route) && matches_path(&route.path, path))
+    }
+
+    /// Get the methods of the routes matching the given path, in the order of their routes. `HEAD` follows `GET`, since `HEAD` requests
+    /// get answered by `GET` routes.
+    pub fn methods(&self, path: &str) -> Vec<&str> {
+        let mut methods = vec![];
+        for route in &self.routes {
+            if !matches_path(&route.path, path) {
+                continue;
+            }
+            for method in [route.method.as_str()]
+                .into_iter()
+                .chain((route.method == "GET").then_some("HEAD"))
+            {
+                if !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+        methods
+    }
+}
+
+/// Check whether the given path matches the given pattern of a route.
+fn matches_path(pattern: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    for expected in pattern.split('/') {
+        if expected.starts_with('*') {
+            return segments.any(|segment| !segment.is_empty());
+        }
+        match segments.next() {
+            Some(segment) if expected.starts_with(':') && !segment.is_empty() => {}
+            Some(segment) if segment == expected => {}
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+/// A [`MetricsBackend`] keeping its metrics in memory, and rendering them in the text format of Prometheus with
+/// [`render`](Self::render). \
+/// Requests are only counted by their method and status code. Their paths are left out, since every distinct path would add another time
+/// series, and methods other than the nine standard ones get counted as `other` for the same reason. A backend created
+/// [`with_routes`](Self::with_routes) counts them by the pattern of their route as well, like `/api/say_hello/:caller`, which adds one
+/// time series per route, and one for the requests not matching any route.
+#[cfg_attr(docsrs, doc(cfg(feature = "prometheus")))]
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    /// The number of received requests by their method. See [`method_label`].
+    requests: Mutex<BTreeMap<&'static str, u64>>,
+    /// The number of written responses by their status code.
+    responses: Mutex<BTreeMap<u16, u64>>,
+    /// The total time spent answering requests in nanoseconds.
+    duration_nanos: AtomicU64,
+    /// The total size of all response bodies.
+    body_bytes: AtomicU64,
+    /// The number of open connections.
+    connections: AtomicI64,
+    /// The metrics of every route indexed by its id, followed by the ones of the requests not matching any route. See
+    /// [`with_routes`](Self::with_routes).
+    routes: Box<[RouteMetrics]>,
+}
+#[cfg(feature = "prometheus")]
+impl PrometheusMetrics {
+    /// Create a new PrometheusMetrics without any recorded event.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new PrometheusMetrics counting the requests by the given routes as well. The HttpServer reporting to this backend has to
+    /// use the same [`route_table`](crate::http_server::HttpServer::route_table), since routes are only reported by their id.
+    pub fn with_routes(routes: &RouteTable) -> Self {
+        let labels = routes
+            .routes()
+            .iter()
+            .map(|route| format!("method=\"{}\",route=\"{}\"", route.method, route.path))
+            .chain([r#"route="unmatched""#.to_string()]);
+        Self {
+            routes: labels
+                .map(|labels| RouteMetrics {
+                    labels,
+                    ..RouteMetrics::default()
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Render the collected metrics in the text exposition format of Prometheus. A route serving them should use the content type
+    /// `text/plain; version=0.0.4`.
+    pub fn render(&self) -> String {
+        let requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let responses = self
+            .responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let mut exposition = String::new();
+        // writing to a string cannot fail
+        let _ = writeln!(
+            exposition,
+            "# HELP goohttp_requests_total The number of received requests.\n# TYPE goohttp_requests_total counter"
+        );
+        for (method, count) in &requests {
+            let _ = writeln!(
+                exposition,
+                "goohttp_requests_total{{method=\"{method}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            exposition,
+            "# HELP goohttp_responses_total The number of written responses.\n# TYPE goohttp_responses_total counter"
+        );
+        for (status, count) in &responses {
+            let _ = writeln!(
+                exposition,
+                "goohttp_responses_total{{status=\"{status}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            exposition,
+            "# HELP goohttp_request_duration_seconds The time spent answering requests.\n# TYPE goohttp_request_duration_seconds summary\n\
+             goohttp_request_duration_seconds_sum {}\ngoohttp_request_duration_seconds_count {}",
+            Duration::from_nanos(self.duration_nanos.load(Ordering::SeqCst)).as_secs_f64(),
+            responses.values().sum::<u64>()
+        );
+        let _ = writeln!(
+            exposition,
+            "# HELP goohttp_response_body_bytes_total The total size of all response bodies.\n\
+             # TYPE goohttp_response_body_bytes_total counter\ngoohttp_response_body_bytes_total {}",
+            self.body_bytes.load(Ordering::SeqCst)
+        );
+        let _ = writeln!(
+            exposition,
+            "# HELP goohttp_open_connections The number of open connections.\n# TYPE goohttp_open_connections gauge\n\
+             goohttp_open_connections {}",
+            self.connections.load(Ordering::SeqCst)
+        );
+        if !self.routes.is_empty() {
+            let _ = writeln!(
+                exposition,
+                "# HELP goohttp_route_requests_total The number of answered requests by their route.\n\
+                 # TYPE goohttp_route_requests_total counter"
+            );
+            for route in self.routes.iter() {
+                let _ = writeln!(
+                    exposition,
+                    "goohttp_route_requests_total{{{}}} {}",
+                    route.labels,
+                    route.requests.load(Ordering::SeqCst)
+                );
+            }
+            let _ = writeln!(
+                exposition,
+                "# HELP goohttp_route_request_duration_seconds The time spent answering requests by their route.\n\
+                 # TYPE goohttp_route_request_duration_seconds summary"
+            );
+            for route in self.routes.iter() {
+                let _ = writeln!(
+                    exposition,
+                    "goohttp_route_request_duration_seconds_sum{{{labels}}} {}\n\
+                     goohttp_route_request_duration_seconds_count{{{labels}}} {}",
+                    Duration::from_nanos(route.duration_nanos.load(Ordering::SeqCst)).as_secs_f64(),
+                    route.requests.load(Ordering::SeqCst),
+                    labels = route.labels
+                );
+            }
+        }
+        exposition
+    }
+}
+#[cfg(feature = "prometheus")]
+impl MetricsBackend for PrometheusMetrics {
+    fn on_request_start(&self, method: &Method, _path: &str) {
+        *self
+            .requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(method_label(method))
+            .or_default() += 1;
+    }
+
+    fn on_request_end(&self, status: StatusCode, duration: Duration, bytes: usize) {
+        *self
+            .responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(status.as_u16())
+            .or_default() += 1;
+        self.duration_nanos.fetch_add(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::SeqCst,
+        );
+        self.body_bytes.fetch_add(bytes as u64, Ordering::SeqCst);
+    }
+
+    fn on_route_end(&self, route: Option<usize>, _status: StatusCode, duration: Duration) {
+        // routes outside of the table count as unmatched
+        let Some(unmatched) = self.routes.last() else {
+            return;
+        };
+        let metrics = route
+            .and_then(|route| self.routes.get(..self.routes.len() - 1)?.get(route))
+            .unwrap_or(unmatched);
+        metrics.requests.fetch_add(1, Ordering::SeqCst);
+        metrics.duration_nanos.fetch_add(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::SeqCst,
+        );
+    }
+
+    fn on_connection_change(&self, delta: i64) {
+        self.connections.fetch_add(delta, Ordering::SeqCst);
+    }
+}
+
+/// The methods of [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-9) and [RFC 5789](https://www.rfc-editor.org/rfc/rfc5789),
+/// which a [`PrometheusMetrics`] counts separately.
+#[cfg(feature = "prometheus")]
+const STANDARD_METHODS: [&str; 9] = [
+    "CONNECT", "DELETE", "GET", "HEAD", "OPTIONS", "PATCH", "POST", "PUT", "TRACE",
+];
+
+/// Get the label a [`PrometheusMetrics`] counts a request with the given method under. Clients can send any method they make up, which
+/// would add a time series each, so the ones not in [`STANDARD_METHODS`] share the label `other`.
+#[cfg(feature = "prometheus")]
+fn method_label(method: &Method) -> &'static str {
+    STANDARD_METHODS
+        .into_iter()
+        .find(|standard| *standard == method.as_str())
+        .unwrap_or("other")
+}
+
+/// The metrics a [`PrometheusMetrics`] keeps about a single route.
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+struct RouteMetrics {
+    /// The labels of the time series of this route, like `method="GET",route="/"`.
+    labels: String,
+    /// The number of answered requests.
+    requests: AtomicU64,
+    /// The total time spent answering requests in nanoseconds.
+    duration_nanos: AtomicU64,
+}
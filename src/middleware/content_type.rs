@@ -0,0 +1,177 @@
+//! This module provides the [`ContentTypeGuessLayer`], which adds a `Content-Type` to responses sent without one.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::{
+    body::{
+        boxed,
+        BoxBody,
+        Bytes,
+        Full,
+        HttpBody,
+    },
+    http::{
+        header::CONTENT_TYPE,
+        HeaderMap,
+        HeaderValue,
+        Request,
+        Response,
+        StatusCode,
+    },
+    BoxError,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The number of bytes at the start of a body the content type gets guessed from.
+const SNIFFED_BYTES: usize = 512;
+
+/// Guesses the content type of responses without a `Content-Type` header from the first 512 bytes of their body, and adds it to them. \
+/// Bodies starting with `{` or `[` are sent as `application/json`, ones starting with `<?xml` as `application/xml`, and ones starting
+/// with `<!DOCTYPE` or `<html` as `text/html; charset=utf-8`. Whitespace in front of them is ignored. Every other body is sent as
+/// `application/octet-stream`, which makes browsers download it instead of guessing themselves. Responses with an empty body are left
+/// as they are.
+///
+/// Only the start of the body gets buffered, so streamed responses keep being streamed. If it cannot be read, the response gets replaced
+/// with an empty `500 Internal Server Error`.
+///
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     middleware::content_type::ContentTypeGuessLayer,
+/// };
+///
+/// let router: Router = Router::new().layer(ContentTypeGuessLayer);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ContentTypeGuessLayer;
+impl<S> Layer<S> for ContentTypeGuessLayer {
+    type Service = ContentTypeGuess<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ContentTypeGuess { inner }
+    }
+}
+
+/// The service created by the [`ContentTypeGuessLayer`].
+#[derive(Clone, Debug)]
+pub struct ContentTypeGuess<S> {
+    /// The service answering the requests.
+    inner: S,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for ContentTypeGuess<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+    ResponseBody: HttpBody<Data = Bytes> + Send + Unpin + 'static,
+    ResponseBody::Error: Into<BoxError>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            if response.headers().contains_key(CONTENT_TYPE) {
+                return Ok(response.map(boxed));
+            }
+
+            let (mut parts, mut body) = response.into_parts();
+            let mut start = vec![];
+            while start.len() < SNIFFED_BYTES {
+                match body.data().await {
+                    Some(Ok(chunk)) => start.extend_from_slice(&chunk),
+                    Some(Err(_)) => {
+                        let mut response = Response::new(boxed(Full::default()));
+                        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                        return Ok(response);
+                    }
+                    None => break,
+                }
+            }
+
+            if !start.is_empty() {
+                parts
+                    .headers
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(guess(&start)));
+            }
+            let body = Prefixed {
+                prefix: Some(Bytes::from(start)).filter(|start| !start.is_empty()),
+                body,
+            };
+            Ok(Response::from_parts(parts, boxed(body)))
+        })
+    }
+}
+
+/// Guess the content type of a body starting with the given bytes.
+fn guess(start: &[u8]) -> &'static str {
+    let start = &start[start
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(start.len())..];
+    let starts_with = |prefix: &[u8]| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+
+    if starts_with(b"{") || starts_with(b"[") {
+        "application/json"
+    } else if starts_with(b"<?xml") {
+        "application/xml"
+    } else if starts_with(b"<!doctype") || starts_with(b"<html") {
+        "text/html; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// A body, whose start was already read, and gets sent ahead of the rest of it.
+struct Prefixed<B> {
+    /// The start of the body, until it was sent.
+    prefix: Option<Bytes>,
+    /// The rest of the body.
+    body: B,
+}
+impl<B> HttpBody for Prefixed<B>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        if let Some(prefix) = self.prefix.take() {
+            return Poll::Ready(Some(Ok(prefix)));
+        }
+        Pin::new(&mut self.body).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.body).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.prefix.is_none() && self.body.is_end_stream()
+    }
+}
@@ -0,0 +1,125 @@
+//! This module provides the [`DnsRebindProtectionLayer`], which rejects requests sent to a host name the server does not know as its own.
+
+use std::{
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::http::{
+    header::HOST,
+    Request,
+    Response,
+    StatusCode,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Rejects requests whose `Host` header names neither one of the allowed hosts, `localhost`, nor an IP address with
+/// `403 Forbidden`. \
+/// In a DNS rebinding attack, a website lets its own host name resolve to the address of a device in the local network of a visitor.
+/// The browser then sends the requests of the website to the device, and lets the website read the responses, since they come from the
+/// same host name. The `Host` header of these requests still carries the host name of the attacker, which gives them away.
+///
+/// Host names are compared without their port and case, and a trailing dot is ignored. Requests without a `Host` header, which browsers
+/// always send, get answered as usual.
+///
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     middleware::dns_rebind::DnsRebindProtectionLayer,
+/// };
+///
+/// // `http://192.168.4.1` and `http://localhost` are allowed as well
+/// let router: Router = Router::new().layer(DnsRebindProtectionLayer {
+///     allowed_hosts: vec!["thermostat.local".to_string()],
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DnsRebindProtectionLayer {
+    /// The host names the server can be reached by, in addition to `localhost` and its IP addresses.
+    pub allowed_hosts: Vec<String>,
+}
+impl<S> Layer<S> for DnsRebindProtectionLayer {
+    type Service = DnsRebindProtection<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DnsRebindProtection {
+            allowed_hosts: self
+                .allowed_hosts
+                .iter()
+                .map(|host| normalize(host).to_ascii_lowercase())
+                .collect(),
+            inner,
+        }
+    }
+}
+
+/// The service created by the [`DnsRebindProtectionLayer`].
+#[derive(Clone, Debug)]
+pub struct DnsRebindProtection<S> {
+    /// The allowed host names in lowercase, without a trailing dot.
+    allowed_hosts: Arc<[String]>,
+    /// The service answering the requests.
+    inner: S,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for DnsRebindProtection<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+    ResponseBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let allowed = match request.headers().get(HOST) {
+            Some(host) => host
+                .to_str()
+                .is_ok_and(|host| is_allowed(host, &self.allowed_hosts)),
+            None => true,
+        };
+        if !allowed {
+            let mut response = Response::new(ResponseBody::default());
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Check whether the given value of a `Host` header names `localhost`, an IP address, or one of the given allowed hosts.
+fn is_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    // IPv6 addresses are enclosed in brackets, since their colons would be mistaken for the port
+    if let Some(address) = host
+        .strip_prefix('[')
+        .and_then(|host| host.split_once(']'))
+        .map(|(address, _)| address)
+    {
+        return address.parse::<IpAddr>().is_ok();
+    }
+
+    let name = normalize(host.rsplit_once(':').map_or(host, |(name, _)| name));
+    name.parse::<IpAddr>().is_ok()
+        || name.eq_ignore_ascii_case("localhost")
+        || allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+}
+
+/// Remove the surrounding whitespace and the trailing dot of a fully qualified name from the given host name.
+fn normalize(host: &str) -> &str {
+    let host = host.trim();
+    host.strip_suffix('.').unwrap_or(host)
+}
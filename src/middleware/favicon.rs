@@ -0,0 +1,142 @@
+//! This module provides the [`FaviconLayer`], which answers the requests for `/favicon.ico` browsers send on their own.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::{
+    body::{
+        self,
+        Bytes,
+        Full,
+    },
+    http::{
+        header::{
+            CACHE_CONTROL,
+            CONTENT_TYPE,
+        },
+        HeaderValue,
+        Method,
+        Request,
+        StatusCode,
+    },
+    response::Response,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The path browsers request the icon of a website from.
+const FAVICON_PATH: &str = "/favicon.ico";
+/// Browsers may keep the answer for a day, so they do not ask again on every page.
+const CACHE_FOR_A_DAY: HeaderValue = HeaderValue::from_static("public, max-age=86400");
+
+/// Answers `GET` and `HEAD` requests for `/favicon.ico` with the given icon, or with `204 No Content` if there is none. \
+/// Browsers request this path on their own for every website they show. Without a route for it, every page load of a device's web
+/// interface ends in a `404 Not Found`, which clutters its logs. Both answers may be cached by the browser for a day, so it does not
+/// keep asking.
+///
+/// Requests for other paths, and requests for `/favicon.ico` with other methods, get passed to the router.
+///
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     middleware::favicon::FaviconLayer,
+/// };
+///
+/// // usually `include_bytes!("favicon.ico")`
+/// static ICON: &[u8] = &[0, 0, 1, 0];
+///
+/// let router: Router = Router::new().layer(FaviconLayer::icon(ICON, "image/x-icon"));
+/// // for a device without an icon
+/// let router: Router = Router::new().layer(FaviconLayer::no_content());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FaviconLayer {
+    /// The body and content type of the icon, or `None` to answer with `204 No Content`.
+    icon: Option<Arc<(Bytes, HeaderValue)>>,
+}
+impl FaviconLayer {
+    /// Answer with the given icon, sent with the given content type, like `image/x-icon` or `image/png`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the content type is not a valid header value.
+    pub fn icon(icon: impl Into<Bytes>, content_type: &str) -> Self {
+        let content_type = HeaderValue::from_str(content_type)
+            .unwrap_or_else(|_| panic!("The content type `{content_type}` is invalid."));
+        Self {
+            icon: Some(Arc::new((icon.into(), content_type))),
+        }
+    }
+
+    /// Answer with an empty `204 No Content`, for devices without an icon.
+    pub fn no_content() -> Self {
+        Self { icon: None }
+    }
+}
+impl<S> Layer<S> for FaviconLayer {
+    type Service = Favicon<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Favicon {
+            icon: self.icon.clone(),
+            inner,
+        }
+    }
+}
+
+/// The service created by the [`FaviconLayer`].
+#[derive(Clone, Debug)]
+pub struct Favicon<S> {
+    /// See [`FaviconLayer::icon`].
+    icon: Option<Arc<(Bytes, HeaderValue)>>,
+    /// The service answering every other request.
+    inner: S,
+}
+impl<S, RequestBody> Service<Request<RequestBody>> for Favicon<S>
+where
+    S: Service<Request<RequestBody>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let method = request.method();
+        if request.uri().path() != FAVICON_PATH || (method != Method::GET && method != Method::HEAD)
+        {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let mut response = match &self.icon {
+            Some(icon) => {
+                let (bytes, content_type) = &**icon;
+                let mut response = Response::new(body::boxed(Full::new(bytes.clone())));
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, content_type.clone());
+                response
+            }
+            None => {
+                let mut response = Response::new(body::boxed(body::Empty::new()));
+                *response.status_mut() = StatusCode::NO_CONTENT;
+                response
+            }
+        };
+        response
+            .headers_mut()
+            .insert(CACHE_CONTROL, CACHE_FOR_A_DAY);
+        Box::pin(async { Ok(response) })
+    }
+}
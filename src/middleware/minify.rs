@@ -0,0 +1,203 @@
+//! This module provides the [`HtmlMinifyLayer`], which makes HTML responses smaller.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::{
+    body::{
+        boxed,
+        BoxBody,
+        Bytes,
+        Full,
+        HttpBody,
+    },
+    http::{
+        header::{
+            CONTENT_LENGTH,
+            CONTENT_TYPE,
+        },
+        Request,
+        Response,
+        StatusCode,
+    },
+    BoxError,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The tags whose content gets sent unchanged, since whitespace is meaningful inside of them.
+const PRESERVED_TAGS: [&str; 4] = ["pre", "code", "textarea", "script"];
+/// The tags of inline elements, between which whitespace is displayed as a space.
+const INLINE_TAGS: [&str; 33] = [
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "dfn", "em", "i",
+    "img", "input", "kbd", "label", "mark", "meter", "output", "progress", "q", "s", "samp",
+    "select", "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// Removes the whitespace between the tags of responses with the content type `text/html`. \
+/// Templates are usually indented to be readable, which makes up a noticeable part of the pages a device has to send. The whitespace
+/// inside of `<pre>`, `<code>`, `<textarea>`, and `<script>` tags is kept, since it changes how their content is displayed or run. Between
+/// two inline elements, like in `<b>Hello</b> <i>World</i>`, it gets collapsed to a single space instead, since it is displayed as one.
+///
+/// Since HTML responses get buffered to be minified, this layer should only be used for routes responding with pages of a reasonable size.
+/// If the body of a response cannot be read, it gets replaced with an empty `500 Internal Server Error`.
+///
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     middleware::minify::HtmlMinifyLayer,
+/// };
+///
+/// let router: Router = Router::new().layer(HtmlMinifyLayer);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HtmlMinifyLayer;
+impl<S> Layer<S> for HtmlMinifyLayer {
+    type Service = HtmlMinify<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HtmlMinify { inner }
+    }
+}
+
+/// The service created by the [`HtmlMinifyLayer`].
+#[derive(Clone, Debug)]
+pub struct HtmlMinify<S> {
+    /// The service answering the requests.
+    inner: S,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for HtmlMinify<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+    ResponseBody: HttpBody<Data = Bytes> + Send + Unpin + 'static,
+    ResponseBody::Error: Into<BoxError>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            let is_html = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .is_some_and(|content_type| {
+                    content_type
+                        .get(..9)
+                        .is_some_and(|mime| mime.eq_ignore_ascii_case("text/html"))
+                });
+            if !is_html {
+                return Ok(response.map(boxed));
+            }
+
+            let (mut parts, mut body) = response.into_parts();
+            let mut html = vec![];
+            while let Some(chunk) = body.data().await {
+                let Ok(chunk) = chunk else {
+                    let mut response = Response::new(boxed(Full::default()));
+                    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                    return Ok(response);
+                };
+                html.extend_from_slice(&chunk);
+            }
+
+            let body = match String::from_utf8(html) {
+                Ok(html) => minify(&html).into_bytes(),
+                Err(error) => error.into_bytes(),
+            };
+            parts.headers.remove(CONTENT_LENGTH);
+            Ok(Response::from_parts(parts, boxed(Full::from(body))))
+        })
+    }
+}
+
+/// Remove the whitespace between the tags of the given HTML, except for the content of the [`PRESERVED_TAGS`]. Whitespace between two
+/// [`INLINE_TAGS`] gets collapsed to a single space.
+fn minify(html: &str) -> String {
+    let bytes = html.as_bytes();
+    let mut minified = String::with_capacity(html.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'<' {
+            // text, which is kept as it is
+            let end = find(bytes, i, b"<").unwrap_or(bytes.len());
+            minified.push_str(&html[i..end]);
+            i = end;
+            continue;
+        }
+
+        if let Some(tag) = preserved_tag(&bytes[i..]) {
+            // everything up to the closing tag is kept as it is
+            let closing_tag = format!("</{tag}");
+            let end = find(bytes, i + 1, closing_tag.as_bytes()).unwrap_or(bytes.len());
+            minified.push_str(&html[i..end]);
+            i = end;
+            continue;
+        }
+
+        let start = i;
+        let end = find(bytes, i, b">").map_or(bytes.len(), |end| end + 1);
+        minified.push_str(&html[i..end]);
+        i = end;
+
+        let mut whitespace_end = i;
+        while whitespace_end < bytes.len() && bytes[whitespace_end].is_ascii_whitespace() {
+            whitespace_end += 1;
+        }
+        if whitespace_end > i && bytes.get(whitespace_end) == Some(&b'<') {
+            if is_inline(&bytes[start..]) && is_inline(&bytes[whitespace_end..]) {
+                minified.push(' ');
+            }
+            i = whitespace_end;
+        }
+    }
+
+    minified
+}
+
+/// Get the name of the preserved tag the given HTML starts with.
+fn preserved_tag(html: &[u8]) -> Option<&'static str> {
+    PRESERVED_TAGS.into_iter().find(|tag| {
+        html.get(1..=tag.len())
+            .is_some_and(|name| name.eq_ignore_ascii_case(tag.as_bytes()))
+            && html
+                .get(tag.len() + 1)
+                .is_some_and(|next| *next == b'>' || *next == b'/' || next.is_ascii_whitespace())
+    })
+}
+
+/// Check whether the given HTML starts with an opening or closing tag of one of the [`INLINE_TAGS`].
+fn is_inline(html: &[u8]) -> bool {
+    let name = html.get(1..).unwrap_or_default();
+    let name = name.strip_prefix(b"/").unwrap_or(name);
+    let length = name
+        .iter()
+        .position(|byte| *byte == b'>' || *byte == b'/' || byte.is_ascii_whitespace())
+        .unwrap_or(name.len());
+    INLINE_TAGS
+        .into_iter()
+        .any(|tag| name[..length].eq_ignore_ascii_case(tag.as_bytes()))
+}
+
+/// Find the position of the given ASCII pattern in the given bytes, starting at the given position and ignoring the case of letters.
+fn find(bytes: &[u8], start: usize, pattern: &[u8]) -> Option<usize> {
+    bytes[start..]
+        .windows(pattern.len())
+        .position(|window| window.eq_ignore_ascii_case(pattern))
+        .map(|position| start + position)
+}
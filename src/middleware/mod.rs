@@ -0,0 +1,11 @@
+//! This module provides [`Layer`](tower_layer::Layer)s which can be added to an [`axum Router`](axum::Router) to change the responses of its
+//! routes.
+
+pub mod content_type;
+pub mod dns_rebind;
+pub mod favicon;
+#[cfg_attr(docsrs, doc(cfg(feature = "minify")))]
+#[cfg(feature = "minify")]
+pub mod minify;
+pub mod throttle;
+pub mod vary;
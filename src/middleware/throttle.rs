@@ -0,0 +1,266 @@
+//! This module provides the [`PriorityThrottleLayer`], which limits the rate of requests separately for each [`Priority`].
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc,
+        Mutex,
+        PoisonError,
+    },
+    task::{
+        Context,
+        Poll,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use axum::http::{
+    header::RETRY_AFTER,
+    request::Parts,
+    HeaderValue,
+    Request,
+    Response,
+    StatusCode,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The priority of a request, which decides the limit it counts against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Requests which may wait, like bulk uploads or downloads of logs.
+    Low,
+    /// Every request not matched otherwise.
+    Normal,
+    /// Requests which must get through even while the others are throttled, like health checks or emergency commands.
+    High,
+}
+
+/// The number of requests allowed within a period. \
+/// Up to `requests` requests are allowed at once, after which a new one gets allowed every `period / requests`. A zero `period` does not
+/// limit anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rate {
+    /// The number of requests allowed within the period.
+    pub requests: u32,
+    /// The time it takes until all requests are allowed again.
+    pub period: Duration,
+}
+
+/// The settings of a [`PriorityThrottleLayer`]: a function assigning a [`Priority`] to every request, and the [`Rate`] of each priority.
+/// Priorities without a rate are not limited.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use goohttp::middleware::throttle::{
+///     Priority,
+///     PriorityThrottleConfig,
+///     Rate,
+/// };
+///
+/// let config = PriorityThrottleConfig::new(|request| match request.uri.path() {
+///     "/health" | "/emergency-stop" => Priority::High,
+///     path if path.starts_with("/logs") => Priority::Low,
+///     _ => Priority::Normal,
+/// })
+/// .limit(
+///     Priority::Normal,
+///     Rate {
+///         requests: 20,
+///         period: Duration::from_secs(1),
+///     },
+/// )
+/// .limit(
+///     Priority::Low,
+///     Rate {
+///         requests: 2,
+///         period: Duration::from_secs(1),
+///     },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct PriorityThrottleConfig {
+    /// Assigns the priority of every request.
+    matcher: Arc<dyn Fn(&Parts) -> Priority + Send + Sync>,
+    /// The rate of every priority, indexed by [`index`].
+    rates: [Option<Rate>; 3],
+}
+impl PriorityThrottleConfig {
+    /// Create a new PriorityThrottleConfig assigning priorities with the given function, without limiting any of them yet.
+    pub fn new(matcher: impl Fn(&Parts) -> Priority + Send + Sync + 'static) -> Self {
+        Self {
+            matcher: Arc::new(matcher),
+            rates: [None; 3],
+        }
+    }
+
+    /// Limit the requests of the given priority to the given rate.
+    pub fn limit(mut self, priority: Priority, rate: Rate) -> Self {
+        self.rates[index(priority)] = Some(rate);
+        self
+    }
+}
+impl fmt::Debug for PriorityThrottleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityThrottleConfig")
+            .field("rates", &self.rates)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Limits the rate of requests separately for each [`Priority`], as configured by a [`PriorityThrottleConfig`]. \
+/// Every priority has its own token bucket, so requests of a high priority still get answered while the limit of a lower one is
+/// exhausted. Throttled requests get answered with an empty `429 Too Many Requests`, whose `Retry-After` header tells the client how many
+/// seconds to wait.
+///
+/// The limits are shared by every service created by this layer and its clones, so they apply to the whole router. Use
+/// [`HttpServer::priority_throttle`](crate::http_server::HttpServer::priority_throttle) to apply them to every request of an HttpServer.
+#[derive(Clone, Debug)]
+pub struct PriorityThrottleLayer {
+    /// The settings and the token buckets.
+    state: Arc<ThrottleState>,
+}
+impl PriorityThrottleLayer {
+    /// Create a new PriorityThrottleLayer with the given settings.
+    pub fn new(config: PriorityThrottleConfig) -> Self {
+        let now = Instant::now();
+        let buckets = config.rates.map(|rate| {
+            rate.map(|rate| Bucket {
+                rate,
+                tokens: f64::from(rate.requests),
+                updated: now,
+            })
+        });
+        Self {
+            state: Arc::new(ThrottleState {
+                buckets: Mutex::new(buckets),
+                config,
+            }),
+        }
+    }
+}
+impl<S> Layer<S> for PriorityThrottleLayer {
+    type Service = PriorityThrottle<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PriorityThrottle {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// The service created by the [`PriorityThrottleLayer`].
+#[derive(Clone, Debug)]
+pub struct PriorityThrottle<S> {
+    /// The service answering the requests.
+    inner: S,
+    /// The settings and the token buckets.
+    state: Arc<ThrottleState>,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for PriorityThrottle<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+    ResponseBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let (parts, body) = request.into_parts();
+        let priority = (self.state.config.matcher)(&parts);
+
+        if let Err(retry_after) = self.state.take(priority) {
+            let mut response = Response::new(ResponseBody::default());
+            *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+            // clients only understand whole seconds, and retrying too early would be throttled again
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+            return Box::pin(async { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(Request::from_parts(parts, body)))
+    }
+}
+
+/// The state shared by a [`PriorityThrottleLayer`] and its services.
+struct ThrottleState {
+    /// The settings of the layer.
+    config: PriorityThrottleConfig,
+    /// The token bucket of every limited priority, indexed by [`index`].
+    buckets: Mutex<[Option<Bucket>; 3]>,
+}
+impl ThrottleState {
+    /// Take a token of the given priority. If there is none left, the time until the next one is available gets returned.
+    fn take(&self, priority: Priority) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        match &mut buckets[index(priority)] {
+            Some(bucket) => bucket.take(Instant::now()),
+            None => Ok(()),
+        }
+    }
+}
+impl fmt::Debug for ThrottleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThrottleState")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A token bucket limiting the requests of one priority.
+struct Bucket {
+    /// The rate at which tokens get refilled, which also sets the number of tokens the bucket can hold.
+    rate: Rate,
+    /// The number of tokens left, including the fraction of the next one.
+    tokens: f64,
+    /// The last time the tokens were refilled.
+    updated: Instant,
+}
+impl Bucket {
+    /// Refill the tokens up to the given time and take one of them. If there is none left, the time until the next one is available gets
+    /// returned.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(self.rate.requests);
+        if self.rate.period.is_zero() {
+            return Ok(());
+        }
+        let per_second = capacity / self.rate.period.as_secs_f64();
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if per_second > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+        } else {
+            // no request is allowed at all
+            Err(self.rate.period)
+        }
+    }
+}
+
+/// Get the index of the given priority in the arrays of the rates and token buckets.
+fn index(priority: Priority) -> usize {
+    match priority {
+        Priority::Low => 0,
+        Priority::Normal => 1,
+        Priority::High => 2,
+    }
+}
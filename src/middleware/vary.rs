@@ -0,0 +1,110 @@
+//! This module provides the [`VaryLayer`], which lists the request headers a response depends on.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::http::{
+    header::VARY,
+    HeaderMap,
+    HeaderValue,
+    Request,
+    Response,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Adds the given request headers to the `Vary` header of every response. \
+/// Caches between the client and the server use this header to tell which request headers a response depends on. Without it, they might
+/// send a response compressed with `gzip` to a client not supporting it, or a German page to an English one.
+///
+/// Names already listed in the `Vary` header of a response are not added again, and responses varying by everything (`Vary: *`) are left
+/// unchanged.
+///
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     middleware::vary::VaryLayer,
+/// };
+///
+/// let router: Router = Router::new().layer(VaryLayer(vec!["Accept-Encoding", "Accept-Language"]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct VaryLayer(pub Vec<&'static str>);
+impl<S> Layer<S> for VaryLayer {
+    type Service = Vary<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Vary {
+            headers: self.0.clone().into(),
+            inner,
+        }
+    }
+}
+
+/// The service created by the [`VaryLayer`].
+#[derive(Clone, Debug)]
+pub struct Vary<S> {
+    /// The names of the request headers to add to the `Vary` header.
+    headers: Arc<[&'static str]>,
+    /// The service answering the requests.
+    inner: S,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for Vary<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<RequestBody>) -> Self::Future {
+        let headers = self.headers.clone();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = response.await?;
+            add_vary(response.headers_mut(), &headers);
+            Ok(response)
+        })
+    }
+}
+
+/// Add the given header names to the `Vary` header in the given map, skipping the ones already listed.
+fn add_vary(headers: &mut HeaderMap, names: &[&str]) {
+    let mut vary: Vec<String> = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if vary.iter().any(|name| name == "*") {
+        return;
+    }
+
+    let length = vary.len();
+    for name in names {
+        if !vary.iter().any(|listed| listed.eq_ignore_ascii_case(name)) {
+            vary.push(name.to_string());
+        }
+    }
+    if vary.len() == length {
+        return;
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&vary.join(", ")) {
+        headers.insert(VARY, value);
+    }
+}
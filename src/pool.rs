@@ -0,0 +1,136 @@
+//! This module provides the [`BufferPool`], which keeps the buffers responses get serialized into, so they do not have to be allocated
+//! for every response.
+//!
+//! An [`HttpServer`](crate::http_server::HttpServer) creates its own pool with
+//! [`response_buffer_pool`](crate::http_server::HttpServer::response_buffer_pool). A pool created here can instead be shared by several
+//! of them with [`shared_response_buffer_pool`](crate::http_server::HttpServer::shared_response_buffer_pool):
+//! ```
+//! use std::sync::Arc;
+//!
+//! use goohttp::{
+//!     http_server::HttpServer,
+//!     pool::BufferPool,
+//! };
+//!
+//! let pool = Arc::new(BufferPool::new(8, 1024));
+//! let http_server = HttpServer::bind("0.0.0.0:80", None, None)
+//!     .unwrap()
+//!     .shared_response_buffer_pool(pool.clone());
+//! let admin_server = HttpServer::bind("0.0.0.0:8080", None, None)
+//!     .unwrap()
+//!     .shared_response_buffer_pool(pool);
+//! ```
+
+use std::{
+    ops::{
+        Deref,
+        DerefMut,
+    },
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+    },
+};
+
+/// A fixed number of byte buffers of the same capacity, shared by the connections of one or more
+/// [`HttpServer`](crate::http_server::HttpServer)s. \
+/// Taking a buffer out and returning it only locks a mutex around a list of buffers for a moment, which is far cheaper than allocating
+/// and freeing one on a slow allocator.
+pub struct BufferPool {
+    /// The buffers not in use right now.
+    buffers: Mutex<Vec<Vec<u8>>>,
+    /// The number of buffers kept.
+    count: usize,
+    /// The capacity of every buffer.
+    capacity: usize,
+}
+impl BufferPool {
+    /// Create a new BufferPool with the given number of buffers of the given capacity, which get allocated right away.
+    pub fn new(count: usize, capacity: usize) -> Self {
+        Self {
+            buffers: Mutex::new((0..count).map(|_| Vec::with_capacity(capacity)).collect()),
+            count,
+            capacity,
+        }
+    }
+
+    /// Get the number of buffers kept.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the capacity of every buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Take an empty buffer with at least the given capacity out of this pool. A new buffer gets allocated if all buffers of the pool are
+    /// in use, or if they are too small. New buffers fitting into the pool get added to it once they are dropped, unless the pool is full
+    /// by then.
+    pub fn take(self: &Arc<Self>, capacity: usize) -> ResponseBuffer {
+        Self::take_from(Some(self), capacity)
+    }
+
+    /// Take an empty buffer with at least the given capacity out of the given pool, or allocate a new one without a pool. See
+    /// [`take`](Self::take).
+    pub(crate) fn take_from(pool: Option<&Arc<Self>>, capacity: usize) -> ResponseBuffer {
+        match pool.filter(|pool| capacity <= pool.capacity) {
+            Some(pool) => ResponseBuffer {
+                buffer: pool
+                    .lock()
+                    .pop()
+                    .unwrap_or_else(|| Vec::with_capacity(pool.capacity)),
+                pool: Some(pool.clone()),
+            },
+            None => ResponseBuffer {
+                buffer: Vec::with_capacity(capacity),
+                pool: None,
+            },
+        }
+    }
+
+    /// Lock the buffers not in use. A panic while they were locked cannot leave them half-changed, so poisoning gets ignored.
+    fn lock(&self) -> MutexGuard<'_, Vec<Vec<u8>>> {
+        self.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A buffer taken out of a [`BufferPool`], which gets returned to it once it is dropped. \
+/// It dereferences to a [`Vec`], which can be written to like any other. Buffers that had to grow beyond the capacity of the pool are not
+/// returned to it.
+pub struct ResponseBuffer {
+    /// The buffer.
+    buffer: Vec<u8>,
+    /// The pool the buffer gets returned to, if any.
+    pool: Option<Arc<BufferPool>>,
+}
+impl Deref for ResponseBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+impl DerefMut for ResponseBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+impl Drop for ResponseBuffer {
+    fn drop(&mut self) {
+        let Some(pool) = self.pool.take() else {
+            return;
+        };
+        // a buffer that had to grow would keep its larger allocation in the pool
+        if self.buffer.capacity() != pool.capacity {
+            return;
+        }
+        let mut buffers = pool.lock();
+        if buffers.len() < pool.count {
+            self.buffer.clear();
+            buffers.push(std::mem::take(&mut self.buffer));
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! This module provides helpers for building responses and their headers.
+
+use std::{
+    convert::Infallible,
+    fmt,
+    ops::Range,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::{
+    body::Bytes,
+    http::{
+        header::{
+            ACCEPT,
+            ACCEPT_RANGES,
+            CACHE_CONTROL,
+            CONTENT_RANGE,
+            CONTENT_TYPE,
+            RANGE,
+            TRANSFER_ENCODING,
+        },
+        HeaderMap,
+        HeaderValue,
+        StatusCode,
+    },
+    response::{
+        IntoResponse,
+        Response,
+    },
+    BoxError,
+};
+use futures_core::Stream;
+use mime::Mime;
+
+/// A response containing HTML. Its content type gets set to `text/html; charset=utf-8`.
+pub struct HtmlResponse(pub String);
+impl IntoResponse for HtmlResponse {
+    fn into_response(self) -> Response {
+        with_content_type(self.0, "text/html; charset=utf-8")
+    }
+}
+
+/// A response containing plain text. Its content type gets set to `text/plain; charset=utf-8`.
+pub struct PlainText(pub String);
+impl IntoResponse for PlainText {
+    fn into_response(self) -> Response {
+        with_content_type(self.0, "text/plain; charset=utf-8")
+    }
+}
+
+/// A response containing XML. Its content type gets set to `application/xml; charset=utf-8`.
+pub struct Xml(pub String);
+impl IntoResponse for Xml {
+    fn into_response(self) -> Response {
+        with_content_type(self.0, "application/xml; charset=utf-8")
+    }
+}
+
+/// A response whose body gets sent chunk by chunk as the given stream produces it, instead of being collected first. This suits progress
+/// updates or the incremental results of long-running calculations. \
+/// The [`HttpServer`](crate::http_server::HttpServer) writes and flushes every chunk as soon as it arrives, using
+/// `Transfer-Encoding: chunked`, or, for HTTP/1.0 clients, by closing the connection after the last chunk. Since the body is never held
+/// in memory as a whole, the [`max_response_body`](crate::http_server::HttpServer::max_response_body) limit does not apply to it. If the
+/// stream yields an error, the connection gets closed, which tells the client that the body is incomplete.
+///
+/// If the length of the body is known in advance, like the size of a file, it can be sent as a `Content-Length` header. The chunks then
+/// get written as they are, and the connection stays open for HTTP/1.0 clients as well. A body not matching this length closes the
+/// connection, since the client could not tell where the next response starts otherwise:
+/// ```
+/// # use futures_core::Stream;
+/// # use goohttp::{
+/// #     axum::{
+/// #         body::Bytes,
+/// #         http::{
+/// #             header::CONTENT_LENGTH,
+/// #             HeaderValue,
+/// #         },
+/// #         response::{
+/// #             IntoResponse,
+/// #             Response,
+/// #         },
+/// #     },
+/// #     response::StreamBody,
+/// # };
+/// # fn file(length: u64, chunks: impl Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static) -> Response {
+/// ([(CONTENT_LENGTH, HeaderValue::from(length))], StreamBody(chunks)).into_response()
+/// # }
+/// # fn main() {}
+/// ```
+pub struct StreamBody<S>(pub S);
+impl<S, E> IntoResponse for StreamBody<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Into<BoxError>,
+{
+    fn into_response(self) -> Response {
+        let mut response = axum::body::StreamBody::new(self.0).into_response();
+        response
+            .headers_mut()
+            .insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+        response.extensions_mut().insert(Streamed);
+        response
+    }
+}
+
+/// A response sending [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html) as the given stream produces
+/// them, which a browser can receive with an `EventSource`. This suits live dashboards, where the server pushes new readings over time. \
+/// Every event gets written and flushed as soon as it arrives, like the chunks of a [`StreamBody`], and the response stays open until the
+/// stream ends. Its content type gets set to `text/event-stream`, and caching gets disabled.
+///
+/// ```
+/// # use std::{
+/// #     pin::Pin,
+/// #     task::{
+/// #         Context,
+/// #         Poll,
+/// #     },
+/// # };
+/// # use futures_core::Stream;
+/// use goohttp::response::{
+///     Event,
+///     EventStream,
+/// };
+/// # struct Temperatures;
+/// # impl Temperatures {
+/// #     fn map(self, event: fn(f32) -> Event) -> Events {
+/// #         Events(event)
+/// #     }
+/// # }
+/// # struct Events(fn(f32) -> Event);
+/// # impl Stream for Events {
+/// #     type Item = Event;
+/// #     fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Event>> {
+/// #         Poll::Ready(Some((self.0)(21.5)))
+/// #     }
+/// # }
+/// # fn temperatures() -> Temperatures {
+/// #     Temperatures
+/// # }
+///
+/// async fn dashboard() -> EventStream<impl Stream<Item = Event> + Send + 'static> {
+///     // `temperatures` yields a new reading every second
+///     EventStream(temperatures().map(|temperature| {
+///         Event::default()
+///             .event("temperature")
+///             .data(temperature.to_string())
+///     }))
+/// }
+/// # fn main() {}
+/// ```
+pub struct EventStream<S>(pub S);
+impl<S> IntoResponse for EventStream<S>
+where
+    S: Stream<Item = Event> + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        let mut response = StreamBody(EncodedEvents(Box::pin(self.0))).into_response();
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        response
+    }
+}
+
+/// A single server-sent event of an [`EventStream`]. Every field is optional, and fields spanning several lines get split up as the
+/// format requires:
+/// ```
+/// use goohttp::response::Event;
+///
+/// let event = Event::default().event("reading").id("42").data("21.5\n22.0");
+/// assert_eq!(
+///     event.to_string(),
+///     "event: reading\nid: 42\ndata: 21.5\ndata: 22.0\n\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    /// The comment lines, which clients ignore.
+    comment: Option<String>,
+    /// The data of this event.
+    data: Option<String>,
+    /// The name of this event.
+    event: Option<String>,
+    /// The id of this event.
+    id: Option<String>,
+    /// The number of milliseconds a client waits before reconnecting.
+    retry: Option<u64>,
+}
+impl Event {
+    /// Set the data of this event, which a client gets in the `data` attribute of its `MessageEvent`.
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+    /// Set the name of this event. Clients receive named events with `addEventListener(name, ...)` instead of `onmessage`. Line breaks
+    /// get replaced with spaces, since the name has to fit on one line.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(single_line(event.into()));
+        self
+    }
+    /// Set the id of this event. A client reconnecting after losing the connection sends the last id it received in the
+    /// `Last-Event-ID` header, so the server can continue where it left off. Line breaks get replaced with spaces, since the id has to fit
+    /// on one line.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(single_line(id.into()));
+        self
+    }
+    /// Set the time a client waits before reconnecting after losing the connection.
+    pub fn retry(mut self, milliseconds: u64) -> Self {
+        self.retry = Some(milliseconds);
+        self
+    }
+    /// Add a comment, which clients ignore. An event consisting only of a comment keeps proxies from closing an idle connection.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+}
+/// Formats the event as it gets sent, including the empty line ending it.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Write the given field once for every line of the given value.
+        fn write_lines(f: &mut fmt::Formatter<'_>, field: &str, value: &str) -> fmt::Result {
+            for line in value.split('\n') {
+                writeln!(f, "{field}: {}", line.strip_suffix('\r').unwrap_or(line))?;
+            }
+            Ok(())
+        }
+
+        if let Some(comment) = &self.comment {
+            write_lines(f, "", comment)?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {event}")?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {id}")?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {retry}")?;
+        }
+        if let Some(data) = &self.data {
+            write_lines(f, "data", data)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Replace the line breaks in the given value with spaces.
+fn single_line(value: String) -> String {
+    if value.contains(['\r', '\n']) {
+        value.replace(['\r', '\n'], " ")
+    } else {
+        value
+    }
+}
+
+/// Encodes the events of the given stream for an [`EventStream`].
+struct EncodedEvents<S>(Pin<Box<S>>);
+impl<S: Stream<Item = Event>> Stream for EncodedEvents<S> {
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0
+            .as_mut()
+            .poll_next(cx)
+            .map(|event| event.map(|event| Ok(Bytes::from(event.to_string()))))
+    }
+}
+
+/// Marks a [`Response`] whose body should be written chunk by chunk. See [`StreamBody`].
+#[derive(Clone, Copy)]
+pub(crate) struct Streamed;
+
+/// Turn the given body into a response with the given content type.
+fn with_content_type(body: String, content_type: &'static str) -> Response {
+    (
+        [(CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        body,
+    )
+        .into_response()
+}
+
+/// A response to a request which may ask for a part of the body with a `Range` header, like a client resuming a download. \
+/// Only the requested part of the body gets produced, by calling the given function with the range of bytes to send. The response is:
+/// - `206 Partial Content` with a `Content-Range` header, if a single satisfiable range was requested,
+/// - `416 Range Not Satisfiable` with `Content-Range: bytes */<length>` and an empty body, if the range starts after the end of the body,
+/// - `200 OK` with the whole body otherwise, including requests without a `Range` header, with a malformed one, or asking for several
+///   ranges at once, which would need a `multipart/byteranges` body.
+///
+/// Every response announces the support for ranges with `Accept-Ranges: bytes`. The body returned by the function can be anything
+/// implementing [`IntoResponse`], like a `Vec<u8>` or a [`StreamBody`] reading from flash chunk by chunk:
+/// ```
+/// use goohttp::{
+///     axum::http::HeaderMap,
+///     response::RangeResponse,
+/// };
+///
+/// static FIRMWARE: &[u8] = &[0; 4096];
+///
+/// async fn download_firmware(headers: HeaderMap) -> RangeResponse<impl FnOnce(std::ops::Range<u64>) -> Vec<u8>> {
+///     RangeResponse::new(&headers, FIRMWARE.len() as u64, |range| {
+///         FIRMWARE[range.start as usize..range.end as usize].to_vec()
+///     })
+/// }
+/// ```
+pub struct RangeResponse<F> {
+    /// The range requested by the client.
+    range: RangeRequest,
+    /// The length of the whole body.
+    length: u64,
+    /// Produces the requested range of the body.
+    body: F,
+}
+impl<F, B> RangeResponse<F>
+where
+    F: FnOnce(Range<u64>) -> B,
+{
+    /// Create a new RangeResponse for a body of the given length, answering the `Range` header in the given headers of the request.
+    pub fn new(headers: &HeaderMap, length: u64, body: F) -> Self {
+        Self {
+            range: RangeRequest::parse(headers.get(RANGE), length),
+            length,
+            body,
+        }
+    }
+}
+impl<F, B> IntoResponse for RangeResponse<F>
+where
+    F: FnOnce(Range<u64>) -> B,
+    B: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let length = self.length;
+        let (status, content_range, mut response) = match self.range {
+            RangeRequest::Full => (StatusCode::OK, None, (self.body)(0..length).into_response()),
+            RangeRequest::Partial(range) => (
+                StatusCode::PARTIAL_CONTENT,
+                Some(format!("bytes {}-{}/{length}", range.start, range.end - 1)),
+                (self.body)(range).into_response(),
+            ),
+            RangeRequest::Unsatisfiable => (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                Some(format!("bytes */{length}")),
+                ().into_response(),
+            ),
+        };
+
+        *response.status_mut() = status;
+        let headers = response.headers_mut();
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Some(content_range) = content_range {
+            headers.insert(
+                CONTENT_RANGE,
+                HeaderValue::from_str(&content_range)
+                    .expect("A `Content-Range` made of numbers should be a valid header value."),
+            );
+        }
+        response
+    }
+}
+
+/// The part of a body a client asked for with a `Range` header, as answered by a [`RangeResponse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The whole body has to be sent, because no range, a malformed one, or several ranges were requested.
+    Full,
+    /// Only the given range of bytes has to be sent.
+    Partial(Range<u64>),
+    /// The requested range starts after the end of the body.
+    Unsatisfiable,
+}
+impl RangeRequest {
+    /// Parse the given value of a `Range` header for a body of the given length, as described by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-14.2). Ranges reaching past the end of the body get cut off there.
+    ///
+    /// ```
+    /// use goohttp::{
+    ///     axum::http::HeaderValue,
+    ///     response::RangeRequest,
+    /// };
+    ///
+    /// let range = HeaderValue::from_static("bytes=-500");
+    /// assert_eq!(
+    ///     RangeRequest::parse(Some(&range), 2000),
+    ///     RangeRequest::Partial(1500..2000)
+    /// );
+    /// ```
+    pub fn parse(header: Option<&HeaderValue>, length: u64) -> Self {
+        /// Parse the given position, which must only consist of digits.
+        fn position(position: &str) -> Option<u64> {
+            if position.is_empty() || !position.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            position.parse().ok()
+        }
+
+        let Some(header) = header.and_then(|header| header.to_str().ok()) else {
+            return Self::Full;
+        };
+        // units other than bytes have to be ignored
+        let Some((unit, ranges)) = header.split_once('=') else {
+            return Self::Full;
+        };
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return Self::Full;
+        }
+        let mut ranges = ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty());
+        let (Some(range), None) = (ranges.next(), ranges.next()) else {
+            return Self::Full;
+        };
+        let Some((first, last)) = range.split_once('-') else {
+            return Self::Full;
+        };
+        match (first, last) {
+            // the last bytes of the body
+            ("", suffix) => match position(suffix) {
+                Some(0) => Self::Unsatisfiable,
+                Some(_) if length == 0 => Self::Unsatisfiable,
+                Some(suffix) => Self::Partial(length.saturating_sub(suffix)..length),
+                None => Self::Full,
+            },
+            (first, last) => {
+                let Some(first) = position(first) else {
+                    return Self::Full;
+                };
+                let last = match last {
+                    "" => length.saturating_sub(1),
+                    last => match position(last) {
+                        Some(last) if last >= first => last.min(length.saturating_sub(1)),
+                        _ => return Self::Full,
+                    },
+                };
+                if first >= length {
+                    Self::Unsatisfiable
+                } else {
+                    Self::Partial(first..last + 1)
+                }
+            }
+        }
+    }
+}
+
+/// Pick the media type of the response out of the given ones a route can produce, as asked for by the `Accept` header in the given headers
+/// of the request. The returned type can then be used as the `Content-Type` of the response:
+/// ```
+/// use goohttp::{
+///     axum::{
+///         http::{
+///             header::CONTENT_TYPE,
+///             HeaderMap,
+///             StatusCode,
+///         },
+///         response::{
+///             IntoResponse,
+///             Response,
+///         },
+///     },
+///     mime,
+///     response::negotiate,
+/// };
+///
+/// async fn temperature(headers: HeaderMap) -> Response {
+///     let Some(media_type) = negotiate(&headers, &[mime::APPLICATION_JSON, mime::TEXT_PLAIN]) else {
+///         return StatusCode::NOT_ACCEPTABLE.into_response();
+///     };
+///     let body = if media_type == mime::APPLICATION_JSON {
+///         "{\"celsius\":21.5}"
+///     } else {
+///         "21.5 °C"
+///     };
+///     ([(CONTENT_TYPE, media_type.to_string())], body).into_response()
+/// }
+/// ```
+///
+/// Every media type gets the quality of the most specific range of the header matching it, so `text/plain` counts over `text/*`, which
+/// counts over `*/*`. Parameters of the ranges other than the quality `q` are ignored. The type with the highest quality above `0` is
+/// returned, and of types with the same quality, the one listed first. \
+/// Without an `Accept` header, or if none of its ranges could be parsed, the client accepts anything, so the first of the given types is
+/// returned. `None` is returned if the client accepts none of them, which is usually answered with `406 Not Acceptable`.
+pub fn negotiate(headers: &HeaderMap, available: &[Mime]) -> Option<Mime> {
+    let ranges: Vec<(Mime, f32)> = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let range: Mime = range.trim().parse().ok()?;
+            let quality = match range.get_param("q") {
+                Some(quality) => quality
+                    .as_str()
+                    .parse()
+                    .ok()
+                    .filter(|quality| (0.0..=1.0).contains(quality))?,
+                None => 1.0,
+            };
+            Some((range, quality))
+        })
+        .collect();
+    if ranges.is_empty() {
+        return available.first().cloned();
+    }
+
+    let mut best: Option<(&Mime, f32)> = None;
+    for media_type in available {
+        let quality = ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+                let specificity = if range.essence_str() == media_type.essence_str() {
+                    2
+                } else if range.subtype() == mime::STAR && range.type_() == media_type.type_() {
+                    1
+                } else if range.essence_str() == mime::STAR_STAR.essence_str() {
+                    0
+                } else {
+                    return None;
+                };
+                Some((specificity, *quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map_or(0.0, |(_, quality)| quality);
+
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((media_type, quality));
+        }
+    }
+    best.map(|(media_type, _)| media_type.clone())
+}
+
+/// Builds values of the `Content-Disposition` header, which tells a browser whether to display a response or to download it as a file.
+///
+/// Filenames consisting only of printable ASCII characters get sent as a quoted `filename` parameter. All other filenames additionally get
+/// sent as a `filename*` parameter encoded according to [RFC 5987](https://datatracker.ietf.org/doc/html/rfc5987), while the `filename`
+/// parameter holds a fallback for older clients in which every other character got replaced with an underscore.
+///
+/// ```
+/// use goohttp::response::ContentDisposition;
+///
+/// assert_eq!(
+///     ContentDisposition::attachment("report.pdf"),
+///     "attachment; filename=\"report.pdf\""
+/// );
+/// assert_eq!(
+///     ContentDisposition::inline("€ rates.txt"),
+///     "inline; filename=\"_ rates.txt\"; filename*=UTF-8''%E2%82%AC%20rates.txt"
+/// );
+/// ```
+pub struct ContentDisposition;
+impl ContentDisposition {
+    /// Ask the client to download the response as a file with the given name.
+    pub fn attachment(filename: &str) -> HeaderValue {
+        Self::build("attachment", filename)
+    }
+    /// Ask the client to display the response, while still suggesting the given name in case it gets saved.
+    pub fn inline(filename: &str) -> HeaderValue {
+        Self::build("inline", filename)
+    }
+
+    /// Build the header value of the given disposition type and filename.
+    fn build(disposition: &str, filename: &str) -> HeaderValue {
+        let mut value = format!("{disposition}; filename=\"");
+        let mut needs_extended = false;
+        for character in filename.chars() {
+            match character {
+                '"' | '\\' => {
+                    value.push('\\');
+                    value.push(character);
+                }
+                ' '..='~' => value.push(character),
+                _ => {
+                    value.push('_');
+                    needs_extended = true;
+                }
+            }
+        }
+        value.push('"');
+
+        if needs_extended {
+            value.push_str("; filename*=UTF-8''");
+            for byte in filename.bytes() {
+                // the `attr-char` rule of RFC 5987
+                if byte.is_ascii_alphanumeric()
+                    || matches!(
+                        byte,
+                        b'!' | b'#'
+                            | b'$'
+                            | b'&'
+                            | b'+'
+                            | b'-'
+                            | b'.'
+                            | b'^'
+                            | b'_'
+                            | b'`'
+                            | b'|'
+                            | b'~'
+                    )
+                {
+                    value.push(byte as char);
+                } else {
+                    value.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+
+        HeaderValue::from_str(&value).expect(
+            "Every character outside of printable ASCII should have been replaced or encoded.",
+        )
+    }
+}
@@ -0,0 +1,151 @@
+//! This module provides the [`RpcServer`], which answers requests in a trivial binary format with the same [`Router`] as an
+//! [`HttpServer`]. It is meant for clients too constrained for HTTP, like other microcontrollers.
+//!
+//! Every request is a single frame consisting of the path of the route, followed by the body of the request. It gets answered like a
+//! `POST` request to this path, and the response gets sent back as a frame consisting of its status code and body. All numbers are
+//! big-endian, and headers cannot be sent in either direction:
+//! ```text
+//! request:  {u16 path length}{path}{u32 body length}{body}
+//! response: {u16 status code}{u32 body length}{body}
+//! ```
+//! Like with HTTP, a client can send any number of frames over the same connection.
+
+use std::io::{
+    BufRead,
+    Write,
+};
+
+use axum::{
+    http::StatusCode,
+    Router,
+};
+
+use crate::{
+    error::{
+        Error,
+        ParseError,
+        SerializeError,
+    },
+    http_server::{
+        HttpServer,
+        Listener,
+        ServeHandle,
+    },
+};
+
+/// Answers requests sent as binary frames with a [`Router`], as described in the [module documentation](self). \
+/// An RpcServer is created from an [`HttpServer`], whose settings it keeps, like its address, limits and
+/// [`metrics_backend`](HttpServer::metrics_backend):
+/// ```no_run
+/// # async fn example() {
+/// use goohttp::{
+///     axum::{
+///         routing::post,
+///         Router,
+///     },
+///     http_server::HttpServer,
+///     rpc_server::RpcServer,
+/// };
+///
+/// let http_server = HttpServer::bind("0.0.0.0:4000", None, None)
+///     .unwrap()
+///     .max_request_body(1024);
+/// let mut rpc_server = RpcServer::new(http_server);
+/// rpc_server
+///     .serve(Router::new().route("/say_hello", post(|| async { "Hello World!" })))
+///     .unwrap();
+/// # }
+/// ```
+/// Since every request is a `POST` request, only the `POST` handlers of the router can be reached. Every request gets the
+/// [`ConnectionInfo`](crate::ConnectionInfo) of its connection, whose version is always HTTP/1.1. The headers of the responses get
+/// dropped.
+pub struct RpcServer {
+    /// The HttpServer answering the frames.
+    http_server: HttpServer,
+}
+impl RpcServer {
+    /// Create a new RpcServer with the settings of the given [`HttpServer`].
+    pub fn new(http_server: HttpServer) -> Self {
+        Self {
+            http_server: http_server.rpc_frames(),
+        }
+    }
+
+    /// Get the HttpServer answering the frames, e.g. to get the number of its [`open_connections`](HttpServer::open_connections).
+    pub fn http_server(&self) -> &HttpServer {
+        &self.http_server
+    }
+
+    /// Serve the given [`Router`]. See [`HttpServer::serve`].
+    ///
+    /// # Errors
+    ///
+    /// The same errors as for [`HttpServer::serve`] are returned.
+    pub fn serve(&mut self, router: Router) -> Result<ServeHandle, Error> {
+        self.http_server.serve(router)
+    }
+
+    /// Serve the given [`Router`] on the connections of the given [`Listener`]. See [`HttpServer::serve_on`].
+    ///
+    /// # Errors
+    ///
+    /// The same errors as for [`HttpServer::serve_on`] are returned.
+    pub fn serve_on(
+        &mut self,
+        listener: impl Listener,
+        router: Router,
+    ) -> Result<ServeHandle, Error> {
+        self.http_server.serve_on(listener, router)
+    }
+
+    /// Stop serving. See [`HttpServer::shutdown`].
+    pub async fn shutdown(&mut self) {
+        self.http_server.shutdown().await;
+    }
+}
+
+/// Read the head of the next request frame from the given reader, consisting of its path and the length of its body. \
+/// `None` is returned if the client closed the connection before sending another frame. Paths longer than the given limit, or which
+/// are not a valid path of a URI, cause a [`ParseError::RpcFrame`].
+pub(crate) fn read_request_frame(
+    reader: &mut impl BufRead,
+    max_path_bytes: usize,
+) -> Result<Option<(String, u64)>, Error> {
+    let mut path_length = [0; 2];
+    // a connection closed between two frames is not an error
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    reader.read_exact(&mut path_length)?;
+    let path_length = usize::from(u16::from_be_bytes(path_length));
+    if path_length > max_path_bytes {
+        return Err(ParseError::RpcFrame.into());
+    }
+
+    let mut path = vec![0; path_length];
+    reader.read_exact(&mut path)?;
+    let path = String::from_utf8(path).map_err(|_| ParseError::RpcFrame)?;
+    if !path.starts_with('/') {
+        return Err(ParseError::RpcFrame.into());
+    }
+
+    let mut body_length = [0; 4];
+    reader.read_exact(&mut body_length)?;
+    Ok(Some((path, u64::from(u32::from_be_bytes(body_length)))))
+}
+
+/// Write a response frame with the given status code and body to the given client.
+pub(crate) fn write_response_frame(
+    client: &mut impl Write,
+    status: StatusCode,
+    body: &[u8],
+) -> Result<(), Error> {
+    let body_length = u32::try_from(body.len()).map_err(|_| SerializeError::FrameTooLarge)?;
+    let mut frame = Vec::with_capacity(6 + body.len());
+    frame.extend_from_slice(&status.as_u16().to_be_bytes());
+    frame.extend_from_slice(&body_length.to_be_bytes());
+    frame.extend_from_slice(body);
+    client.write_all(&frame)?;
+    client.flush()?;
+    Ok(())
+}
@@ -0,0 +1,241 @@
+//! This module provides the [`CspNonceLayer`], which allows inline scripts and styles under a strict `Content-Security-Policy`.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::http::{
+    header::CONTENT_SECURITY_POLICY,
+    HeaderMap,
+    HeaderValue,
+    Request,
+    Response,
+    StatusCode,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The number of random bytes in a nonce. The CSP specification asks for at least 128 bits.
+const NONCE_LENGTH: usize = 16;
+
+/// The directives which get the nonce of a response added.
+const NONCE_DIRECTIVES: [&str; 2] = ["script-src", "style-src"];
+
+/// Generates a random nonce for every request and allows it in the `script-src` and `style-src` directives of the
+/// `Content-Security-Policy` header of the response. \
+/// An attacker injecting a `<script>` tag into a page cannot guess the nonce, so the browser only runs the inline scripts and styles the
+/// route marked with it. Routes get the nonce of their request as a [`CspNonce`] extension:
+///
+/// ```
+/// use goohttp::{
+///     axum::{
+///         response::Html,
+///         routing::get,
+///         Extension,
+///         Router,
+///     },
+///     security::{
+///         CspNonce,
+///         CspNonceLayer,
+///     },
+/// };
+///
+/// async fn index(Extension(CspNonce(nonce)): Extension<CspNonce>) -> Html<String> {
+///     Html(format!("<script nonce=\"{nonce}\">console.log('Hello!')</script>"))
+/// }
+///
+/// let router: Router = Router::new()
+///     .route("/", get(index))
+///     .layer(CspNonceLayer::with_policy("default-src 'self'"));
+/// ```
+///
+/// A directive missing from the policy of a response falls back to the `default-src` directive, so its sources get copied from there
+/// before the nonce is added. Responses whose policy has neither the directive nor `default-src` do not restrict it, and are left
+/// unchanged. \
+/// If no nonce could be generated, because the random number generator of the system failed, the request gets answered with an empty
+/// `500 Internal Server Error` instead.
+#[derive(Clone, Debug, Default)]
+pub struct CspNonceLayer {
+    /// The policy sent with responses not setting their own.
+    policy: Option<HeaderValue>,
+}
+impl CspNonceLayer {
+    /// Create a new CspNonceLayer, which only changes the `Content-Security-Policy` headers the routes set themselves.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new CspNonceLayer, which sends the given policy with every response not setting its own.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the given policy contains characters not allowed in a header value.
+    pub fn with_policy(policy: &'static str) -> Self {
+        Self {
+            policy: Some(HeaderValue::from_static(policy)),
+        }
+    }
+}
+impl<S> Layer<S> for CspNonceLayer {
+    type Service = CspNonceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CspNonceService {
+            inner,
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+/// The service created by the [`CspNonceLayer`].
+#[derive(Clone, Debug)]
+pub struct CspNonceService<S> {
+    /// The service answering the requests.
+    inner: S,
+    /// The policy sent with responses not setting their own.
+    policy: Option<HeaderValue>,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for CspNonceService<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+    ResponseBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<RequestBody>) -> Self::Future {
+        let Some(nonce) = generate_nonce() else {
+            let mut response = Response::new(ResponseBody::default());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            return Box::pin(async { Ok(response) });
+        };
+        request.extensions_mut().insert(CspNonce(nonce.clone()));
+
+        let policy = self.policy.clone();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = response.await?;
+            let headers = response.headers_mut();
+            if let Some(policy) = policy {
+                headers.entry(CONTENT_SECURITY_POLICY).or_insert(policy);
+            }
+            add_nonce(headers, &nonce);
+            Ok(response)
+        })
+    }
+}
+
+/// The nonce of a request, which the [`CspNonceLayer`] adds to its extensions. Inline scripts and styles need it in their `nonce`
+/// attribute to be allowed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CspNonce(pub String);
+
+/// Generate a new base64 encoded nonce. `None` is returned if the random number generator of the system failed.
+fn generate_nonce() -> Option<String> {
+    let mut bytes = [0; NONCE_LENGTH];
+    getrandom::getrandom(&mut bytes).ok()?;
+    Some(base64(&bytes))
+}
+
+/// Encode the given bytes with the standard base64 alphabet, including padding.
+fn base64(bytes: &[u8]) -> String {
+    /// The characters representing the values from 0 to 63.
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Allow the given nonce in the [`NONCE_DIRECTIVES`] of every `Content-Security-Policy` header in the given map.
+fn add_nonce(headers: &mut HeaderMap, nonce: &str) {
+    let policies: Vec<HeaderValue> = headers
+        .get_all(CONTENT_SECURITY_POLICY)
+        .iter()
+        .map(|policy| {
+            policy
+                .to_str()
+                .ok()
+                .and_then(|policy| HeaderValue::from_str(&add_nonce_to_policy(policy, nonce)).ok())
+                .unwrap_or_else(|| policy.clone())
+        })
+        .collect();
+
+    headers.remove(CONTENT_SECURITY_POLICY);
+    for policy in policies {
+        headers.append(CONTENT_SECURITY_POLICY, policy);
+    }
+}
+
+/// Allow the given nonce in the [`NONCE_DIRECTIVES`] of the given policy.
+fn add_nonce_to_policy(policy: &str, nonce: &str) -> String {
+    /// Split the given directive into its name and its sources.
+    fn split_directive(directive: &str) -> (&str, &str) {
+        directive
+            .split_once(char::is_whitespace)
+            .map_or((directive, ""), |(name, sources)| (name, sources.trim()))
+    }
+    /// Create a directive with the given name allowing the given sources and nonce. `'none'` gets dropped, since it cannot be combined
+    /// with other sources.
+    fn directive_with_nonce(name: &str, sources: &str, nonce: &str) -> String {
+        let mut directive = name.to_string();
+        for source in sources
+            .split_whitespace()
+            .filter(|source| !source.eq_ignore_ascii_case("'none'"))
+        {
+            directive.push(' ');
+            directive.push_str(source);
+        }
+        directive.push_str(&format!(" 'nonce-{nonce}'"));
+        directive
+    }
+
+    let mut directives: Vec<String> = policy
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(str::to_string)
+        .collect();
+    let default_sources = directives
+        .iter()
+        .map(|directive| split_directive(directive))
+        .find(|(name, _)| name.eq_ignore_ascii_case("default-src"))
+        .map(|(_, sources)| sources.to_string());
+
+    for name in NONCE_DIRECTIVES {
+        let directive = directives
+            .iter_mut()
+            .find(|directive| split_directive(directive).0.eq_ignore_ascii_case(name));
+        if let Some(directive) = directive {
+            *directive = directive_with_nonce(name, split_directive(directive).1, nonce);
+        } else if let Some(default_sources) = &default_sources {
+            directives.push(directive_with_nonce(name, default_sources, nonce));
+        }
+    }
+    directives.join("; ")
+}
@@ -0,0 +1,292 @@
+//! This module provides an in-memory transport for the [`HttpServer`](crate::http_server::HttpServer), which allows to test routers
+//! without opening any sockets. Requests still get parsed, routed and serialized like the ones arriving over TCP.
+//!
+//! ```no_run
+//! # async fn example() {
+//! use goohttp::{
+//!     axum::Router,
+//!     http_server::HttpServer,
+//!     testing::{
+//!         memory_transport,
+//!         TestClient,
+//!     },
+//! };
+//!
+//! let (listener, connector) = memory_transport();
+//! let mut http_server = HttpServer::bind("127.0.0.1:0", None, None).unwrap();
+//! http_server.serve_on(listener, Router::new()).unwrap();
+//!
+//! let client = TestClient::new(connector);
+//! let response = client.get("/").unwrap();
+//! assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+//! # }
+//! ```
+//!
+//! For more control over a connection, like sending a request in several parts, [`MemoryConnector::connect`] opens one directly.
+
+use std::{
+    collections::VecDeque,
+    io::{
+        self,
+        ErrorKind,
+        Read,
+        Write,
+    },
+    net::{
+        IpAddr,
+        Ipv4Addr,
+        SocketAddr,
+    },
+    sync::{
+        mpsc::{
+            self,
+            Receiver,
+            Sender,
+            TryRecvError,
+        },
+        Arc,
+        Condvar,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::http_server::{
+    Closer,
+    Listener,
+    Stream,
+};
+
+/// The address every [`MemoryListener`] and [`MemoryStream`] reports, since in-memory connections have no real one.
+const MEMORY_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Create a [`MemoryListener`] for an [`HttpServer`](crate::http_server::HttpServer) to [`serve_on`](crate::http_server::HttpServer::serve_on),
+/// together with a [`MemoryConnector`] for opening connections to it. This is the same as [`MemoryListener::new`].
+pub fn memory_transport() -> (MemoryListener, MemoryConnector) {
+    MemoryListener::new()
+}
+
+/// A [`Listener`] accepting the connections opened by its [`MemoryConnector`]s.
+pub struct MemoryListener {
+    /// The server side of every connection opened so far.
+    incoming: Receiver<MemoryStream>,
+}
+impl MemoryListener {
+    /// Create a new MemoryListener together with a [`MemoryConnector`] for opening connections to it.
+    pub fn new() -> (Self, MemoryConnector) {
+        let (sender, incoming) = mpsc::channel();
+        (Self { incoming }, MemoryConnector { sender })
+    }
+}
+impl Listener for MemoryListener {
+    fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)> {
+        match self.incoming.try_recv() {
+            Ok(server) => Ok((Box::new(server), MEMORY_ADDR)),
+            // without connectors, no connection can arrive anymore, which is no reason to stop serving
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => {
+                Err(ErrorKind::WouldBlock.into())
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(MEMORY_ADDR)
+    }
+}
+
+/// Opens connections to the [`MemoryListener`] it was created with. It can be cloned to connect from several places.
+#[derive(Clone)]
+pub struct MemoryConnector {
+    /// Passes the server side of new connections to the listener.
+    sender: Sender<MemoryStream>,
+}
+impl MemoryConnector {
+    /// Open a new connection and return its client side.
+    ///
+    /// # Errors
+    ///
+    /// An error of kind [`ErrorKind::ConnectionRefused`] is returned if the [`MemoryListener`] was dropped.
+    pub fn connect(&self) -> io::Result<MemoryStream> {
+        let to_server = Arc::new(Pipe::default());
+        let to_client = Arc::new(Pipe::default());
+        let server = MemoryStream {
+            incoming: to_server.clone(),
+            outgoing: to_client.clone(),
+            read_timeout: None,
+        };
+        self.sender
+            .send(server)
+            .map_err(|_| io::Error::from(ErrorKind::ConnectionRefused))?;
+        Ok(MemoryStream {
+            incoming: to_client,
+            outgoing: to_server,
+            read_timeout: None,
+        })
+    }
+}
+
+/// Sends raw requests to an [`HttpServer`](crate::http_server::HttpServer) over the in-memory connections of a [`MemoryConnector`]. \
+/// Every request gets its own connection, whose whole response is read until the server closes it. Requests therefore have to ask for the
+/// connection to be closed, e.g. with `Connection: close`, since reading blocks until then. For the same reason, the server has to run on
+/// another thread, like the ones of a multi-threaded runtime.
+#[derive(Clone)]
+pub struct TestClient {
+    /// Opens the connections to the server.
+    connector: MemoryConnector,
+}
+impl TestClient {
+    /// Create a new TestClient connecting through the given [`MemoryConnector`].
+    pub fn new(connector: MemoryConnector) -> Self {
+        Self { connector }
+    }
+
+    /// Send the given bytes over a new connection, and return everything the server sent back until it closed the connection.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the connection could not be opened, or the response is not valid UTF-8, in which case it is of kind
+    /// [`ErrorKind::InvalidData`].
+    pub fn send(&self, request: &[u8]) -> io::Result<String> {
+        let mut connection = self.connector.connect()?;
+        connection.write_all(request)?;
+
+        let mut response = String::new();
+        connection.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    /// Send a `GET` request for the given path, which asks for the connection to be closed afterwards. See [`send`](Self::send).
+    ///
+    /// # Errors
+    ///
+    /// The same as for [`send`](Self::send).
+    pub fn get(&self, path: &str) -> io::Result<String> {
+        self.send(format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n").as_bytes())
+    }
+}
+
+/// One side of an in-memory connection. Reads block until the other side writes something or gets dropped, in which case the connection
+/// counts as closed.
+pub struct MemoryStream {
+    /// The data written by the other side.
+    incoming: Arc<Pipe>,
+    /// The data written by this side.
+    outgoing: Arc<Pipe>,
+    /// The longest time a read waits for data.
+    read_timeout: Option<Duration>,
+}
+impl MemoryStream {
+    /// Limit the time a read waits for data, or remove the limit with `None`. A read running into the limit fails with an error of kind
+    /// [`ErrorKind::WouldBlock`].
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+}
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.incoming.lock();
+        while state.data.is_empty() && !state.closed {
+            state = match deadline {
+                Some(deadline) => {
+                    let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+                        return Err(ErrorKind::WouldBlock.into());
+                    };
+                    self.incoming
+                        .changed
+                        .wait_timeout(state, timeout)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .incoming
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+
+        let length = buf.len().min(state.data.len());
+        for (byte, data) in buf.iter_mut().zip(state.data.drain(..length)) {
+            *byte = data;
+        }
+        Ok(length)
+    }
+}
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.outgoing.lock();
+        if state.closed {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        state.data.extend(buf);
+        self.outgoing.changed.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Stream for MemoryStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        MemoryStream::set_read_timeout(self, timeout);
+        Ok(())
+    }
+
+    fn closer(&self) -> Option<Closer> {
+        let pipes = [self.incoming.clone(), self.outgoing.clone()];
+        Some(Box::new(move || {
+            for pipe in &pipes {
+                pipe.close();
+            }
+        }))
+    }
+
+    /// The connection counts as closed once the other side was dropped or closed, so nothing written to it would arrive anymore.
+    fn peer_closed(&self) -> io::Result<bool> {
+        Ok(self.outgoing.lock().closed)
+    }
+}
+/// Dropping one side closes the connection, so reads of the other side reach its end once the remaining data is read, and its writes
+/// fail.
+impl Drop for MemoryStream {
+    fn drop(&mut self) {
+        self.incoming.close();
+        self.outgoing.close();
+    }
+}
+
+/// The data flowing in one direction of an in-memory connection.
+#[derive(Default)]
+struct Pipe {
+    /// The data and whether the connection was closed.
+    state: Mutex<PipeState>,
+    /// Gets notified whenever the state changes.
+    changed: Condvar,
+}
+impl Pipe {
+    /// Lock the state of this pipe. A panic while it was locked cannot leave it half-changed, so poisoning gets ignored.
+    fn lock(&self) -> MutexGuard<'_, PipeState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    /// Mark the connection as closed and wake up every side waiting for data.
+    fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+}
+
+/// The state of a [`Pipe`].
+#[derive(Default)]
+struct PipeState {
+    /// The data that was written, but not read yet.
+    data: VecDeque<u8>,
+    /// Whether one side of the connection was dropped or closed.
+    closed: bool,
+}
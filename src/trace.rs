@@ -0,0 +1,293 @@
+//! This module provides the [`TraceContextLayer`], which continues the distributed traces of incoming requests as described by the
+//! [W3C Trace Context](https://www.w3.org/TR/trace-context/) specification.
+
+use std::{
+    fmt::{
+        self,
+        Write,
+    },
+    future::Future,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::http::{
+    HeaderMap,
+    HeaderValue,
+    Request,
+    Response,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::Instrument;
+
+/// The header carrying the trace id and the id of the calling span.
+const TRACEPARENT: &str = "traceparent";
+/// The header carrying vendor specific data about the trace.
+const TRACESTATE: &str = "tracestate";
+/// The flag of the `traceparent` header marking the trace as sampled.
+const SAMPLED: u8 = 0x01;
+
+tokio::task_local! {
+    /// The trace context of the request the current task is answering.
+    static CURRENT: TraceContext;
+}
+
+/// Continues the trace of every request carrying a valid `traceparent` header, and starts a new trace for every other request. \
+/// Every request gets its own span id, which gets recorded together with the trace id and the span id of the caller in a
+/// [`tracing::Span`] named `request`. The route runs inside this span, and gets the [`TraceContext`] of its request both as an
+/// extension and through [`TraceContext::current`].
+///
+/// goohttp does not provide an HTTP client, so outgoing requests have to get the context added with [`TraceContext::inject`] to continue
+/// the trace on the next server:
+///
+/// ```
+/// use goohttp::{
+///     axum::{
+///         http::HeaderMap,
+///         routing::get,
+///         Router,
+///     },
+///     trace::{
+///         TraceContext,
+///         TraceContextLayer,
+///     },
+/// };
+///
+/// async fn index() -> String {
+///     let mut headers = HeaderMap::new();
+///     if let Some(context) = TraceContext::current() {
+///         context.inject(&mut headers);
+///     }
+///     // send an outgoing request with these headers
+///     format!("{headers:?}")
+/// }
+///
+/// let router: Router = Router::new()
+///     .route("/", get(index))
+///     .layer(TraceContextLayer);
+/// ```
+///
+/// A `traceparent` header not following the specification gets ignored together with the `tracestate` header, as the specification asks
+/// for. If no span id could be generated, because the random number generator of the system failed, the request gets answered without a
+/// trace context instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceContextLayer;
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextService { inner }
+    }
+}
+
+/// The service created by the [`TraceContextLayer`].
+#[derive(Clone, Debug)]
+pub struct TraceContextService<S> {
+    /// The service answering the requests.
+    inner: S,
+}
+impl<S, RequestBody, ResponseBody> Service<Request<RequestBody>> for TraceContextService<S>
+where
+    S: Service<Request<RequestBody>, Response = Response<ResponseBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<RequestBody>) -> Self::Future {
+        let Some(context) = TraceContext::from_headers(request.headers()) else {
+            return Box::pin(self.inner.call(request));
+        };
+        request.extensions_mut().insert(context.clone());
+
+        let span = tracing::info_span!(
+            "request",
+            method = %request.method(),
+            path = request.uri().path(),
+            trace_id = %context.trace_id(),
+            span_id = %context.span_id(),
+            parent_span_id = context.parent_span_id().map(tracing::field::display),
+        );
+        let response = self.inner.call(request);
+        Box::pin(CURRENT.scope(context, response.instrument(span)))
+    }
+}
+
+/// The position of a request in a distributed trace, which the [`TraceContextLayer`] adds to the extensions of every request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The id of the whole trace.
+    trace_id: [u8; 16],
+    /// The id of the span answering the request.
+    span_id: [u8; 8],
+    /// The id of the span of the caller, if the request continued a trace.
+    parent_span_id: Option<[u8; 8]>,
+    /// The flags of the trace, like whether it is sampled.
+    flags: u8,
+    /// The vendor specific data about the trace, which gets passed on unchanged.
+    trace_state: Option<HeaderValue>,
+}
+impl TraceContext {
+    /// Get the trace context of the request the current task is answering. `None` is returned outside of routes behind a
+    /// [`TraceContextLayer`], including in tasks spawned by them.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Get the id of the trace as 32 lowercase hex digits.
+    pub fn trace_id(&self) -> String {
+        hex(&self.trace_id)
+    }
+
+    /// Get the id of the span answering the request as 16 lowercase hex digits.
+    pub fn span_id(&self) -> String {
+        hex(&self.span_id)
+    }
+
+    /// Get the id of the span of the caller as 16 lowercase hex digits. `None` is returned if the request started a new trace.
+    pub fn parent_span_id(&self) -> Option<String> {
+        self.parent_span_id.as_ref().map(|span_id| hex(span_id))
+    }
+
+    /// Check whether the caller marked the trace as sampled. New traces are always sampled.
+    pub fn sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+
+    /// Get the `traceparent` header for an outgoing request, which makes the span answering the request the parent of the next one.
+    pub fn traceparent(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.to_string())
+            .expect("Hex digits should be valid in a header value.")
+    }
+
+    /// Add the `traceparent` and `tracestate` headers for an outgoing request to the given map, replacing the ones already in there.
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        headers.insert(TRACEPARENT, self.traceparent());
+        match &self.trace_state {
+            Some(trace_state) => {
+                headers.insert(TRACESTATE, trace_state.clone());
+            }
+            None => {
+                headers.remove(TRACESTATE);
+            }
+        }
+    }
+
+    /// Create the trace context of a request with the given headers, which continues the trace of a valid `traceparent` header. `None`
+    /// is returned if the random number generator of the system failed.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut span_id = [0; 8];
+        random_id(&mut span_id)?;
+
+        let parent = headers
+            .get(TRACEPARENT)
+            .and_then(|traceparent| traceparent.to_str().ok())
+            .and_then(parse_traceparent);
+        if let Some((trace_id, parent_span_id, flags)) = parent {
+            // several `tracestate` headers have to be combined like a list
+            let trace_state: Vec<&str> = headers
+                .get_all(TRACESTATE)
+                .iter()
+                .filter_map(|trace_state| trace_state.to_str().ok())
+                .map(str::trim)
+                .filter(|trace_state| !trace_state.is_empty())
+                .collect();
+            return Some(Self {
+                trace_id,
+                span_id,
+                parent_span_id: Some(parent_span_id),
+                flags,
+                trace_state: HeaderValue::from_str(&trace_state.join(","))
+                    .ok()
+                    .filter(|trace_state| !trace_state.is_empty()),
+            });
+        }
+
+        let mut trace_id = [0; 16];
+        random_id(&mut trace_id)?;
+        Some(Self {
+            trace_id,
+            span_id,
+            parent_span_id: None,
+            flags: SAMPLED,
+            trace_state: None,
+        })
+    }
+}
+impl fmt::Display for TraceContext {
+    /// Format this trace context like its [`traceparent`](Self::traceparent) header.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{}-{}-{:02x}",
+            self.trace_id(),
+            self.span_id(),
+            self.flags
+        )
+    }
+}
+
+/// Parse the given `traceparent` header into its trace id, the span id of the caller, and the flags. `None` is returned if the header does
+/// not follow the specification.
+fn parse_traceparent(traceparent: &str) -> Option<([u8; 16], [u8; 8], u8)> {
+    let mut fields = traceparent.trim().split('-');
+    let [version] = parse_hex::<1>(fields.next()?)?;
+    let trace_id = parse_hex::<16>(fields.next()?)?;
+    let parent_span_id = parse_hex::<8>(fields.next()?)?;
+    let [flags] = parse_hex::<1>(fields.next()?)?;
+
+    // later versions may add fields, which this version does not know about
+    if version == 0xff || (version == 0 && fields.next().is_some()) {
+        return None;
+    }
+    if trace_id == [0; 16] || parent_span_id == [0; 8] {
+        return None;
+    }
+    Some((trace_id, parent_span_id, flags))
+}
+
+/// Parse the given lowercase hex digits into exactly `N` bytes.
+fn parse_hex<const N: usize>(digits: &str) -> Option<[u8; N]> {
+    if digits.len() != N * 2
+        || !digits
+            .bytes()
+            .all(|digit| digit.is_ascii_digit() || (b'a'..=b'f').contains(&digit))
+    {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (index, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Format the given bytes as lowercase hex digits.
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// Fill the given id with random bytes, which are not all zero, since the specification forbids such ids. `None` is returned if the
+/// random number generator of the system failed.
+fn random_id(id: &mut [u8]) -> Option<()> {
+    loop {
+        getrandom::getrandom(id).ok()?;
+        if id.iter().any(|byte| *byte != 0) {
+            return Some(());
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! This module provides the [`WebDavHandler`], which makes a directory accessible to the file managers of desktop systems.
+
+use std::{
+    fmt::Write,
+    fs::{
+        self,
+        File,
+    },
+    future::Future,
+    io::{
+        self,
+        ErrorKind,
+        Read,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use axum::{
+    body::{
+        Body,
+        Bytes,
+        HttpBody,
+    },
+    handler::Handler,
+    http::{
+        header::{
+            ALLOW,
+            CONTENT_LENGTH,
+            CONTENT_TYPE,
+        },
+        HeaderMap,
+        HeaderValue,
+        Request,
+        StatusCode,
+    },
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+use futures_core::Stream;
+
+use crate::{
+    __private::encode_path_segment,
+    response::StreamBody,
+};
+
+/// The methods the [`WebDavHandler`] answers.
+const ALLOWED_METHODS: &str = "OPTIONS, PROPFIND, GET, PUT, DELETE, MKCOL, COPY";
+/// The number of bytes of a downloaded file read and sent at once.
+const FILE_CHUNK_SIZE: usize = 4 * 1024;
+
+/// Serves the files in the given directory over [WebDAV](https://datatracker.ietf.org/doc/html/rfc4918), so desktop file managers can
+/// open them as a network drive. This makes the files on the SD card of a device accessible without a dedicated app.
+///
+/// The path of a request gets mapped onto the root directory as it is, so the handler should be added as a fallback or nested service:
+/// ```
+/// use goohttp::{
+///     axum::Router,
+///     webdav::WebDavHandler,
+/// };
+///
+/// let router: Router = Router::new().fallback(WebDavHandler {
+///     root: "/sdcard".into(),
+/// });
+/// ```
+///
+/// The following methods are supported:
+///
+/// | Method     | Action                                                                                       |
+/// |------------|----------------------------------------------------------------------------------------------|
+/// | `OPTIONS`  | Tell the client that WebDAV is supported.                                                    |
+/// | `PROPFIND` | List the type and size of a file or directory, and the entries of a directory unless `Depth: 0` is sent. |
+/// | `GET`      | Download a file.                                                                             |
+/// | `PUT`      | Upload a file, replacing an existing one.                                                    |
+/// | `DELETE`   | Delete a file or a directory with all of its content.                                        |
+/// | `MKCOL`    | Create a directory.                                                                          |
+/// | `COPY`     | Copy a file or a directory to the path in the `Destination` header.                          |
+///
+/// Downloaded files get read and sent in chunks of 4KB, so they can be larger than the memory of the device. Uploaded files get received
+/// as a whole, so they should fit into it. Paths containing `..` get rejected with `403 Forbidden`, so no files outside of the root
+/// directory can be accessed. The same goes for deleting the root directory itself, for copying onto it, and for copying a directory into
+/// itself, which would never end.
+#[derive(Clone, Debug)]
+pub struct WebDavHandler {
+    /// The directory whose files get served.
+    pub root: PathBuf,
+}
+impl<S> Handler<(), S, Body> for WebDavHandler {
+    type Future = Pin<Box<dyn Future<Output = Response> + Send>>;
+
+    fn call(self, request: Request<Body>, _: S) -> Self::Future {
+        Box::pin(async move {
+            match self.answer(request).await {
+                Ok(response) => response,
+                Err(status) => status.into_response(),
+            }
+        })
+    }
+}
+impl WebDavHandler {
+    /// Answer the given request, or return the status code to answer it with if it failed.
+    async fn answer(&self, request: Request<Body>) -> Result<Response, StatusCode> {
+        let path = self.file_path(request.uri().path())?;
+        match request.method().as_str() {
+            "OPTIONS" => Ok((
+                [
+                    ("DAV", HeaderValue::from_static("1")),
+                    (ALLOW.as_str(), HeaderValue::from_static(ALLOWED_METHODS)),
+                ],
+                StatusCode::OK,
+            )
+                .into_response()),
+            "PROPFIND" => propfind(&path, request.uri().path(), request.headers()),
+            "GET" => {
+                if path.is_dir() {
+                    return Err(StatusCode::METHOD_NOT_ALLOWED);
+                }
+                let file = File::open(&path).map_err(status_of)?;
+                let length = file.metadata().map_err(status_of)?.len();
+                Ok((
+                    [
+                        (
+                            CONTENT_TYPE,
+                            HeaderValue::from_static("application/octet-stream"),
+                        ),
+                        (CONTENT_LENGTH, HeaderValue::from(length)),
+                    ],
+                    StreamBody(FileChunks(file)),
+                )
+                    .into_response())
+            }
+            "PUT" => {
+                if path.is_dir() {
+                    return Err(StatusCode::METHOD_NOT_ALLOWED);
+                }
+                let existed = path.exists();
+                let file = read_body(request.into_body()).await?;
+                fs::write(&path, file).map_err(status_of)?;
+                Ok(created_or_replaced(existed))
+            }
+            "DELETE" => {
+                if path == self.root {
+                    return Err(StatusCode::FORBIDDEN);
+                }
+                if path.is_dir() {
+                    fs::remove_dir_all(&path).map_err(status_of)?;
+                } else {
+                    fs::remove_file(&path).map_err(status_of)?;
+                }
+                Ok(StatusCode::NO_CONTENT.into_response())
+            }
+            "MKCOL" => {
+                if path.exists() {
+                    return Err(StatusCode::METHOD_NOT_ALLOWED);
+                }
+                fs::create_dir(&path).map_err(status_of)?;
+                Ok(StatusCode::CREATED.into_response())
+            }
+            "COPY" => {
+                let destination = request
+                    .headers()
+                    .get("destination")
+                    .and_then(|destination| destination.to_str().ok())
+                    .ok_or(StatusCode::BAD_REQUEST)?;
+                // the destination is usually an absolute URI
+                let destination = match destination.find("://") {
+                    Some(scheme_end) => {
+                        let authority = &destination[scheme_end + 3..];
+                        authority.find('/').map_or("/", |path| &authority[path..])
+                    }
+                    None => destination,
+                };
+                let destination = self.file_path(destination)?;
+                // overwriting the root would delete every file, and a copy inside of the source would be copied again and again
+                if destination == self.root || destination.starts_with(&path) {
+                    return Err(StatusCode::FORBIDDEN);
+                }
+                let overwrite = request
+                    .headers()
+                    .get("overwrite")
+                    .is_none_or(|overwrite| overwrite != "F");
+
+                if !path.exists() {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+                let existed = destination.exists();
+                if existed && !overwrite {
+                    return Err(StatusCode::PRECONDITION_FAILED);
+                }
+                if existed && destination.is_dir() {
+                    fs::remove_dir_all(&destination).map_err(status_of)?;
+                }
+                copy(&path, &destination).map_err(status_of)?;
+                Ok(created_or_replaced(existed))
+            }
+            _ => Ok((
+                [(ALLOW, HeaderValue::from_static(ALLOWED_METHODS))],
+                StatusCode::METHOD_NOT_ALLOWED,
+            )
+                .into_response()),
+        }
+    }
+
+    /// Get the path of the file the given, percent-encoded path of a request points to.
+    fn file_path(&self, request_path: &str) -> Result<PathBuf, StatusCode> {
+        let mut path = self.root.clone();
+        for segment in request_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+        {
+            let segment = decode_path_segment(segment).ok_or(StatusCode::BAD_REQUEST)?;
+            if segment == "." || segment == ".." || segment.contains(['/', '\\']) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+            path.push(segment);
+        }
+        Ok(path)
+    }
+}
+
+/// Answer a `PROPFIND` request for the given file or directory with a `207 Multi-Status` listing it, and the entries of a directory
+/// unless the `Depth` header is `0`.
+fn propfind(path: &Path, request_path: &str, headers: &HeaderMap) -> Result<Response, StatusCode> {
+    let metadata = fs::metadata(path).map_err(status_of)?;
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n",
+    );
+    let mut href = String::new();
+    for segment in request_path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+    {
+        href.push('/');
+        encode_path_segment(
+            &mut href,
+            &decode_path_segment(segment).ok_or(StatusCode::BAD_REQUEST)?,
+        );
+    }
+    if href.is_empty() || metadata.is_dir() {
+        href.push('/');
+    }
+    write_response_element(&mut xml, &href, &metadata);
+
+    let depth_zero = headers.get("depth").is_some_and(|depth| depth == "0");
+    if metadata.is_dir() && !depth_zero {
+        let mut entries = fs::read_dir(path)
+            .map_err(status_of)?
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let mut entry_href = href.clone();
+            encode_path_segment(&mut entry_href, &entry.file_name().to_string_lossy());
+            if metadata.is_dir() {
+                entry_href.push('/');
+            }
+            write_response_element(&mut xml, &entry_href, &metadata);
+        }
+    }
+    xml.push_str("</D:multistatus>\n");
+
+    Ok((
+        StatusCode::MULTI_STATUS,
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/xml; charset=utf-8"),
+        )],
+        xml,
+    )
+        .into_response())
+}
+
+/// Append the `response` element describing the file or directory at the given href to the given XML.
+fn write_response_element(xml: &mut String, href: &str, metadata: &fs::Metadata) {
+    let _ = write!(
+        xml,
+        "<D:response><D:href>{href}</D:href><D:propstat><D:prop>"
+    );
+    if metadata.is_dir() {
+        xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        let _ = write!(
+            xml,
+            "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>",
+            metadata.len()
+        );
+    }
+    xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>\n");
+}
+
+/// The content of a file, which gets read chunk by chunk while it is sent.
+struct FileChunks(File);
+impl Stream for FileChunks {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut chunk = vec![0; FILE_CHUNK_SIZE];
+        match self.0.read(&mut chunk) {
+            Ok(0) => Poll::Ready(None),
+            Ok(read) => {
+                chunk.truncate(read);
+                Poll::Ready(Some(Ok(chunk.into())))
+            }
+            Err(error) => Poll::Ready(Some(Err(error))),
+        }
+    }
+}
+
+/// Read the whole given body.
+async fn read_body(mut body: Body) -> Result<Vec<u8>, StatusCode> {
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    Ok(bytes)
+}
+
+/// Copy the given file or directory with all of its content to the given path.
+fn copy(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+/// Get the response to a request which created a file if it did not exist before, or replaced it otherwise.
+fn created_or_replaced(existed: bool) -> Response {
+    if existed {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::CREATED.into_response()
+    }
+}
+
+/// Get the status code describing the given error of the file system.
+fn status_of(error: std::io::Error) -> StatusCode {
+    match error.kind() {
+        // a parent directory is missing
+        ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        ErrorKind::AlreadyExists => StatusCode::METHOD_NOT_ALLOWED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Decode the percent-encoded characters of the given path segment. `None` is returned if it is not valid UTF-8 afterwards.
+fn decode_path_segment(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
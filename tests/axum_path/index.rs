@@ -0,0 +1,5 @@
+use crate::web::response::IntoResponse;
+
+pub async fn index() -> impl IntoResponse {
+    "index".into_response()
+}
\ No newline at end of file
@@ -0,0 +1,38 @@
+use goohttp::router;
+use hyper::{
+    body::HttpBody,
+    service::Service,
+    Body,
+    Request,
+};
+
+/// Stands in for a version of axum the user depends on directly.
+mod web {
+    pub use goohttp::axum::*;
+}
+
+/// Would collide with the `get` of axum if the macro imported it.
+#[allow(dead_code)]
+fn get() {}
+
+#[tokio::test]
+async fn main() {
+    let mut website: web::Router = website();
+
+    let index_response = website
+        .call(Request::get(urls::index).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(std::str::from_utf8(&index_response).unwrap(), "index");
+}
+
+router! {
+    #![use_axum = crate::web]
+    website {
+        index, get
+    }
+}
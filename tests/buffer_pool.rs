@@ -0,0 +1,222 @@
+//! This test counts every allocation of its process, so it runs on its own instead of next to the other tests of the HttpServer.
+
+use std::{
+    alloc::{
+        GlobalAlloc,
+        Layout,
+        System,
+    },
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        ErrorKind,
+        Read,
+        Write,
+    },
+    net::SocketAddr,
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    time::Duration,
+};
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    http_server::{
+        HttpServer,
+        Listener,
+        Stream,
+    },
+    testing::{
+        MemoryListener,
+        MemoryStream,
+    },
+};
+
+/// Counts the allocations of this process.
+struct CountingAllocator;
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// The number of allocations since this process was started.
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The number of requests whose allocations get counted.
+const REQUESTS: u64 = 200;
+
+/// Read a single response with a `Content-Length` from the given connection.
+fn read_response(connection: &mut BufReader<MemoryStream>, line: &mut String) {
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        assert_ne!(connection.read_line(line).unwrap(), 0);
+        if line == "\r\n" {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("content-length: ") {
+            content_length = value.trim().parse().unwrap();
+        }
+    }
+    let mut body = [0; 64];
+    connection.read_exact(&mut body[..content_length]).unwrap();
+}
+
+/// Count the allocations of both the given HttpServer and its client while answering [`REQUESTS`] requests.
+async fn allocations(mut http_server: HttpServer) -> u64 {
+    let (listener, connector) = MemoryListener::new();
+    http_server
+        .serve_on(
+            listener,
+            Router::new().route("/", get(|| async { "Hello, World!" })),
+        )
+        .unwrap();
+    let mut connection = BufReader::new(connector.connect().unwrap());
+    let mut line = String::with_capacity(128);
+    let request = b"GET / HTTP/1.1\r\nHost: 192.168.4.1\r\n\r\n";
+
+    // the first requests allocate the buffers of the connection
+    for _ in 0..10 {
+        connection.get_mut().write_all(request).unwrap();
+        read_response(&mut connection, &mut line);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    for _ in 0..REQUESTS {
+        connection.get_mut().write_all(request).unwrap();
+        read_response(&mut connection, &mut line);
+    }
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations;
+
+    drop(connection);
+    http_server.shutdown().await;
+    allocations
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pooled_buffers_save_allocations() {
+    let unpooled = allocations(HttpServer::bind("127.0.0.1:0", None, None).unwrap()).await;
+    let pooled = allocations(
+        HttpServer::bind("127.0.0.1:0", None, None)
+            .unwrap()
+            .response_buffer_pool(4, 1024),
+    )
+    .await;
+
+    // every response saves the allocation of its buffer, give or take some allocations of other tasks
+    assert!(
+        pooled + REQUESTS / 2 <= unpooled,
+        "{pooled} allocations with the pool, {unpooled} without it"
+    );
+}
+
+/// Wraps the server side of the connections of a [`MemoryListener`] in [`DiscardingStream`]s.
+struct DiscardingListener {
+    /// The listener of the wrapped connections.
+    listener: MemoryListener,
+    /// Whether a connection not sending anything for a moment gets reset.
+    reset_stalled: bool,
+}
+impl Listener for DiscardingListener {
+    fn accept(&self) -> io::Result<(Box<dyn Stream>, SocketAddr)> {
+        let (mut stream, addr) = self.listener.accept()?;
+        if self.reset_stalled {
+            stream.set_read_timeout(Some(Duration::from_millis(1)))?;
+        }
+        Ok((Box::new(DiscardingStream(stream)), addr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+}
+
+/// Discards everything written to it, unlike an in-memory connection, which has to allocate a buffer for it. A read running into the
+/// read timeout of the wrapped connection fails as if it was reset.
+struct DiscardingStream(Box<dyn Stream>);
+impl Read for DiscardingStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).map_err(|error| match error.kind() {
+            ErrorKind::WouldBlock => ErrorKind::ConnectionReset.into(),
+            _ => error,
+        })
+    }
+}
+impl Write for DiscardingStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Stream for DiscardingStream {}
+
+/// Count the allocations of an HttpServer allowing a single header while it reads [`REQUESTS`] connections sending the given request.
+/// With `reset_stalled`, the connections get reset once the request was sent.
+async fn rejection_allocations(request: &[u8], reset_stalled: bool) -> u64 {
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .max_request_headers(1);
+    let listener = DiscardingListener {
+        listener,
+        reset_stalled,
+    };
+    http_server.serve_on(listener, Router::new()).unwrap();
+    let reject = || {
+        let mut connection = connector.connect().unwrap();
+        connection.write_all(request).unwrap();
+        // nothing arrives, but the read only ends once the server closed the connection
+        assert_eq!(connection.read(&mut [0]).unwrap(), 0);
+    };
+
+    for _ in 0..10 {
+        reject();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    for _ in 0..REQUESTS {
+        reject();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations;
+
+    http_server.shutdown().await;
+    allocations
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn error_responses_do_not_allocate() {
+    // a malformed request line gets a `400 Bad Request`, and a second header a `431 Request Header Fields Too Large`, while the parts in
+    // front of their last line break get read the same way, but end with the connection
+    for (rejected, reset) in [
+        (&b"GARBAGE\r\n"[..], &b"GARBAGE"[..]),
+        (
+            b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n",
+            b"GET / HTTP/1.1\r\nA: 1\r\nB: 2",
+        ),
+    ] {
+        let answered = rejection_allocations(rejected, false).await;
+        let unanswered = rejection_allocations(reset, true).await;
+
+        // sending the error response is the only difference, which must not allocate anything, give or take a few allocations of other
+        // tasks
+        assert!(
+            answered <= unanswered + 10,
+            "{answered} allocations with error responses, {unanswered} without them"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use goohttp::build::verify_route_modules;
+
+#[test]
+fn existing_route_modules() {
+    verify_route_modules(
+        env!("CARGO_MANIFEST_DIR"),
+        &[
+            ("tests/router_macro", "api"),
+            ("tests/router_macro", "pages"),
+            ("tests/router_macro/api", "say_hello"),
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "the module of the route `say_goodbye` is missing")]
+fn missing_route_module() {
+    verify_route_modules(
+        env!("CARGO_MANIFEST_DIR"),
+        &[
+            ("tests/router_macro/api", "say_hello"),
+            ("tests/router_macro/api", "say_goodbye"),
+        ],
+    );
+}
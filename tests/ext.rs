@@ -0,0 +1,32 @@
+use goohttp::{
+    axum::{
+        body::Body,
+        http::{
+            Request,
+            StatusCode,
+        },
+        routing::get,
+        Router,
+    },
+    ext::RouterExt,
+};
+use hyper::body::to_bytes;
+
+#[tokio::test]
+async fn try_call() {
+    let mut router = Router::new().route("/", get(|| async { "Hello!" }));
+
+    let response = router
+        .try_call(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(to_bytes(response.into_body()).await.unwrap(), "Hello!");
+
+    // errors of the routing get turned into responses as well
+    let response = router
+        .try_call(Request::post("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
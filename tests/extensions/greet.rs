@@ -0,0 +1,16 @@
+use goohttp::axum::{
+    response::IntoResponse,
+    Extension,
+};
+
+use crate::{
+    Greeting,
+    Visitors,
+};
+
+pub async fn greet(
+    Extension(Greeting(greeting)): Extension<Greeting>,
+    Extension(Visitors(visitors)): Extension<Visitors>,
+) -> impl IntoResponse {
+    format!("{greeting}, visitor {visitors}").into_response()
+}
@@ -0,0 +1,52 @@
+use goohttp::{
+    axum::Router,
+    router,
+};
+use hyper::{
+    body::HttpBody,
+    service::Service,
+    Body,
+    Request,
+};
+
+/// Stands in for a shared resource like a database pool.
+#[derive(Clone)]
+pub struct Greeting(pub &'static str);
+impl Default for Greeting {
+    fn default() -> Self {
+        Self("Hello")
+    }
+}
+
+/// Stands in for a second shared resource like a log sink.
+#[derive(Clone, Default)]
+pub struct Visitors(pub u32);
+
+/// Send a request for the given path through the given router and get the body of the response.
+async fn request(mut router: Router, path: &str) -> String {
+    let body = router
+        .call(Request::get(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn main() {
+    assert_eq!(request(website(), urls::greet).await, "Hello, visitor 0");
+    assert_eq!(
+        request(with_extensions(Greeting("Hi"), Visitors(7)), urls::greet).await,
+        "Hi, visitor 7"
+    );
+}
+
+router! {
+    #[extension(Greeting, Visitors)]
+    website {
+        greet, get
+    }
+}
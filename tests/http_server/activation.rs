@@ -0,0 +1,142 @@
+use std::{
+    env,
+    io::{
+        self,
+        Read,
+    },
+    net::TcpListener,
+    os::{
+        fd::AsRawFd,
+        unix::process::CommandExt,
+    },
+    process::{
+        Command,
+        Stdio,
+    },
+};
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    error::ActivationError,
+    http_server::HttpServer,
+    Error,
+};
+
+use crate::send;
+
+/// Set when this test binary runs as the process activated by [`systemd_sockets`].
+const CHILD: &str = "GOOHTTP_ACTIVATION_CHILD";
+
+/// Pass two pre-bound listeners to a new process running [`activated_child`], the way systemd does, and check that both get served.
+#[test]
+fn systemd_sockets() {
+    let listeners = [
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    ];
+    let fds = listeners.each_ref().map(|listener| listener.as_raw_fd());
+    let mut command = Command::new(env::current_exe().unwrap());
+    command
+        .args(["activation::activated_child", "--exact", "--nocapture"])
+        .env(CHILD, "1")
+        .env("LISTEN_FDS", "2")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    // SAFETY: only async-signal-safe functions are called between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            // move the listeners out of the way first, in case one of them already is the target of the other
+            let high = fds.map(|fd| libc::fcntl(fd, libc::F_DUPFD, 10));
+            for (target, fd) in [3, 4].into_iter().zip(high) {
+                if fd < 0 || libc::dup2(fd, target) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    let child = command.spawn().unwrap();
+
+    for listener in &listeners {
+        let response = send(
+            listener.local_addr().unwrap(),
+            b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.ends_with("\r\n\r\nactivated"), "{response:?}");
+    }
+
+    // closes stdin, which stops the child
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+/// Serve the sockets passed by [`systemd_sockets`] until stdin gets closed. Without being started by it, this test does nothing.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn activated_child() {
+    if env::var_os(CHILD).is_none() {
+        return;
+    }
+    // the id of this process is only known after it was started
+    env::set_var("LISTEN_PID", std::process::id().to_string());
+
+    let mut http_server = HttpServer::from_systemd(None, None).unwrap();
+    http_server
+        .serve(Router::new().route("/", get(|| async { "activated" })))
+        .unwrap();
+    assert!(matches!(
+        HttpServer::from_systemd(None, None),
+        Err(Error::Activation(ActivationError::AlreadyTaken))
+    ));
+
+    tokio::task::spawn_blocking(|| io::stdin().read_to_end(&mut vec![]))
+        .await
+        .unwrap()
+        .unwrap();
+    http_server.shutdown().await;
+}
+
+#[test]
+fn misconfigured_activation() {
+    // the checks below fail before any file descriptor gets looked at, so none of this process gets taken over
+    let pid = std::process::id();
+    for (listen_pid, listen_fds, expected) in [
+        (None, Some("1"), ActivationError::NotActivated),
+        (Some("self"), None, ActivationError::NotActivated),
+        (
+            Some("abc"),
+            Some("1"),
+            ActivationError::InvalidVariable("LISTEN_PID"),
+        ),
+        (Some("1"), Some("1"), ActivationError::OtherProcess(1)),
+        (
+            Some("self"),
+            Some("many"),
+            ActivationError::InvalidVariable("LISTEN_FDS"),
+        ),
+        (Some("self"), Some("0"), ActivationError::NoSockets),
+    ] {
+        match listen_pid {
+            Some("self") => env::set_var("LISTEN_PID", pid.to_string()),
+            Some(listen_pid) => env::set_var("LISTEN_PID", listen_pid),
+            None => env::remove_var("LISTEN_PID"),
+        }
+        match listen_fds {
+            Some(listen_fds) => env::set_var("LISTEN_FDS", listen_fds),
+            None => env::remove_var("LISTEN_FDS"),
+        }
+
+        match HttpServer::from_systemd(None, None) {
+            Err(Error::Activation(error)) => assert_eq!(error, expected),
+            result => panic!("expected {expected:?}, got {:?}", result.map(|_| ())),
+        }
+    }
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+}
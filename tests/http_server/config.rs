@@ -0,0 +1,155 @@
+use std::{
+    io::{
+        self,
+        ErrorKind,
+    },
+    sync::mpsc,
+    time::Duration,
+};
+
+use goohttp::{
+    axum::Router,
+    error::ParseError,
+    http_server::{
+        ConfigError,
+        HttpServer,
+    },
+    Error,
+};
+
+use crate::{
+    capture_logs,
+    free_addr,
+    logs_of,
+    send,
+    serve,
+};
+
+#[tokio::test]
+async fn contradicting_settings() {
+    let mut http_server = HttpServer::bind(free_addr(), None, Some(Duration::ZERO)).unwrap();
+    assert_eq!(http_server.validate(), Err(ConfigError::ZeroRefreshRate));
+    let error = http_server.serve(Router::new()).unwrap_err();
+    assert!(matches!(error, Error::Config(ConfigError::ZeroRefreshRate)));
+    assert!(!http_server.is_running());
+
+    let mut http_server = HttpServer::bind(free_addr(), None, None)
+        .unwrap()
+        .max_response_body(8)
+        .not_found_page("<h1>Not here</h1>", "text/html");
+    assert_eq!(
+        http_server.validate(),
+        Err(ConfigError::NotFoundPageTooLarge { page: 17, limit: 8 })
+    );
+    let error = io::Error::from(http_server.serve(Router::new()).unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(error.to_string().contains("17 bytes"));
+
+    let http_server = HttpServer::bind(free_addr(), None, None)
+        .unwrap()
+        .queue_depth(0);
+    assert_eq!(http_server.validate(), Err(ConfigError::ZeroQueueDepth));
+
+    let http_server = HttpServer::bind(free_addr(), None, None)
+        .unwrap()
+        .dedicated_runtime(0, 64 * 1024);
+    assert_eq!(http_server.validate(), Err(ConfigError::ZeroWorkerThreads));
+
+    let http_server = HttpServer::bind(free_addr(), None, None)
+        .unwrap()
+        .max_response_body(32)
+        .not_found_page("<h1>Not here</h1>", "text/html");
+    assert_eq!(http_server.validate(), Ok(()));
+}
+
+#[test]
+fn unresolvable_address() {
+    assert!(matches!(
+        HttpServer::bind("this address does not exist", None, None),
+        Err(Error::Resolve)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn bind_addr() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind_addr(addr, Some("Resolved"), None);
+    assert_eq!(http_server.local_addr(), addr);
+    assert_eq!(http_server.refresh_rate(), Duration::from_millis(10));
+
+    http_server.serve(Router::new()).unwrap();
+    let response = tokio::task::spawn_blocking(move || {
+        send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+    })
+    .await
+    .unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+        "{response}"
+    );
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn startup_banner() {
+    capture_logs();
+
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("Banner"), None)
+        .unwrap()
+        .startup_banner(true)
+        .max_response_body(4096)
+        .keep_alive_timeout(Duration::from_secs(5));
+    http_server.serve(Router::new()).unwrap();
+    http_server.shutdown().await;
+
+    let logs = logs_of("Banner");
+    for expected in [
+        "==== Banner ====".to_string(),
+        format!("Address              {addr}"),
+        "TLS                  disabled".to_string(),
+        "Keep-alive timeout   5s".to_string(),
+        "Linger               system default".to_string(),
+        "Requests/connection  unlimited".to_string(),
+        "Response body limit  4096 bytes".to_string(),
+        "Accept errors        ignored".to_string(),
+    ] {
+        assert!(
+            logs.contains(&expected),
+            "`{expected}` is missing in {logs:#?}"
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn double_serve() {
+    let (mut http_server, _) = serve(Router::new());
+    assert!(matches!(
+        http_server.serve(Router::new()),
+        Err(Error::AlreadyRunning)
+    ));
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn malformed_requests() {
+    let (results, received_results) = mpsc::channel();
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .on_connection_result(move |result| {
+            let _ = results.send(matches!(result, Err(Error::Parse(ParseError::RequestLine))));
+        });
+    http_server.serve(Router::new()).unwrap();
+
+    let response = send(addr, b"GARBAGE\r\n\r\n");
+    assert_eq!(
+        response,
+        "HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    );
+    assert!(received_results
+        .recv_timeout(Duration::from_secs(1))
+        .unwrap());
+
+    http_server.shutdown().await;
+}
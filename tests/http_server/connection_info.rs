@@ -0,0 +1,50 @@
+use goohttp::{
+    axum::{
+        routing::get,
+        Extension,
+        Router,
+    },
+    http_server::ConnInfo,
+    ConnectionInfo,
+};
+
+use crate::{
+    send,
+    serve,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn connection_info() {
+    let router = Router::new()
+        .route(
+            "/info",
+            get(|ConnInfo(info): ConnInfo| async move {
+                format!(
+                    "[{:?} {} {} {} {}]",
+                    info.version,
+                    info.raw_target,
+                    info.requests_served,
+                    info.tls,
+                    info.peer_addr.ip()
+                )
+            }),
+        )
+        .route(
+            "/extension",
+            get(|Extension(info): Extension<ConnectionInfo>| async move {
+                format!("[{}]", info.requests_served)
+            }),
+        );
+    let (mut http_server, addr) = serve(router);
+
+    let response = send(
+        addr,
+        b"GET http://localhost/info HTTP/1.1\r\n\r\nGET /extension HTTP/1.1\r\n\r\nGET /info?page=%41 HTTP/1.0\r\n\r\n",
+    );
+
+    assert!(response.contains("\r\n\r\n[HTTP/1.1 http://localhost/info 0 false 127.0.0.1]"));
+    assert!(response.contains("\r\n\r\n[1]"));
+    assert!(response.ends_with("\r\n\r\n[HTTP/1.0 /info?page=%41 2 false 127.0.0.1]"));
+
+    http_server.shutdown().await;
+}
@@ -0,0 +1,59 @@
+use goohttp::{
+    axum::http::Method,
+    http_server::{
+        HttpServer,
+        RequestHandler,
+    },
+};
+use hyper::{
+    Body,
+    Request,
+    Response,
+};
+
+use crate::{
+    free_addr,
+    send,
+};
+
+fn index(_: Request<Body>) -> Response<Body> {
+    Response::new(Body::from("index"))
+}
+
+fn echo_query(request: Request<Body>) -> Response<Body> {
+    Response::new(Body::from(request.uri().query().unwrap_or("").to_string()))
+}
+
+fn dispatch(method: &Method, path: &str) -> Option<RequestHandler> {
+    match (method, path) {
+        (&Method::GET, "/") => Some(index),
+        (&Method::GET, "/echo") => Some(echo_query),
+        _ => None,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn low_level_dispatch() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .not_found_page("missing", "text/plain");
+    http_server.serve_dispatch(dispatch).unwrap();
+
+    let response = send(
+        addr,
+        b"GET / HTTP/1.1\r\n\r\nGET /echo?hello HTTP/1.1\r\n\r\nPOST / HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let mut responses = response.split("HTTP/1.1 ").skip(1);
+
+    let index_response = responses.next().unwrap();
+    assert!(index_response.starts_with("200 OK\r\n"));
+    assert!(index_response.contains("content-length: 5\r\n"));
+    assert!(index_response.ends_with("\r\n\r\nindex"));
+    assert!(responses.next().unwrap().ends_with("\r\n\r\nhello"));
+    let not_found_response = responses.next().unwrap();
+    assert!(not_found_response.starts_with("404 Not Found\r\n"));
+    assert!(not_found_response.ends_with("\r\n\r\nmissing"));
+
+    http_server.shutdown().await;
+}
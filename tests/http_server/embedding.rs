@@ -0,0 +1,46 @@
+use std::{
+    net::TcpListener,
+    thread,
+};
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+};
+
+use crate::{
+    free_addr,
+    send,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn own_accept_loop() {
+    let http_server = HttpServer::bind(free_addr(), None, None)
+        .unwrap()
+        .not_found_page("nothing here", "text/plain");
+    let router = Router::new().route("/", get(|| async { "index" }));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let clients = thread::spawn(move || {
+        let index = send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+        let missing = send(addr, b"GET /missing HTTP/1.1\r\nConnection: close\r\n\r\n");
+        (index, missing)
+    });
+
+    for _ in 0..2 {
+        let (client, _) = listener.accept().unwrap();
+        let connection = http_server.serve_connection(client, router.clone());
+        tokio::spawn(connection).await.unwrap().unwrap();
+    }
+    assert_eq!(http_server.idle_connections(), 0);
+
+    let (index, missing) = clients.join().unwrap();
+    assert!(index.ends_with("\r\n\r\nindex"));
+    // the settings of the HttpServer apply, even though it never started serving
+    assert!(missing.ends_with("\r\n\r\nnothing here"));
+    assert!(!http_server.is_running());
+}
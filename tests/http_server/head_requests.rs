@@ -0,0 +1,85 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+    vec::IntoIter,
+};
+
+use futures_core::Stream;
+use goohttp::{
+    axum::{
+        body::Bytes,
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+    response::StreamBody,
+};
+
+use crate::{
+    free_addr,
+    send,
+    send_in_memory,
+    serve_in_memory,
+};
+
+/// A stream yielding the given chunks.
+struct Chunks(IntoIter<&'static str>);
+impl Stream for Chunks {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.next().map(|chunk| Ok(Bytes::from(chunk))))
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn head_requests_get_no_body() {
+    let router = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route(
+            "/stream",
+            get(|| async { StreamBody(Chunks(vec!["first ", "second"].into_iter())) }),
+        );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    // the pipelined `GET` request must not be mistaken for a body of the `HEAD` responses
+    let response = send_in_memory(
+        &connector,
+        b"HEAD / HTTP/1.1\r\n\r\nHEAD /stream HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let responses: Vec<&str> = response.split("HTTP/1.1 ").skip(1).collect();
+    assert_eq!(responses.len(), 3, "{response:?}");
+
+    assert!(responses[0].starts_with("200 OK\r\n"));
+    assert!(responses[0].contains("content-length: 13\r\n"));
+    assert!(responses[0].ends_with("\r\n\r\n"));
+
+    assert!(responses[1].starts_with("200 OK\r\n"));
+    assert!(responses[1].contains("transfer-encoding: chunked\r\n"));
+    assert!(responses[1].ends_with("\r\n\r\n"));
+
+    assert!(responses[2].contains("content-length: 13\r\n"));
+    assert!(responses[2].ends_with("\r\n\r\nHello, World!"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn head_requests_get_no_not_found_page() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .not_found_page("<h1>Not here</h1>", "text/html; charset=utf-8");
+    http_server.serve(Router::new()).unwrap();
+
+    let response = send(addr, b"HEAD /nowhere HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(response.contains("content-length: 17\r\n"));
+    assert!(response.ends_with("\r\n\r\n"));
+
+    http_server.shutdown().await;
+}
@@ -0,0 +1,98 @@
+use goohttp::axum::{
+    body::Body,
+    http::{
+        header::{
+            CACHE_CONTROL,
+            LOCATION,
+        },
+        HeaderValue,
+        Response,
+        StatusCode,
+    },
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+
+use crate::{
+    send_in_memory,
+    serve_in_memory,
+};
+
+/// Create a response with the given header, whose value gets set without checking it.
+#[cfg_attr(debug_assertions, allow(dead_code))]
+fn response_with_header(header: &'static str, value: &'static [u8]) -> Response<Body> {
+    let (mut parts, body) = Response::new(Body::from("body")).into_parts();
+    parts.headers.insert(
+        header,
+        // SAFETY: an invalid value is the point of the test, and only gets written by the server, which checks it
+        unsafe { HeaderValue::from_maybe_shared_unchecked(value) },
+    );
+    parts
+        .headers
+        .append(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Response::from_parts(parts, body)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn valid_headers_are_kept() {
+    let router = Router::new().route(
+        "/",
+        get(|| async {
+            let (mut parts, body) = "body".into_response().into_parts();
+            parts.status = StatusCode::FOUND;
+            parts
+                .headers
+                .insert(LOCATION, HeaderValue::from_static("/login?next=%2F"));
+            parts.headers.insert(
+                "x-note",
+                HeaderValue::from_bytes(b"tab\tand space").unwrap(),
+            );
+            Response::from_parts(parts, body)
+        }),
+    );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    let response = send_in_memory(&connector, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 302 Found\r\n"));
+    assert!(response.contains("location: /login?next=%2F\r\n"));
+    assert!(response.contains("x-note: tab\tand space\r\n"));
+    assert!(response.ends_with("\r\n\r\nbody"));
+
+    http_server.shutdown().await;
+}
+
+// debug builds of `http` refuse to create invalid header values even without checking them
+#[cfg(not(debug_assertions))]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn invalid_headers_get_removed() {
+    let router = Router::new()
+        .route(
+            "/note",
+            get(|| async { response_with_header("x-note", b"note\r\nx-injected: yes") }),
+        )
+        .route(
+            "/redirect",
+            get(|| async { response_with_header("location", b"/\r\n\r\n<script>") }),
+        );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    let response = send_in_memory(
+        &connector,
+        b"GET /note HTTP/1.1\r\n\r\nGET /redirect HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let responses: Vec<&str> = response.split("HTTP/1.1 ").skip(1).collect();
+    assert_eq!(responses.len(), 2, "{response:?}");
+
+    assert!(responses[0].starts_with("200 OK\r\n"));
+    assert!(!responses[0].contains("x-note"));
+    assert!(!responses[0].contains("x-injected"));
+    assert!(responses[0].contains("cache-control: no-cache\r\n"));
+    assert!(responses[0].ends_with("\r\n\r\nbody"));
+
+    assert!(responses[1].starts_with("500 Internal Server Error\r\n"));
+    assert!(!responses[1].contains("<script>"));
+    assert!(responses[1].contains("content-length: 0\r\n"));
+
+    http_server.shutdown().await;
+}
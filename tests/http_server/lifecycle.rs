@@ -0,0 +1,911 @@
+use std::{
+    io::{
+        Read,
+        Write,
+    },
+    net::TcpStream,
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    http_server::{
+        AcceptErrorPolicy,
+        ConfigError,
+        ConnInfo,
+        ConnectionState,
+        HttpServer,
+        RestartPolicy,
+    },
+    Error,
+};
+use tokio::{
+    sync::Notify,
+    time::sleep,
+};
+
+use crate::{
+    capture_logs,
+    free_addr,
+    logs_of,
+    send,
+    serve,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn is_running() {
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+    assert!(!http_server.is_running());
+
+    http_server.serve(Router::new()).unwrap();
+    assert!(http_server.is_running());
+
+    http_server.shutdown().await;
+    assert!(!http_server.is_running());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn shutdown_closes_the_listener() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    http_server.serve(Router::new()).unwrap();
+    http_server.shutdown().await;
+
+    // the address can only be bound again once the listener got dropped
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    http_server.serve(Router::new()).unwrap();
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn dropping_closes_the_listener() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    http_server.serve(Router::new()).unwrap();
+    drop(http_server);
+
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    let mut attempts = 0;
+    while http_server.serve(Router::new()).is_err() {
+        attempts += 1;
+        assert!(attempts < 100, "The address did not become free in time.");
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    // dropping after an explicit shutdown does nothing
+    http_server.shutdown().await;
+    drop(http_server);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn connection_states() {
+    let request_received = Arc::new(Notify::new());
+    let release_response = Arc::new(Notify::new());
+    let router = {
+        let request_received = request_received.clone();
+        let release_response = release_response.clone();
+        Router::new().route(
+            "/",
+            get(|| async move {
+                request_received.notify_one();
+                release_response.notified().await;
+                "released"
+            }),
+        )
+    };
+    let (mut http_server, addr) = serve(router);
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    wait_until(|| http_server.idle_connections() == 1).await;
+    assert_eq!(http_server.active_connections(), 0);
+
+    client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    request_received.notified().await;
+    assert_eq!(http_server.active_connections(), 1);
+    assert_eq!(http_server.idle_connections(), 0);
+
+    release_response.notify_one();
+    let mut response = [0; 1024];
+    let mut read = 0;
+    while !response[..read].ends_with(b"released") {
+        read += client.read(&mut response[read..]).unwrap();
+    }
+    wait_until(|| http_server.idle_connections() == 1).await;
+    assert_eq!(http_server.active_connections(), 0);
+
+    drop(client);
+    wait_until(|| http_server.idle_connections() == 0).await;
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn connection_snapshots() {
+    let request_received = Arc::new(Notify::new());
+    let release_response = Arc::new(Notify::new());
+    let router = {
+        let request_received = request_received.clone();
+        let release_response = release_response.clone();
+        Router::new().route("/", get(|| async { "index" })).route(
+            "/slow",
+            get(|| async move {
+                request_received.notify_one();
+                release_response.notified().await;
+                "released"
+            }),
+        )
+    };
+    let (mut http_server, addr) = serve(router);
+    assert!(http_server.connections().is_empty());
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    let request = b"GET / HTTP/1.1\r\n\r\n";
+    client.write_all(request).unwrap();
+    let mut response = [0; 1024];
+    let mut read = 0;
+    while !response[..read].ends_with(b"index") {
+        read += client.read(&mut response[read..]).unwrap();
+    }
+    client.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+    request_received.notified().await;
+
+    let connections = http_server.connections();
+    assert_eq!(connections.len(), 1);
+    let connection = connections[0];
+    assert_eq!(connection.peer_addr, client.local_addr().unwrap());
+    assert_eq!(connection.state, ConnectionState::Active);
+    assert_eq!(connection.requests, 2);
+    assert_eq!(
+        connection.bytes_received,
+        (request.len() + b"GET /slow HTTP/1.1\r\n\r\n".len()) as u64
+    );
+    assert_eq!(connection.bytes_sent, read as u64);
+    assert!(connection.connected_at <= connection.state_since);
+
+    release_response.notify_one();
+    drop(client);
+    wait_until(|| http_server.connections().is_empty()).await;
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn idle_eviction() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .idle_eviction(Duration::from_millis(200));
+    http_server
+        .serve(Router::new().route("/", get(|| async { "index" })))
+        .unwrap();
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let started = Instant::now();
+    let mut response = String::new();
+    // the connection is kept alive until it gets evicted
+    client.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("index"));
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    wait_until(|| http_server.open_connections() == 0).await;
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn close_connection() {
+    let (mut http_server, addr) = serve(Router::new());
+    assert!(!http_server.close_connection(addr));
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let other = TcpStream::connect(addr).unwrap();
+    wait_until(|| http_server.open_connections() == 2).await;
+
+    assert!(http_server.close_connection(client.local_addr().unwrap()));
+    assert_eq!(client.read(&mut [0; 16]).unwrap(), 0);
+    wait_until(|| http_server.open_connections() == 1).await;
+    assert_eq!(
+        http_server.connections()[0].peer_addr,
+        other.local_addr().unwrap()
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn cancel_on_disconnect() {
+    /// Marks the route as cancelled once it gets dropped before it finished.
+    struct Cancelled(Arc<AtomicBool>);
+    impl Drop for Cancelled {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    capture_logs();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let router = Router::new()
+        .route(
+            "/slow",
+            get({
+                let cancelled = cancelled.clone();
+                move || async move {
+                    let guard = Cancelled(cancelled);
+                    sleep(Duration::from_secs(10)).await;
+                    std::mem::forget(guard);
+                    "slow"
+                }
+            }),
+        )
+        .route("/fast", get(|| async { "fast" }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("Disconnect"), None)
+        .unwrap()
+        .cancel_on_disconnect(Duration::from_millis(10));
+    http_server.serve(router).unwrap();
+
+    // routes of clients waiting for their response run to completion
+    let response = send(addr, b"GET /fast HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\nfast"));
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    client.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+    wait_until(|| http_server.active_connections() == 1).await;
+    drop(client);
+    wait_until(|| cancelled.load(Ordering::SeqCst)).await;
+    wait_until(|| http_server.open_connections() == 0).await;
+    assert!(logs_of("Disconnect")
+        .iter()
+        .any(|log| log.contains("`GET /slow` closed the connection")));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn dedicated_runtime() {
+    let router = Router::new().route(
+        "/",
+        get(|| async {
+            std::thread::current()
+                .name()
+                .unwrap_or_default()
+                .to_string()
+        }),
+    );
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("Dedicated"), None)
+        .unwrap()
+        .dedicated_runtime(1, 256 * 1024);
+
+    // the runtime gets reused after a shutdown
+    for _ in 0..2 {
+        http_server.serve(router.clone()).unwrap();
+        let response = tokio::task::spawn_blocking(move || {
+            send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        })
+        .await
+        .unwrap();
+        assert!(
+            response.ends_with("\r\n\r\nDedicated-worker"),
+            "{response:?}"
+        );
+        http_server.shutdown().await;
+    }
+
+    // dropping the runtime inside of an async function must not panic
+    drop(http_server);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn counters() {
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+    let handle = http_server
+        .serve(Router::new().route("/", get(|| async { "index" })))
+        .unwrap();
+    let addr = handle.local_addr();
+    assert_eq!(http_server.open_connections(), 0);
+    assert_eq!(http_server.total_requests(), 0);
+
+    // two requests over a connection which is kept alive
+    let kept_alive = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        let mut buffer = [0; 1024];
+        while response.matches("index").count() < 2 {
+            let read = client.read(&mut buffer).unwrap();
+            response.push_str(std::str::from_utf8(&buffer[..read]).unwrap());
+        }
+        client
+    })
+    .await
+    .unwrap();
+    assert_eq!(http_server.open_connections(), 1);
+    assert_eq!(http_server.total_requests(), 2);
+    assert_eq!(handle.open_connections(), 1);
+    drop(kept_alive);
+    wait_until(|| http_server.open_connections() == 0).await;
+
+    // and one over a connection which gets closed
+    tokio::task::spawn_blocking(move || send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n"))
+        .await
+        .unwrap();
+    wait_until(|| handle.open_connections() == 0).await;
+    assert_eq!(http_server.total_requests(), 3);
+    assert_eq!(handle.total_requests(), 3);
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn refresh_rate() {
+    let (mut http_server, addr) = serve(Router::new().route("/", get(|| async { "index" })));
+    assert_eq!(http_server.refresh_rate(), Duration::from_millis(10));
+
+    assert_eq!(
+        http_server.set_refresh_rate(Duration::ZERO),
+        Err(ConfigError::ZeroRefreshRate)
+    );
+    http_server
+        .set_refresh_rate(Duration::from_millis(400))
+        .unwrap();
+    assert_eq!(http_server.refresh_rate(), Duration::from_millis(400));
+
+    // let the sleep with the old refresh rate end, so the next one uses the new one
+    sleep(Duration::from_millis(100)).await;
+    let start = Instant::now();
+    let response = tokio::task::spawn_blocking(move || {
+        send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+    })
+    .await
+    .unwrap();
+    assert!(response.ends_with("index"));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn inline_connections() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .handle_connections_inline(true);
+    http_server
+        .serve(Router::new().route("/", get(|| async { "index" })))
+        .unwrap();
+
+    let second_response = tokio::task::spawn_blocking(move || {
+        let mut first_client = TcpStream::connect(addr).unwrap();
+        first_client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = [0; 1024];
+        let mut read = 0;
+        while !response[..read].ends_with(b"index") {
+            read += first_client.read(&mut response[read..]).unwrap();
+        }
+
+        // the first connection is still open, so the second one does not get handled yet
+        let mut second_client = TcpStream::connect(addr).unwrap();
+        second_client
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        second_client
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        assert!(second_client.read(&mut response).is_err());
+
+        drop(first_client);
+        second_client.set_read_timeout(None).unwrap();
+        let mut second_response = String::new();
+        second_client.read_to_string(&mut second_response).unwrap();
+        second_response
+    })
+    .await
+    .unwrap();
+    assert!(second_response.ends_with("index"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn port_zero() {
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None).unwrap();
+    assert_eq!(http_server.local_addr().port(), 0);
+
+    let handle = http_server
+        .serve(Router::new().route("/", get(|| async { "Hello" })))
+        .unwrap();
+    let addr = http_server.local_addr();
+    assert_ne!(addr.port(), 0);
+    assert_eq!(addr, handle.local_addr());
+
+    let response = tokio::task::spawn_blocking(move || {
+        send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+    })
+    .await
+    .unwrap();
+    assert!(response.ends_with("Hello"), "{response}");
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn serve_handle() {
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None).unwrap();
+    let handle = http_server.serve(Router::new()).unwrap();
+    assert_ne!(handle.local_addr().port(), 0);
+    assert!(handle.is_running());
+
+    let waiter = {
+        let handle = handle.clone();
+        tokio::spawn(async move { handle.await_stopped().await })
+    };
+    let stopper = {
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            handle.shutdown().await;
+        })
+    };
+    stopper.await.unwrap();
+    waiter.await.unwrap();
+
+    assert!(!handle.is_running());
+    assert!(!http_server.is_running());
+    assert!(TcpStream::connect(handle.local_addr()).is_err());
+
+    // the HttpServer notices that it was already stopped
+    http_server.shutdown().await;
+    handle.await_stopped().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn graceful_shutdown() {
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+
+    for (grace_period, answered_in_time) in [
+        (Duration::from_secs(5), true),
+        (Duration::from_millis(50), false),
+    ] {
+        let handle = http_server
+            .serve(Router::new().route(
+                "/",
+                get(|| async {
+                    sleep(Duration::from_millis(200)).await;
+                    "slow"
+                }),
+            ))
+            .unwrap();
+        let addr = handle.local_addr();
+        let response = tokio::task::spawn_blocking(move || {
+            send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        });
+        wait_until(|| http_server.active_connections() == 1).await;
+
+        assert_eq!(
+            handle.graceful_shutdown(grace_period).await,
+            answered_in_time
+        );
+        // the HttpServer stops even if the grace period is over, but the request still gets answered
+        assert!(!handle.is_running());
+        assert!(response.await.unwrap().ends_with("slow"));
+    }
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn join_after_shutdown() {
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+    // never started
+    http_server.join().await.unwrap();
+
+    let handle = http_server.serve(Router::new()).unwrap();
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(50)).await;
+        handle.shutdown().await;
+    });
+    http_server.join().await.unwrap();
+    assert!(!http_server.is_running());
+
+    // already joined
+    http_server.join().await.unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn join_after_fault() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .accept_error_policy(AcceptErrorPolicy::Stop);
+    http_server.serve(Router::new()).unwrap();
+
+    // shutting a listening socket down makes every following accept() fail with `EINVAL`
+    let listener = listener_fd(addr.port());
+    assert_eq!(unsafe { libc::shutdown(listener, libc::SHUT_RDWR) }, 0);
+
+    let error = http_server.join().await.unwrap_err();
+    assert!(matches!(error, Error::Accept(_)), "{error}");
+    assert!(!http_server.is_running());
+
+    // the HttpServer can be restarted after the fault
+    let mut attempts = 0;
+    while http_server.serve(Router::new()).is_err() {
+        attempts += 1;
+        assert!(attempts < 100, "The address did not become free in time.");
+        sleep(Duration::from_millis(10)).await;
+    }
+    http_server.shutdown().await;
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stop_hook() {
+    let results = Arc::new(Mutex::new(vec![]));
+    let recorded = results.clone();
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .accept_error_policy(AcceptErrorPolicy::Stop)
+        .on_stop(move |result| {
+            recorded.lock().unwrap().push(match result {
+                Ok(()) => "stopped".to_string(),
+                Err(error) => error.to_string(),
+            });
+        });
+    let handle = http_server.serve(Router::new()).unwrap();
+
+    let listener = listener_fd(addr.port());
+    assert_eq!(unsafe { libc::shutdown(listener, libc::SHUT_RDWR) }, 0);
+
+    // the hook gets called before the HttpServer counts as stopped
+    handle.await_stopped().await;
+    {
+        let results = results.lock().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].starts_with("Accept:"), "{}", results[0]);
+    }
+    assert!(http_server.join().await.is_err());
+
+    let mut attempts = 0;
+    while http_server.serve(Router::new()).is_err() {
+        attempts += 1;
+        assert!(attempts < 100, "The address did not become free in time.");
+        sleep(Duration::from_millis(10)).await;
+    }
+    http_server.shutdown().await;
+    assert_eq!(results.lock().unwrap().last().unwrap(), "stopped");
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn auto_restart() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .accept_error_policy(AcceptErrorPolicy::Stop)
+        .auto_restart(RestartPolicy {
+            backoff: Duration::from_millis(50),
+            max_restarts: 1,
+        });
+    http_server
+        .serve(Router::new().route("/", get(|| async { "Hello" })))
+        .unwrap();
+
+    let listener = listener_fd(addr.port());
+    assert_eq!(unsafe { libc::shutdown(listener, libc::SHUT_RDWR) }, 0);
+    wait_until(|| http_server.restarts() == 1).await;
+    wait_until(|| TcpStream::connect(addr).is_ok()).await;
+
+    // the new listener answers requests with the same router
+    let response = tokio::task::spawn_blocking(move || {
+        send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+    })
+    .await
+    .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    assert!(response.ends_with("Hello"), "{response}");
+    assert!(http_server.is_running());
+
+    // once the limit is reached, the fault stops the HttpServer
+    let listener = listener_fd(addr.port());
+    assert_eq!(unsafe { libc::shutdown(listener, libc::SHUT_RDWR) }, 0);
+    let error = http_server.join().await.unwrap_err();
+    assert!(matches!(error, Error::Accept(_)), "{error}");
+    assert_eq!(http_server.restarts(), 1);
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn shutdown_cancels_restart() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .accept_error_policy(AcceptErrorPolicy::Stop)
+        .auto_restart(RestartPolicy {
+            backoff: Duration::from_secs(10),
+            max_restarts: 1,
+        });
+    let handle = http_server.serve(Router::new()).unwrap();
+
+    let listener = listener_fd(addr.port());
+    assert_eq!(unsafe { libc::shutdown(listener, libc::SHUT_RDWR) }, 0);
+    wait_until(|| handle.restarts() == 1).await;
+
+    let start = Instant::now();
+    http_server.shutdown().await;
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(!handle.is_running());
+    // no new listener was bound
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn reuse_port() {
+    let addr = free_addr();
+    let mut old = HttpServer::bind(addr, None, None).unwrap().reuse_port(true);
+    let old_handle = old
+        .serve(Router::new().route("/", get(|| async { "old" })))
+        .unwrap();
+    let mut new = HttpServer::bind(addr, None, None).unwrap().reuse_port(true);
+    new.serve(Router::new().route("/", get(|| async { "new" })))
+        .unwrap();
+
+    // the kernel spreads the connections across both listeners
+    let responses = tokio::task::spawn_blocking(move || {
+        (0..100)
+            .map(|_| send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n"))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap();
+    assert!(responses.iter().any(|response| response.ends_with("old")));
+    assert!(responses.iter().any(|response| response.ends_with("new")));
+
+    // no request fails while the old HttpServer hands the port over
+    let requests = tokio::task::spawn_blocking(move || {
+        (0..50)
+            .map(|_| {
+                std::thread::sleep(Duration::from_millis(5));
+                send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            })
+            .collect::<Vec<_>>()
+    });
+    sleep(Duration::from_millis(50)).await;
+    assert!(old_handle.graceful_shutdown(Duration::from_secs(1)).await);
+    let responses = requests.await.unwrap();
+    assert!(responses
+        .iter()
+        .all(|response| response.starts_with("HTTP/1.1 200 OK\r\n")));
+    assert!(responses.last().unwrap().ends_with("new"));
+
+    new.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn selftest_passes() {
+    use goohttp::{
+        metrics::RouteTable,
+        RouteInfo,
+    };
+
+    capture_logs();
+    let router = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/api/say_hello/:caller", get(|| async { "Hello!" }))
+        .route(
+            "/broken",
+            get(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+        );
+    let route = |path: &str, smoke_test| RouteInfo {
+        method: "GET".to_string(),
+        path: path.to_string(),
+        smoke_test,
+        description: None,
+    };
+
+    // without requests, the routes of the route table get checked, except for the skipped ones
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("SelfTest"), None)
+        .unwrap()
+        .route_table(RouteTable::new([
+            route("/", true),
+            route("/api/say_hello/:caller", true),
+            route("/broken", false),
+        ]));
+    http_server
+        .serve_with_selftest(router.clone(), &[])
+        .await
+        .unwrap();
+    assert!(logs_of("SelfTest").contains(
+        &"The self-test passed. All 2 requests were answered without a server error.".to_string()
+    ));
+    let response = send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\nHello, World!"));
+    http_server.shutdown().await;
+
+    // routes extracting the connection info get it like with clients
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+    http_server
+        .serve_with_selftest(
+            Router::new().route(
+                "/peer",
+                get(|ConnInfo(info): ConnInfo| async move { info.peer_addr.to_string() }),
+            ),
+            &[("GET", "/peer")],
+        )
+        .await
+        .unwrap();
+    http_server.shutdown().await;
+
+    // client errors do not fail the self-test
+    let mut http_server = HttpServer::bind(free_addr(), None, None).unwrap();
+    http_server
+        .serve_with_selftest(router, &[("GET", "/"), ("POST", "/"), ("GET", "/missing")])
+        .await
+        .unwrap();
+    assert!(http_server.is_running());
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn selftest_fails() {
+    let router = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route(
+            "/broken",
+            get(|| async { axum::http::StatusCode::SERVICE_UNAVAILABLE }),
+        )
+        .route(
+            "/panic",
+            get(|| async {
+                if true {
+                    panic!("deliberately broken");
+                }
+                "unreachable"
+            }),
+        );
+
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    match http_server
+        .serve_with_selftest(router.clone(), &[("GET", "/"), ("GET", "/broken")])
+        .await
+    {
+        Err(Error::SelfTest { path, status }) => {
+            assert_eq!(path, "/broken");
+            assert_eq!(status, Some(axum::http::StatusCode::SERVICE_UNAVAILABLE));
+        }
+        result => panic!("The self-test should have failed. Result: {result:?}"),
+    }
+    match http_server
+        .serve_with_selftest(router, &[("GET", "/panic"), ("GET", "/")])
+        .await
+    {
+        Err(error @ Error::SelfTest { .. }) => assert_eq!(
+            error.to_string(),
+            "SelfTest: The request to `/panic` panicked or could not be built."
+        ),
+        result => panic!("The self-test should have failed. Result: {result:?}"),
+    }
+
+    // the listener never got bound
+    assert!(!http_server.is_running());
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn linger() {
+    /// Count the sockets of the given port waiting in `TIME_WAIT`.
+    fn time_waits(port: u16) -> usize {
+        std::fs::read_to_string("/proc/net/tcp")
+            .unwrap()
+            .lines()
+            .skip(1)
+            .filter(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                fields[1].ends_with(&format!(":{port:04X}")) && fields[3] == "06"
+            })
+            .count()
+    }
+    /// Send a request closing the connection, and return what was received before the server closed it.
+    fn request(addr: std::net::SocketAddr) -> String {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        // the response may be followed by a reset instead of the end of the connection
+        let mut response = vec![];
+        let mut buffer = [0; 1024];
+        while let Ok(read @ 1..) = client.read(&mut buffer) {
+            response.extend_from_slice(&buffer[..read]);
+        }
+        String::from_utf8(response).unwrap()
+    }
+
+    let router = || Router::new().route("/", get(|| async { "Hello, World!" }));
+
+    // by default, the server keeps the sockets of the connections it closed in `TIME_WAIT`
+    // other sockets may have used the same port before
+    let (mut http_server, addr) = serve(router());
+    let before = time_waits(addr.port());
+    assert!(request(addr).ends_with("\r\n\r\nHello, World!"));
+    http_server.shutdown().await;
+    assert!(time_waits(addr.port()) > before);
+
+    // an abortive close frees them right away
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .linger(Duration::ZERO);
+    http_server.serve(router()).unwrap();
+    let before = time_waits(addr.port());
+    for _ in 0..3 {
+        request(addr);
+    }
+    http_server.shutdown().await;
+    assert_eq!(time_waits(addr.port()), before);
+}
+
+/// Find the file descriptor of the socket listening on the given port.
+#[cfg(target_os = "linux")]
+fn listener_fd(port: u16) -> libc::c_int {
+    (0..1024)
+        .find(|&fd| unsafe {
+            let mut addr: libc::sockaddr_in = std::mem::zeroed();
+            let mut addr_len = std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+            let mut listening: libc::c_int = 0;
+            let mut listening_len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+            libc::getsockname(
+                fd,
+                &mut addr as *mut _ as *mut libc::sockaddr,
+                &mut addr_len,
+            ) == 0
+                && addr.sin_family == libc::AF_INET as libc::sa_family_t
+                && u16::from_be(addr.sin_port) == port
+                && libc::getsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_ACCEPTCONN,
+                    &mut listening as *mut _ as *mut libc::c_void,
+                    &mut listening_len,
+                ) == 0
+                && listening == 1
+        })
+        .expect("The listener should be open.")
+}
+
+/// Wait up to a second for the given condition to become true.
+async fn wait_until(condition: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+    panic!("The condition did not become true in time.");
+}
@@ -0,0 +1,522 @@
+use std::{
+    io::{
+        Read,
+        Write,
+    },
+    net::TcpStream,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use goohttp::{
+    axum::{
+        extract::DefaultBodyLimit,
+        http::HeaderMap,
+        routing::{
+            get,
+            post,
+        },
+        Router,
+    },
+    http_server::HttpServer,
+    middleware::throttle::{
+        Priority,
+        PriorityThrottleConfig,
+        Rate,
+    },
+    testing::MemoryListener,
+};
+use hyper::{
+    Body,
+    Request,
+    Response,
+};
+use tokio::{
+    task::spawn_blocking,
+    time::sleep,
+};
+
+use crate::{
+    free_addr,
+    send,
+    send_in_memory,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn routes_override_the_default_body_limit() {
+    let router = Router::new()
+        .route("/echo", post(|body: String| async move { body }))
+        .route(
+            "/upload",
+            post(|body: String| async move { body }).layer(DefaultBodyLimit::max(64)),
+        );
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_request_body(8);
+    http_server.serve(router).unwrap();
+
+    // the rejected body must not be read as the next request
+    let response = send(
+        addr,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 22\r\nConnection: close\r\n\r\nGET /echo HTTP/1.1\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert_eq!(response.matches("HTTP/1.1").count(), 1);
+
+    let response = send(
+        addr,
+        b"POST /upload HTTP/1.1\r\nContent-Length: 22\r\nConnection: close\r\n\r\nGET /echo HTTP/1.1\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nGET /echo HTTP/1.1\r\n\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn large_responses_get_replaced() {
+    let router = Router::new()
+        .route("/small", get(|| async { "small" }))
+        .route("/large", get(|| async { "large".repeat(100) }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_response_body(64);
+    http_server.serve(router).unwrap();
+
+    let response = send(
+        addr,
+        b"GET /small HTTP/1.1\r\n\r\nGET /large HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("\r\n\r\nsmallHTTP/1.1 500 Internal Server Error\r\n"));
+    assert!(response.ends_with("content-length: 0\r\nconnection: close\r\n\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn large_responses_get_truncated() {
+    const MB: usize = 1024 * 1024;
+
+    let router = Router::new().route("/log", get(|| async { "x".repeat(10 * MB) }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_response_body(MB)
+        .truncate_large_responses(true);
+    http_server.serve(router).unwrap();
+
+    let response = send(addr, b"GET /log HTTP/1.1\r\nConnection: close\r\n\r\n");
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("warning: 214 - \"Transformation Applied\"\r\n"));
+    assert!(head.contains(&format!("content-length: {MB}\r\n")));
+    assert_eq!(body.len(), MB);
+
+    http_server.shutdown().await;
+
+    let router = Router::new().route("/log", get(|| async { "x".repeat(10 * MB) }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_response_body(MB);
+    http_server.serve(router).unwrap();
+
+    let response = send(addr, b"GET /log HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+    assert!(!response.contains("warning"));
+    assert!(response.ends_with("content-length: 0\r\nconnection: close\r\n\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn priority_throttle_spans_connections() {
+    let router = Router::new()
+        .route("/", get(|| async { "index" }))
+        .route("/health", get(|| async { "healthy" }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .priority_throttle(
+            PriorityThrottleConfig::new(|request| {
+                if request.uri.path() == "/health" {
+                    Priority::High
+                } else {
+                    Priority::Normal
+                }
+            })
+            .limit(
+                Priority::Normal,
+                Rate {
+                    requests: 1,
+                    period: Duration::from_secs(60),
+                },
+            ),
+        );
+    http_server.serve(router).unwrap();
+
+    let response = send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    let response = send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+    let response = send(addr, b"GET /health HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn queue_depth_limits_concurrent_requests() {
+    let answering = Arc::new(AtomicUsize::new(0));
+    let most_answering = Arc::new(AtomicUsize::new(0));
+    let router = {
+        let answering = answering.clone();
+        let most_answering = most_answering.clone();
+        Router::new().route(
+            "/",
+            get(|| async move {
+                let now = answering.fetch_add(1, Ordering::SeqCst) + 1;
+                most_answering.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_millis(50)).await;
+                answering.fetch_sub(1, Ordering::SeqCst);
+                "index"
+            }),
+        )
+    };
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap().queue_depth(2);
+    http_server.serve(router).unwrap();
+
+    let clients: Vec<_> = (0..6)
+        .map(|_| spawn_blocking(move || send(addr, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")))
+        .collect();
+    for client in clients {
+        assert!(client.await.unwrap().ends_with("index"));
+    }
+    assert_eq!(most_answering.load(Ordering::SeqCst), 2);
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn fast_paths_skip_the_queue() {
+    fn healthz(_: Request<Body>) -> Response<Body> {
+        Response::new(Body::from("ok"))
+    }
+
+    let router = Router::new()
+        .route(
+            "/slow",
+            get(|| async {
+                sleep(Duration::from_millis(500)).await;
+                "slow"
+            }),
+        )
+        .route("/healthz", get(|| async { "router" }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .queue_depth(1)
+        .fast_path("/healthz", healthz);
+    http_server.serve(router).unwrap();
+
+    // one request takes the only permit, while the other one queues behind it
+    let clients: Vec<_> = (0..2)
+        .map(|_| {
+            spawn_blocking(move || send(addr, b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n"))
+        })
+        .collect();
+    while http_server.total_requests() < 1 || http_server.open_connections() < 2 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(50)).await;
+
+    let started = Instant::now();
+    let response =
+        spawn_blocking(move || send(addr, b"GET /healthz HTTP/1.1\r\nConnection: close\r\n\r\n"))
+            .await
+            .unwrap();
+    assert!(response.ends_with("\r\n\r\nok"), "{response:?}");
+    assert!(started.elapsed() < Duration::from_millis(400));
+    // the queued request only counts once it got its permit
+    assert_eq!(http_server.total_requests(), 2);
+
+    for client in clients {
+        assert!(client.await.unwrap().ends_with("slow"));
+    }
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn enormous_header_values_get_rejected() {
+    let router = Router::new().route("/", get(|| async { "index" }));
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_header_value_bytes(1024);
+    http_server.serve(router).unwrap();
+
+    let response = send(
+        addr,
+        format!(
+            "GET / HTTP/1.1\r\nX-Small: {}\r\nConnection: close\r\n\r\n",
+            "a".repeat(1024)
+        )
+        .as_bytes(),
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+
+    let request = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        // the server stops reading after the limit, so the rest may not be accepted anymore
+        let _ = client.write_all(
+            format!(
+                "GET / HTTP/1.1\r\nX-Huge: {}\r\n\r\n",
+                "a".repeat(4 * 1024 * 1024)
+            )
+            .as_bytes(),
+        );
+        let mut response = vec![];
+        let _ = client.read_to_end(&mut response);
+        String::from_utf8(response).unwrap()
+    });
+    let response = request.await.unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+        "{response:?}"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn enormous_request_lines_get_rejected() {
+    let router = Router::new().fallback(|| async { "fallback" });
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .max_header_value_bytes(1024);
+    http_server.serve(router).unwrap();
+
+    // the request line may be as long as a header line
+    let response = send(
+        addr,
+        format!(
+            "GET /{} HTTP/1.1\r\nConnection: close\r\n\r\n",
+            "a".repeat(1024)
+        )
+        .as_bytes(),
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+
+    let request = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        // the server stops reading after the limit, so the rest may not be accepted anymore
+        let _ = client
+            .write_all(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(4 * 1024 * 1024)).as_bytes());
+        let mut response = vec![];
+        let _ = client.read_to_end(&mut response);
+        String::from_utf8(response).unwrap()
+    });
+    let response = request.await.unwrap();
+    assert_eq!(
+        response,
+        "HTTP/1.1 414 URI Too Long\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn too_many_headers_get_rejected() {
+    let router = Router::new().route(
+        "/",
+        get(|headers: HeaderMap| async move {
+            let values: Vec<_> = headers
+                .get_all("x-value")
+                .iter()
+                .map(|value| value.to_str().unwrap())
+                .collect();
+            values.join(",")
+        }),
+    );
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .max_request_headers(4);
+    http_server.serve_on(listener, router).unwrap();
+
+    // repeated headers keep all of their values in order, and the connection header counts as well
+    let response = send_in_memory(
+        &connector,
+        b"GET / HTTP/1.1\r\nX-Value: 1\r\nX-Value: 2\r\nx-value: 3\r\nConnection: close\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+    assert!(response.ends_with("\r\n\r\n1,2,3"), "{response:?}");
+
+    let response = send_in_memory(
+        &connector,
+        b"GET / HTTP/1.1\r\nX-Value: 1\r\nX-Value: 2\r\nX-Value: 3\r\nX-Value: 4\r\nConnection: close\r\n\r\n",
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+        "{response:?}"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn custom_error_responses() {
+    use goohttp::axum::http::{
+        header::CONTENT_TYPE,
+        HeaderValue,
+        Response,
+    };
+
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .max_request_body(8)
+        .max_request_headers(1)
+        .error_responder(|status| {
+            let mut response = Response::new(format!("Error {}", status.as_u16()).into_bytes());
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            response
+        });
+    let router = Router::new().route("/echo", post(|body: String| async move { body }));
+    http_server.serve_on(listener, router).unwrap();
+
+    // every rejected request gets the same bytes
+    for _ in 0..2 {
+        let response = send_in_memory(&connector, b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n");
+        assert_eq!(
+            response,
+            "HTTP/1.1 431 Request Header Fields Too Large\r\n\
+             content-type: text/plain\r\n\
+             content-length: 9\r\n\
+             connection: close\r\n\
+             \r\n\
+             Error 431"
+        );
+    }
+
+    let response = send_in_memory(&connector, b"GET / HTTP/1.1\r\nA 1\r\n\r\n");
+    assert_eq!(
+        response,
+        "HTTP/1.1 400 Bad Request\r\n\
+         content-type: text/plain\r\n\
+         content-length: 9\r\n\
+         connection: close\r\n\
+         \r\n\
+         Error 400"
+    );
+
+    // the rejection of axum's body limit gets replaced as well
+    let response = send_in_memory(
+        &connector,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 16\r\n\r\n0123456789abcdef",
+    );
+    assert_eq!(
+        response,
+        "HTTP/1.1 413 Payload Too Large\r\n\
+         content-type: text/plain\r\n\
+         content-length: 9\r\n\
+         connection: close\r\n\
+         \r\n\
+         Error 413"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn stalled_requests_time_out() {
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .keep_alive_timeout(Duration::from_millis(100));
+    http_server.serve_on(listener, Router::new()).unwrap();
+
+    // the empty line ending the headers never arrives
+    let response = spawn_blocking(move || {
+        let mut connection = connector.connect().unwrap();
+        connection
+            .write_all(b"GET / HTTP/1.1\r\nHost: 192.168.4.1\r\n")
+            .unwrap();
+        let mut response = String::new();
+        connection.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+        response,
+        "HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn full_queues_reject_requests() {
+    fn healthz(_: Request<Body>) -> Response<Body> {
+        Response::new(Body::from("ok"))
+    }
+
+    let router = Router::new().route(
+        "/slow",
+        get(|| async {
+            sleep(Duration::from_millis(500)).await;
+            "slow"
+        }),
+    );
+    let addr = free_addr();
+    // with a fast path, connections keep getting accepted while the permit is taken
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .queue_depth(1)
+        .fast_path("/healthz", healthz);
+    http_server.serve(router).unwrap();
+
+    // one request takes the only permit, while the other one takes the only place in the queue
+    let clients: Vec<_> = (0..2)
+        .map(|_| {
+            spawn_blocking(move || send(addr, b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n"))
+        })
+        .collect();
+    while http_server.total_requests() < 1 || http_server.open_connections() < 2 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    sleep(Duration::from_millis(50)).await;
+
+    let response =
+        spawn_blocking(move || send(addr, b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n"))
+            .await
+            .unwrap();
+    assert_eq!(
+        response,
+        "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    );
+
+    for client in clients {
+        assert!(client.await.unwrap().ends_with("slow"));
+    }
+
+    http_server.shutdown().await;
+}
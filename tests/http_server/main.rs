@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    io::{
+        Read,
+        Write,
+    },
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::{
+        Mutex,
+        Once,
+    },
+};
+
+use goohttp::{
+    axum::Router,
+    http_server::HttpServer,
+    testing::{
+        memory_transport,
+        MemoryConnector,
+        TestClient,
+    },
+};
+use log::{
+    LevelFilter,
+    Log,
+    Metadata,
+    Record,
+};
+
+#[cfg(unix)]
+mod activation;
+mod config;
+mod connection_info;
+mod dispatch;
+mod embedding;
+mod head_requests;
+mod header_sanitization;
+mod lifecycle;
+mod limits;
+mod mdns;
+mod metrics;
+mod not_found;
+mod pipelining;
+mod proxy_protocol;
+mod rpc;
+mod serialization;
+mod streaming;
+mod upgrades;
+
+/// Find an address the [`HttpServer`] can bind to.
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+/// Serve the given [`Router`] on a free address.
+fn serve(router: Router) -> (HttpServer, SocketAddr) {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    http_server.serve(router).unwrap();
+    (http_server, addr)
+}
+
+/// Serve the given [`Router`] on an in-memory listener.
+fn serve_in_memory(router: Router) -> (HttpServer, MemoryConnector) {
+    let (listener, connector) = memory_transport();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None).unwrap();
+    http_server.serve_on(listener, router).unwrap();
+    (http_server, connector)
+}
+
+/// Send the given bytes over a new in-memory connection and read everything until the server closes it.
+fn send_in_memory(connector: &MemoryConnector, request: &[u8]) -> String {
+    TestClient::new(connector.clone()).send(request).unwrap()
+}
+
+/// Send the given bytes to the given address and read everything until the server closes the connection.
+fn send(addr: SocketAddr, request: &[u8]) -> String {
+    let mut client = TcpStream::connect(addr).unwrap();
+    client.write_all(request).unwrap();
+
+    let mut response = String::new();
+    client.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// Collects the logged messages by the name of the HttpServer they were logged by.
+struct Logs(Mutex<Option<HashMap<String, Vec<String>>>>);
+impl Log for Logs {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.0
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .entry(record.target().to_string())
+            .or_default()
+            .push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// The messages logged by every HttpServer of this test binary.
+static LOGS: Logs = Logs(Mutex::new(None));
+
+/// Start collecting the messages logged by every HttpServer. Only one logger can be set per process, so every test reading logs has to
+/// call this instead of setting its own.
+fn capture_logs() {
+    static SET_LOGGER: Once = Once::new();
+    SET_LOGGER.call_once(|| {
+        log::set_logger(&LOGS).unwrap();
+        log::set_max_level(LevelFilter::Debug);
+    });
+}
+
+/// Get the messages logged so far by the HttpServer with the given name. [`capture_logs`] has to be called before the HttpServer
+/// starts.
+fn logs_of(name: &str) -> Vec<String> {
+    LOGS.0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|logs| logs.get(name).cloned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,87 @@
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use goohttp::{
+    esp::mdns::MdnsBackend,
+    http_server::HttpServer,
+};
+
+/// Records the calls made to it instead of announcing anything.
+#[derive(Clone, Default)]
+struct MockMdns {
+    /// Every call made so far.
+    calls: Arc<Mutex<Vec<String>>>,
+    /// Whether registering a service fails.
+    fail: bool,
+}
+impl MdnsBackend for MockMdns {
+    type Error = &'static str;
+
+    fn set_hostname(&mut self, hostname: &str) -> Result<(), Self::Error> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("set_hostname {hostname}"));
+        Ok(())
+    }
+
+    fn add_service(
+        &mut self,
+        instance: &str,
+        service_type: &str,
+        protocol: &str,
+        port: u16,
+    ) -> Result<(), Self::Error> {
+        if self.fail {
+            return Err("no network");
+        }
+        self.calls.lock().unwrap().push(format!(
+            "add_service {instance} {service_type}.{protocol} {port}"
+        ));
+        Ok(())
+    }
+
+    fn remove_service(&mut self, service_type: &str, protocol: &str) -> Result<(), Self::Error> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("remove_service {service_type}.{protocol}"));
+        Ok(())
+    }
+}
+
+#[test]
+fn mdns() {
+    let http_server = HttpServer::bind("127.0.0.1:8080", None, None).unwrap();
+    let backend = MockMdns::default();
+
+    let advertiser = http_server
+        .advertise_mdns(backend.clone(), "thermostat", "Living Room Thermostat")
+        .unwrap();
+    assert_eq!(
+        *backend.calls.lock().unwrap(),
+        [
+            "set_hostname thermostat",
+            "add_service Living Room Thermostat _http._tcp 8080"
+        ]
+    );
+
+    drop(advertiser);
+    assert_eq!(
+        backend.calls.lock().unwrap().last().unwrap(),
+        "remove_service _http._tcp"
+    );
+
+    let failing_backend = MockMdns {
+        fail: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        http_server
+            .advertise_mdns(failing_backend, "thermostat", "Living Room Thermostat")
+            .err(),
+        Some("no network")
+    );
+}
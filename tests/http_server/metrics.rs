@@ -0,0 +1,280 @@
+use std::{
+    sync::{
+        mpsc::{
+            self,
+            Sender,
+        },
+        Mutex,
+    },
+    time::Duration,
+};
+
+use goohttp::{
+    axum::{
+        http::{
+            Method,
+            StatusCode,
+        },
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+    metrics::MetricsBackend,
+};
+
+use crate::{
+    free_addr,
+    send,
+};
+
+/// A [`MetricsBackend`] sending a description of every event through its channel.
+struct Recorder(Mutex<Sender<String>>);
+impl Recorder {
+    /// Send the given event through the channel.
+    fn record(&self, event: String) {
+        let _ = self.0.lock().unwrap().send(event);
+    }
+}
+impl MetricsBackend for Recorder {
+    fn on_request_start(&self, method: &Method, path: &str) {
+        self.record(format!("start {method} {path}"));
+    }
+
+    fn on_request_end(&self, status: StatusCode, _duration: Duration, bytes: usize) {
+        self.record(format!("end {} {bytes}", status.as_u16()));
+    }
+
+    fn on_connection_change(&self, delta: i64) {
+        self.record(format!("connection {delta}"));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn metrics_backend() {
+    let addr = free_addr();
+    let (events, received_events) = mpsc::channel();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .metrics_backend(Recorder(Mutex::new(events)));
+    http_server
+        .serve(Router::new().route("/hello", get(|| async { "Hello!" })))
+        .unwrap();
+
+    send(
+        addr,
+        b"GET /hello HTTP/1.1\r\n\r\nPOST /missing?query HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let received: Vec<String> = (0..6)
+        .map(|_| {
+            received_events
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        received,
+        [
+            "connection 1",
+            "start GET /hello",
+            "end 200 6",
+            "start POST /missing",
+            "end 404 0",
+            "connection -1",
+        ]
+    );
+
+    http_server.shutdown().await;
+}
+
+#[cfg(feature = "prometheus")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn prometheus_metrics() {
+    use std::sync::Arc;
+
+    use goohttp::metrics::PrometheusMetrics;
+
+    let addr = free_addr();
+    let metrics = Arc::new(PrometheusMetrics::new());
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .metrics_backend(metrics.clone());
+    http_server
+        .serve(Router::new().route("/hello", get(|| async { "Hello!" })))
+        .unwrap();
+
+    for _ in 0..2 {
+        send(addr, b"GET /hello HTTP/1.1\r\nConnection: close\r\n\r\n");
+    }
+    send(addr, b"DELETE /hello HTTP/1.1\r\nConnection: close\r\n\r\n");
+    // made up methods share a single time series
+    for method in ["BREW", "WHEN"] {
+        send(
+            addr,
+            format!("{method} /hello HTTP/1.1\r\nConnection: close\r\n\r\n").as_bytes(),
+        );
+    }
+    http_server.shutdown().await;
+
+    let exposition = metrics.render();
+    for line in [
+        "# TYPE goohttp_requests_total counter",
+        "goohttp_requests_total{method=\"DELETE\"} 1",
+        "goohttp_requests_total{method=\"GET\"} 2",
+        "goohttp_requests_total{method=\"other\"} 2",
+        "goohttp_responses_total{status=\"200\"} 2",
+        "goohttp_responses_total{status=\"405\"} 3",
+        "goohttp_request_duration_seconds_count 5",
+        "goohttp_response_body_bytes_total 12",
+    ] {
+        assert!(
+            exposition.lines().any(|exposed| exposed == line),
+            "`{line}` is missing from:\n{exposition}"
+        );
+    }
+}
+
+#[test]
+fn route_table() {
+    use goohttp::{
+        metrics::RouteTable,
+        RouteInfo,
+    };
+
+    let route = |method: &str, path: &str| RouteInfo {
+        method: method.to_string(),
+        path: path.to_string(),
+        smoke_test: true,
+        description: None,
+    };
+    let routes = RouteTable::new([
+        route("GET", "/"),
+        route("GET", "/api/say_hello/:caller"),
+        route("DELETE", "/api/say_hello/:caller"),
+        route("GET", "/files/*path"),
+    ]);
+
+    assert_eq!(routes.find(&Method::GET, "/"), Some(0));
+    assert_eq!(routes.find(&Method::HEAD, "/"), Some(0));
+    assert_eq!(routes.find(&Method::GET, "/api/say_hello/Gooxey"), Some(1));
+    assert_eq!(
+        routes.find(&Method::DELETE, "/api/say_hello/Gooxey"),
+        Some(2)
+    );
+    assert_eq!(routes.find(&Method::GET, "/files/a/b.txt"), Some(3));
+    for (method, path) in [
+        (Method::POST, "/"),
+        (Method::GET, "/api/say_hello"),
+        (Method::GET, "/api/say_hello/"),
+        (Method::GET, "/api/say_hello/Gooxey/again"),
+        (Method::GET, "/files/"),
+    ] {
+        assert_eq!(routes.find(&method, path), None, "{method} {path}");
+    }
+
+    assert_eq!(
+        routes.methods("/api/say_hello/Gooxey"),
+        ["GET", "HEAD", "DELETE"]
+    );
+    assert!(routes.methods("/nowhere").is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn method_not_allowed_lists_the_allowed_methods() {
+    use goohttp::{
+        axum::routing::any,
+        metrics::RouteTable,
+        RouteInfo,
+    };
+
+    let route = |method: &str, path: &str| RouteInfo {
+        method: method.to_string(),
+        path: path.to_string(),
+        smoke_test: true,
+        description: None,
+    };
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .route_table(RouteTable::new([route("GET", "/"), route("GET", "/axum")]));
+    // unlike axum's method routers, this route does not add an `Allow` header of its own
+    let router = Router::new()
+        .route(
+            "/",
+            any(|method: Method| async move {
+                if method == Method::GET {
+                    StatusCode::OK
+                } else {
+                    StatusCode::METHOD_NOT_ALLOWED
+                }
+            }),
+        )
+        .route("/axum", get(|| async { "axum" }));
+    http_server.serve(router).unwrap();
+
+    let response = send(addr, b"POST / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    assert!(response.contains("allow: GET, HEAD\r\n"), "{response:?}");
+
+    // the header of the router is kept
+    let response = send(addr, b"POST /axum HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    assert_eq!(response.matches("allow: ").count(), 1, "{response:?}");
+    assert!(response.contains("allow: GET,HEAD\r\n"));
+    http_server.shutdown().await;
+}
+
+#[cfg(feature = "prometheus")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn route_metrics() {
+    use std::sync::Arc;
+
+    use goohttp::{
+        metrics::{
+            PrometheusMetrics,
+            RouteTable,
+        },
+        RouteInfo,
+    };
+
+    let addr = free_addr();
+    let routes = RouteTable::new([RouteInfo {
+        method: "GET".to_string(),
+        path: "/say_hello/:caller".to_string(),
+        smoke_test: true,
+        description: None,
+    }]);
+    let metrics = Arc::new(PrometheusMetrics::with_routes(&routes));
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .route_table(routes)
+        .metrics_backend(metrics.clone());
+    http_server
+        .serve(Router::new().route("/say_hello/:caller", get(|| async { "Hello!" })))
+        .unwrap();
+
+    send(
+        addr,
+        b"GET /say_hello/Gooxey HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    send(
+        addr,
+        b"GET /say_hello/world HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    send(addr, b"GET /missing HTTP/1.1\r\nConnection: close\r\n\r\n");
+    http_server.shutdown().await;
+
+    // both paths land in the series of their route
+    let exposition = metrics.render();
+    for line in [
+        "# TYPE goohttp_route_requests_total counter",
+        "goohttp_route_requests_total{method=\"GET\",route=\"/say_hello/:caller\"} 2",
+        "goohttp_route_requests_total{route=\"unmatched\"} 1",
+        "goohttp_route_request_duration_seconds_count{method=\"GET\",route=\"/say_hello/:caller\"} 2",
+    ] {
+        assert!(
+            exposition.lines().any(|exposed| exposed == line),
+            "`{line}` is missing from:\n{exposition}"
+        );
+    }
+    assert!(!exposition.contains("Gooxey"));
+}
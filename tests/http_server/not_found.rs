@@ -0,0 +1,78 @@
+use goohttp::{
+    axum::{
+        http::StatusCode,
+        routing::get,
+        Router,
+    },
+    http_server::HttpServer,
+};
+
+use crate::{
+    capture_logs,
+    free_addr,
+    logs_of,
+    send,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn empty_404s_get_the_not_found_page() {
+    let router = Router::new().route("/", get(|| async { "home" })).route(
+        "/missing-user",
+        get(|| async { (StatusCode::NOT_FOUND, "no such user") }),
+    );
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .not_found_page("<h1>Not here</h1>", "text/html; charset=utf-8");
+    http_server.serve(router).unwrap();
+
+    let response = send(
+        addr,
+        b"GET /nowhere HTTP/1.1\r\n\r\nGET /missing-user HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let (unmatched, handled) = response.split_once("<h1>Not here</h1>").unwrap();
+
+    assert!(unmatched.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(unmatched.contains("content-type: text/html; charset=utf-8\r\n"));
+    assert!(unmatched.contains("content-length: 17\r\n"));
+    assert!(handled.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(handled.ends_with("\r\n\r\nno such user"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn unmatched_routes_get_logged() {
+    capture_logs();
+    let router = Router::new().route(
+        "/missing-user",
+        get(|| async { (StatusCode::NOT_FOUND, "no such user") }),
+    );
+    // only the first request matches no route, since the others are answered by `405 Method Not Allowed` and a 404 with a body
+    let request = b"GET /nowhere?page=2 HTTP/1.1\r\n\r\nPOST /missing-user HTTP/1.1\r\n\r\n\
+        GET /missing-user HTTP/1.1\r\nConnection: close\r\n\r\n";
+
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("Unmatched"), None).unwrap();
+    http_server.serve(router.clone()).unwrap();
+    send(addr, request);
+    http_server.shutdown().await;
+    assert_eq!(
+        logs_of("Unmatched")
+            .into_iter()
+            .filter(|message| message.starts_with("No route matched"))
+            .collect::<Vec<_>>(),
+        ["No route matched the request `GET /nowhere`."]
+    );
+
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, Some("UnmatchedQuiet"), None)
+        .unwrap()
+        .log_unmatched_routes(false);
+    http_server.serve(router).unwrap();
+    send(addr, request);
+    http_server.shutdown().await;
+    assert!(!logs_of("UnmatchedQuiet")
+        .iter()
+        .any(|message| message.starts_with("No route matched")));
+}
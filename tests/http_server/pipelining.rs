@@ -0,0 +1,433 @@
+use std::{
+    io::{
+        Read,
+        Write,
+    },
+    net::{
+        Shutdown,
+        SocketAddr,
+        TcpStream,
+    },
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use goohttp::{
+    axum::{
+        body::Bytes,
+        http::{
+            header::CONNECTION,
+            StatusCode,
+        },
+        routing::{
+            get,
+            post,
+        },
+        Extension,
+        Router,
+    },
+    http_server::{
+        HttpServer,
+        Trailers,
+    },
+    testing::MemoryListener,
+};
+use tokio::task::yield_now;
+
+use crate::{
+    free_addr,
+    send,
+    send_in_memory,
+    serve,
+    serve_in_memory,
+};
+
+fn router() -> Router {
+    Router::new()
+        .route("/first", get(|| async { "first" }))
+        .route("/second", get(|| async { "second" }))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn pipelined_requests() {
+    let (mut http_server, connector) = serve_in_memory(router());
+
+    let response = send_in_memory(
+        &connector,
+        b"GET /first HTTP/1.1\r\nHost: localhost\r\n\r\nGET /second HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+    );
+
+    let first = response.find("\r\n\r\nfirst").unwrap();
+    let second = response.find("\r\n\r\nsecond").unwrap();
+    assert!(first < second);
+    assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2);
+    assert!(response.contains("content-length: 5\r\n"));
+    assert!(response.contains("content-length: 6\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn http_1_0_closes_by_default() {
+    let (mut http_server, addr) = serve(router());
+
+    let response = send(
+        addr,
+        b"GET /first HTTP/1.0\r\n\r\nGET /second HTTP/1.0\r\n\r\n",
+    );
+
+    assert!(response.ends_with("\r\n\r\nfirst"));
+    assert!(response.contains("connection: close\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn bad_requests_close_the_connection() {
+    let router = router()
+        .route(
+            "/invalid",
+            get(|| async { (StatusCode::BAD_REQUEST, "invalid") }),
+        )
+        .route(
+            "/closing",
+            get(|| async { ([(CONNECTION, "Close")], "closing") }),
+        );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    // the pipelined request after a `400 Bad Request` does not get answered, even though the client asked to keep the connection open
+    let response = send_in_memory(
+        &connector,
+        b"GET /invalid HTTP/1.1\r\nConnection: keep-alive\r\n\r\nGET /first HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(response.contains("connection: close\r\n"), "{response:?}");
+    assert!(response.ends_with("\r\n\r\ninvalid"), "{response:?}");
+
+    // the header of the route gets replaced instead of sent twice
+    let response = send_in_memory(
+        &connector,
+        b"GET /closing HTTP/1.1\r\n\r\nGET /first HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    assert_eq!(
+        response.to_lowercase().matches("connection: close").count(),
+        1,
+        "{response:?}"
+    );
+    assert!(response.ends_with("\r\n\r\nclosing"), "{response:?}");
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn request_bodies_do_not_leak_into_the_next_request() {
+    let router = Router::new()
+        .route("/echo", post(|body: String| async move { body }))
+        .route("/second", get(|| async { "second" }));
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    let response = send_in_memory(
+        &connector,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 24\r\n\r\nGET /second HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+
+    assert!(response.contains("\r\n\r\nGET /second HTTP/1.1"));
+    assert!(response.ends_with("\r\n\r\nsecond"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn unread_bodies_get_skipped() {
+    let (mut http_server, connector) = serve_in_memory(router());
+
+    // the routes never read the bodies of the `GET` requests, which still must not be mistaken for the pipelined requests
+    let response = send_in_memory(
+        &connector,
+        b"GET /first HTTP/1.1\r\nContent-Length: 22\r\n\r\nGET /first HTTP/1.1\r\n\r\n\
+        GET /first HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\nTrailer: value\r\n\r\n\
+        GET /second HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+    let responses: Vec<&str> = response.split("HTTP/1.1 ").skip(1).collect();
+    assert_eq!(responses.len(), 3, "{response:?}");
+    assert!(responses[0].ends_with("\r\n\r\nfirst"), "{response:?}");
+    assert!(responses[1].ends_with("\r\n\r\nfirst"), "{response:?}");
+    assert!(!responses[1].contains("connection: close"), "{response:?}");
+    assert!(responses[2].ends_with("\r\n\r\nsecond"), "{response:?}");
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn body_read_strategies() {
+    /// Send the given bytes to the given address, and close the sending half of the connection to end the body.
+    fn send_and_close(addr: SocketAddr, request: &[u8]) -> String {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(request).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    let router = Router::new().route("/echo", post(|body: String| async move { body }));
+    let (mut http_server, addr) = serve(router);
+
+    // HTTP/1.0 bodies without a `Content-Length` end with the connection
+    let response = send_and_close(addr, b"POST /echo HTTP/1.0\r\n\r\nuntil the end");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+    assert!(response.ends_with("\r\n\r\nuntil the end"), "{response:?}");
+
+    // otherwise, the `Content-Length` is read exactly
+    let response = send_and_close(
+        addr,
+        b"POST /echo HTTP/1.0\r\nContent-Length: 5\r\n\r\nhello, world",
+    );
+    assert!(response.ends_with("\r\n\r\nhello"), "{response:?}");
+    let response = send_and_close(
+        addr,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello, world",
+    );
+    // the rest gets read as the next request, which is malformed
+    assert!(
+        response.contains("\r\n\r\nhelloHTTP/1.1 400 Bad Request\r\n"),
+        "{response:?}"
+    );
+
+    // a chunked body has to end with its last chunk, not with the connection
+    let response = send_and_close(
+        addr,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n3\r\n, w",
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{response:?}"
+    );
+    assert!(response.contains("malformed"), "{response:?}");
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn chunked_request_bodies_with_trailers() {
+    let router = Router::new()
+        .route(
+            "/upload",
+            post(
+                |Extension(trailers): Extension<Trailers>, body: Bytes| async move {
+                    let trailers = trailers.get().unwrap();
+                    format!(
+                        "[{}|{}|{}]",
+                        String::from_utf8_lossy(&body),
+                        trailers["x-checksum"].to_str().unwrap(),
+                        trailers.len()
+                    )
+                },
+            ),
+        )
+        .route("/second", get(|| async { "second" }));
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    let response = send_in_memory(
+        &connector,
+        b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;name=value\r\nhello\r\n7\r\n, world\r\n0\r\nX-Checksum: 1234\r\nX-Other: 1\r\n\r\nGET /second HTTP/1.1\r\nConnection: close\r\n\r\n",
+    );
+
+    // the trailers are neither part of the body nor mistaken for the next request
+    assert!(
+        response.contains("\r\n\r\n[hello, world|1234|2]"),
+        "{response:?}"
+    );
+    assert!(response.ends_with("\r\n\r\nsecond"), "{response:?}");
+    assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2);
+
+    // a chunked body next to a `Content-Length` could be read differently by a proxy in front of the server
+    let response = send_in_memory(
+        &connector,
+        b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 5\r\n\r\n0\r\n\r\n",
+    );
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{response:?}"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn http_10_requests_without_a_body() {
+    let router = Router::new().route(
+        "/echo",
+        get(|body: String| async move { format!("[{body}]") }),
+    );
+    let (mut http_server, addr) = serve(router);
+
+    // the client waits for the response without closing the connection, so there is no body to wait for
+    let response = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        client.write_all(b"GET /echo HTTP/1.0\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+    assert!(response.ends_with("\r\n\r\n[]"), "{response:?}");
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn keep_alive_header() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .keep_alive_timeout(Duration::from_secs(5))
+        .max_requests_per_connection(3);
+    http_server.serve(router()).unwrap();
+
+    let response = send(
+        addr,
+        b"GET /first HTTP/1.1\r\n\r\nGET /first HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\n\r\n",
+    );
+    let responses = response
+        .split("HTTP/1.1 200 OK")
+        .skip(1)
+        .collect::<Vec<_>>();
+    // the last request exceeds the limit and does not get answered
+    assert_eq!(responses.len(), 3);
+    assert!(responses[0].contains("keep-alive: timeout=5, max=2\r\n"));
+    assert!(responses[1].contains("keep-alive: timeout=5, max=1\r\n"));
+    assert!(responses[2].contains("connection: close\r\n"));
+    assert!(!responses[2].contains("keep-alive"));
+
+    // HTTP/1.0 clients have to be told that the connection stays open
+    let mut client = TcpStream::connect(addr).unwrap();
+    client
+        .write_all(b"GET /first HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+        .unwrap();
+    let mut response = [0; 1024];
+    let mut read = 0;
+    while !response[..read].ends_with(b"first") {
+        read += client.read(&mut response[read..]).unwrap();
+    }
+    let response = String::from_utf8_lossy(&response[..read]);
+    assert!(response.contains("connection: keep-alive\r\n"));
+    assert!(response.contains("keep-alive: timeout=5, max=2\r\n"));
+    drop(client);
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn idle_connections_time_out() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None)
+        .unwrap()
+        .keep_alive_timeout(Duration::from_millis(200));
+    http_server.serve(router()).unwrap();
+
+    let start = Instant::now();
+    let response = send(addr, b"GET /first HTTP/1.1\r\n\r\n");
+    assert!(response.ends_with("\r\n\r\nfirst"));
+    assert!(response.contains("keep-alive: timeout=0\r\n"));
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn idle_keep_alive_clients_do_not_stall_the_runtime() {
+    let addr = free_addr();
+    let mut http_server = HttpServer::bind(addr, None, None).unwrap();
+    http_server.serve(router()).unwrap();
+
+    let answered = tokio::task::spawn_blocking(move || {
+        // every client has to be answered long before the idle ones time out
+        let request = |path: &str| {
+            let mut client = TcpStream::connect(addr).unwrap();
+            client
+                .set_read_timeout(Some(Duration::from_secs(1)))
+                .unwrap();
+            client
+                .write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes())
+                .unwrap();
+            let mut response = [0; 1024];
+            let mut read = 0;
+            while !response[..read].ends_with(&path.as_bytes()[1..]) {
+                match client.read(&mut response[read..]) {
+                    Ok(0) | Err(_) => return None,
+                    Ok(bytes) => read += bytes,
+                }
+            }
+            Some(client)
+        };
+        // like browsers, the clients keep their connections open after their response
+        let idle = (0..4).map(|_| request("/first")).collect::<Vec<_>>();
+        let answered = idle.iter().all(Option::is_some) && request("/second").is_some();
+        drop(idle);
+        answered
+    })
+    .await
+    .unwrap();
+    assert!(answered);
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn yield_between_requests() {
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let ticker = {
+        let ticks = ticks.clone();
+        tokio::spawn(async move {
+            loop {
+                ticks.fetch_add(1, Ordering::SeqCst);
+                yield_now().await;
+            }
+        })
+    };
+    let router = Router::new().route(
+        "/ticks",
+        get(move || async move { format!("[{}]", ticks.load(Ordering::SeqCst)) }),
+    );
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .yield_between_requests(true);
+    http_server.serve_on(listener, router).unwrap();
+
+    // every request is already waiting, so the connection could answer all of them at once
+    let mut requests = b"GET /ticks HTTP/1.1\r\n\r\n".repeat(9);
+    requests.extend_from_slice(b"GET /ticks HTTP/1.1\r\nConnection: close\r\n\r\n");
+    let response = tokio::task::spawn_blocking(move || send_in_memory(&connector, &requests))
+        .await
+        .unwrap();
+
+    let mut ticks: Vec<usize> = response
+        .split('[')
+        .skip(1)
+        .map(|ticks| ticks.split(']').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(ticks.len(), 10);
+    // without yielding, every request would see the same number of ticks
+    ticks.dedup();
+    assert!(ticks.len() >= 5, "{ticks:?}");
+
+    ticker.abort();
+    http_server.shutdown().await;
+}
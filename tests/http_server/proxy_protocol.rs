@@ -0,0 +1,102 @@
+use std::sync::mpsc;
+
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    error::ParseError,
+    http_server::{
+        ConnInfo,
+        HttpServer,
+    },
+    testing::MemoryListener,
+    Error,
+};
+
+use crate::send_in_memory;
+
+/// Build a header of version 2 with the given command, address family, and addresses.
+fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+    let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+    header.push(0x20 | command);
+    header.push(family);
+    header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+    header.extend_from_slice(addresses);
+    header
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn proxy_protocol() {
+    let (results, received) = mpsc::channel();
+    let router = Router::new().route(
+        "/",
+        get(|ConnInfo(info): ConnInfo| async move { format!("[{}]", info.peer_addr) }),
+    );
+    let (listener, connector) = MemoryListener::new();
+    let mut http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .proxy_protocol(true)
+        .on_connection_result(move |result| {
+            let _ = results.send(matches!(result, Err(Error::Parse(ParseError::ProxyHeader))));
+        });
+    http_server.serve_on(listener, router).unwrap();
+
+    let mut v2_tcp4 = vec![192, 168, 1, 20, 10, 0, 0, 1];
+    v2_tcp4.extend_from_slice(&[0xd4, 0x31, 0x00, 0x50]);
+    let mut v2_tcp6 = vec![0x20, 0x01, 0x0d, 0xb8];
+    v2_tcp6.extend_from_slice(&[0; 11]);
+    v2_tcp6.push(1);
+    v2_tcp6.extend_from_slice(&[0; 16]);
+    v2_tcp6.extend_from_slice(&[0xd4, 0x31, 0x00, 0x50]);
+    // a TLV after the addresses gets skipped
+    v2_tcp6.extend_from_slice(&[0x04, 0x00, 0x01, 0x00]);
+    let cases = [
+        (
+            b"PROXY TCP4 192.168.1.20 10.0.0.1 54321 80\r\n".to_vec(),
+            "[192.168.1.20:54321]",
+        ),
+        (
+            b"PROXY TCP6 2001:db8::1 2001:db8::2 54321 80\r\n".to_vec(),
+            "[[2001:db8::1]:54321]",
+        ),
+        (b"PROXY UNKNOWN\r\n".to_vec(), "[127.0.0.1:0]"),
+        (v2_header(1, 0x11, &v2_tcp4), "[192.168.1.20:54321]"),
+        (v2_header(1, 0x21, &v2_tcp6), "[[2001:db8::1]:54321]"),
+        (v2_header(0, 0x00, &[]), "[127.0.0.1:0]"),
+    ];
+    for (header, peer_addr) in cases {
+        let mut request = header.clone();
+        request.extend_from_slice(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n");
+        let response = tokio::task::spawn_blocking({
+            let connector = connector.clone();
+            move || send_in_memory(&connector, &request)
+        })
+        .await
+        .unwrap();
+        assert!(
+            response.ends_with(peer_addr),
+            "{:?}: {response}",
+            String::from_utf8_lossy(&header)
+        );
+        assert!(!received.recv().unwrap());
+    }
+
+    // without a valid header, the connection gets closed before the request is read
+    for request in [
+        b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n".to_vec(),
+        b"PROXY TCP4 not-an-address 10.0.0.1 54321 80\r\nGET / HTTP/1.1\r\n\r\n".to_vec(),
+        v2_header(1, 0x11, &[192, 168, 1, 20]),
+    ] {
+        let response = tokio::task::spawn_blocking({
+            let connector = connector.clone();
+            move || send_in_memory(&connector, &request)
+        })
+        .await
+        .unwrap();
+        assert_eq!(response, "");
+        assert!(received.recv().unwrap());
+    }
+
+    http_server.shutdown().await;
+}
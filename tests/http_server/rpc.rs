@@ -0,0 +1,127 @@
+use std::io::{
+    Read,
+    Write,
+};
+
+use goohttp::{
+    axum::{
+        routing::{
+            get,
+            post,
+        },
+        Router,
+    },
+    http_server::{
+        ConnInfo,
+        HttpServer,
+    },
+    rpc_server::RpcServer,
+    testing::{
+        MemoryConnector,
+        MemoryListener,
+        MemoryStream,
+    },
+};
+
+/// Serve a small router with an [`RpcServer`] created from the given [`HttpServer`].
+fn serve_rpc(http_server: HttpServer) -> (RpcServer, MemoryConnector) {
+    let router = Router::new()
+        .route("/echo", post(|body: String| async move { body }))
+        .route(
+            "/requests",
+            post(|ConnInfo(info): ConnInfo| async move { info.requests_served.to_string() }),
+        )
+        .route("/get_only", get(|| async { "unreachable" }));
+    let (listener, connector) = MemoryListener::new();
+    let mut rpc_server = RpcServer::new(http_server);
+    rpc_server.serve_on(listener, router).unwrap();
+    (rpc_server, connector)
+}
+
+/// Build a request frame with the given path and body.
+fn frame(path: &str, body: &[u8]) -> Vec<u8> {
+    let mut frame = (path.len() as u16).to_be_bytes().to_vec();
+    frame.extend_from_slice(path.as_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Read the status code and body of the next response frame.
+fn read_frame(client: &mut MemoryStream) -> (u16, Vec<u8>) {
+    let mut head = [0; 6];
+    client.read_exact(&mut head).unwrap();
+    let mut body = vec![0; u32::from_be_bytes([head[2], head[3], head[4], head[5]]) as usize];
+    client.read_exact(&mut body).unwrap();
+    (u16::from_be_bytes([head[0], head[1]]), body)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn frames_get_answered_by_the_router() {
+    let (mut rpc_server, connector) =
+        serve_rpc(HttpServer::bind("127.0.0.1:0", None, None).unwrap());
+
+    let mut client = connector.connect().unwrap();
+    // several frames can be sent at once over the same connection
+    let mut frames = frame("/echo", b"Hello World!");
+    frames.extend(frame("/requests", b""));
+    frames.extend(frame("/missing", b""));
+    frames.extend(frame("/get_only", b""));
+    client.write_all(&frames).unwrap();
+
+    assert_eq!(read_frame(&mut client), (200, b"Hello World!".to_vec()));
+    assert_eq!(read_frame(&mut client), (200, b"1".to_vec()));
+    assert_eq!(read_frame(&mut client).0, 404);
+    assert_eq!(read_frame(&mut client).0, 405);
+    assert_eq!(rpc_server.http_server().total_requests(), 4);
+
+    rpc_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn limits_of_the_http_server_apply() {
+    let http_server = HttpServer::bind("127.0.0.1:0", None, None)
+        .unwrap()
+        .max_request_body(8)
+        .max_requests_per_connection(2);
+    let (mut rpc_server, connector) = serve_rpc(http_server);
+
+    let mut client = connector.connect().unwrap();
+    client
+        .write_all(&frame("/echo", b"too large for the limit"))
+        .unwrap();
+    assert_eq!(read_frame(&mut client).0, 413);
+
+    // a body the route did not read ends the connection
+    let mut client = connector.connect().unwrap();
+    client.write_all(&frame("/requests", b"unread")).unwrap();
+    assert_eq!(read_frame(&mut client), (200, b"0".to_vec()));
+    assert_eq!(client.read(&mut [0; 16]).unwrap(), 0);
+
+    let mut client = connector.connect().unwrap();
+    for _ in 0..2 {
+        client.write_all(&frame("/echo", b"small")).unwrap();
+        assert_eq!(read_frame(&mut client), (200, b"small".to_vec()));
+    }
+    assert_eq!(client.read(&mut [0; 16]).unwrap(), 0);
+
+    rpc_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn malformed_frames_close_the_connection() {
+    let (mut rpc_server, connector) =
+        serve_rpc(HttpServer::bind("127.0.0.1:0", None, None).unwrap());
+
+    // a relative path, and one which is not valid UTF-8
+    for path in [&b"echo"[..], b"/\xff"] {
+        let mut client = connector.connect().unwrap();
+        let mut request = (path.len() as u16).to_be_bytes().to_vec();
+        request.extend_from_slice(path);
+        request.extend_from_slice(&0_u32.to_be_bytes());
+        client.write_all(&request).unwrap();
+        assert_eq!(client.read(&mut [0; 16]).unwrap(), 0);
+    }
+
+    rpc_server.shutdown().await;
+}
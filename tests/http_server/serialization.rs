@@ -0,0 +1,95 @@
+use goohttp::{
+    axum::{
+        http::{
+            header::{
+                CACHE_CONTROL,
+                LINK,
+            },
+            HeaderMap,
+            HeaderValue,
+            StatusCode,
+        },
+        response::IntoResponse,
+        routing::get,
+        Router,
+    },
+    EarlyHints,
+};
+
+use crate::{
+    send_in_memory,
+    serve_in_memory,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn responses_get_serialized_byte_for_byte() {
+    let router = Router::new().route(
+        "/",
+        get(|| async {
+            let mut headers = HeaderMap::new();
+            headers.append(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+            headers.append(CACHE_CONTROL, HeaderValue::from_static("private"));
+            headers.append("x-note", HeaderValue::from_static("tab\tand space"));
+            (StatusCode::IM_A_TEAPOT, headers, "short and stout")
+        }),
+    );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    assert_eq!(
+        send_in_memory(&connector, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n"),
+        "HTTP/1.1 418 I'm a teapot\r\n\
+         content-type: text/plain; charset=utf-8\r\n\
+         cache-control: no-store\r\n\
+         cache-control: private\r\n\
+         x-note: tab\tand space\r\n\
+         content-length: 15\r\n\
+         connection: close\r\n\
+         \r\n\
+         short and stout"
+    );
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn early_hints_precede_the_response() {
+    let router = Router::new().route(
+        "/",
+        get(|| async {
+            let mut preload = HeaderMap::new();
+            preload.append(LINK, HeaderValue::from_static("</style.css>; rel=preload"));
+            let mut preconnect = HeaderMap::new();
+            preconnect.append(
+                LINK,
+                HeaderValue::from_static("<https://cdn>; rel=preconnect"),
+            );
+
+            let mut response = "page".into_response();
+            response
+                .extensions_mut()
+                .insert(EarlyHints(vec![preload, preconnect]));
+            response
+        }),
+    );
+    let (mut http_server, connector) = serve_in_memory(router);
+
+    assert_eq!(
+        send_in_memory(&connector, b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n"),
+        "HTTP/1.1 103 Early Hints\r\n\
+         link: </style.css>; rel=preload\r\n\
+         \r\n\
+         HTTP/1.1 103 Early Hints\r\n\
+         link: <https://cdn>; rel=preconnect\r\n\
+         \r\n\
+         HTTP/1.1 200 OK\r\n\
+         content-type: text/plain; charset=utf-8\r\n\
+         content-length: 4\r\n\
+         connection: close\r\n\
+         \r\n\
+         page"
+    );
+    // HTTP/1.0 clients do not know informational responses
+    let response = send_in_memory(&connector, b"GET / HTTP/1.0\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response:?}");
+    assert!(!response.contains("103"), "{response:?}");
+    http_server.shutdown().await;
+}
@@ -0,0 +1,265 @@
+use std::{
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    net::TcpStream,
+    pin::Pin,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use futures_core::Stream;
+use goohttp::{
+    axum::{
+        body::Bytes,
+        extract::Path,
+        http::{
+            header::CONTENT_LENGTH,
+            HeaderValue,
+        },
+        routing::get,
+        Router,
+    },
+    response::{
+        Event,
+        EventStream,
+        StreamBody,
+    },
+};
+use tokio::sync::mpsc::{
+    unbounded_channel,
+    UnboundedReceiver,
+    UnboundedSender,
+};
+
+use crate::{
+    send,
+    serve,
+};
+
+/// A stream yielding every chunk sent through its channel.
+struct Chunks(UnboundedReceiver<Result<Bytes, io::Error>>);
+impl Stream for Chunks {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// A stream yielding every event sent through its channel.
+struct Events(UnboundedReceiver<Event>);
+impl Stream for Events {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// A router streaming the chunks sent through the returned channel to the first client requesting `/`.
+fn router() -> (Router, UnboundedSender<Result<Bytes, io::Error>>) {
+    let (sender, receiver) = unbounded_channel();
+    let receiver = Arc::new(Mutex::new(Some(receiver)));
+    let router = Router::new().route(
+        "/",
+        get(|| async move { StreamBody(Chunks(receiver.lock().unwrap().take().unwrap())) }),
+    );
+    (router, sender)
+}
+
+/// Read from the given client until the received bytes end with the given suffix.
+fn read_until(client: &mut TcpStream, received: &mut Vec<u8>, suffix: &[u8]) {
+    let mut buffer = [0; 1024];
+    while !received.ends_with(suffix) {
+        let read = client.read(&mut buffer).unwrap();
+        assert_ne!(read, 0, "The connection was closed early.");
+        received.extend_from_slice(&buffer[..read]);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn chunks_arrive_in_order() {
+    let (router, sender) = router();
+    let (mut http_server, addr) = serve(router);
+
+    let received = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+
+        // every chunk has to arrive before the next one gets produced
+        let mut received = vec![];
+        for chunk in 1..=5 {
+            let chunk = format!("progress {}0%", chunk * 2);
+            sender.send(Ok(Bytes::from(chunk.clone()))).unwrap();
+            read_until(
+                &mut client,
+                &mut received,
+                format!("{chunk}\r\n").as_bytes(),
+            );
+        }
+        drop(sender);
+        client.read_to_end(&mut received).unwrap();
+        String::from_utf8(received).unwrap()
+    })
+    .await
+    .unwrap();
+
+    let (head, body) = received.split_once("\r\n\r\n").unwrap();
+    assert!(head.contains("transfer-encoding: chunked"));
+    assert!(!head.contains("content-length"));
+    assert_eq!(
+        body,
+        "c\r\nprogress 20%\r\nc\r\nprogress 40%\r\nc\r\nprogress 60%\r\nc\r\nprogress 80%\r\nd\r\nprogress 100%\r\n0\r\n\r\n"
+    );
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn http_1_0_reads_until_close() {
+    let (router, sender) = router();
+    let (mut http_server, addr) = serve(router);
+
+    sender.send(Ok(Bytes::from("first "))).unwrap();
+    sender.send(Ok(Bytes::from("second"))).unwrap();
+    drop(sender);
+    let response = tokio::task::spawn_blocking(move || send(addr, b"GET / HTTP/1.0\r\n\r\n"))
+        .await
+        .unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(!head.contains("transfer-encoding"));
+    assert!(head.contains("connection: close"));
+    assert_eq!(body, "first second");
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn errors_close_the_connection() {
+    let (router, sender) = router();
+    let (mut http_server, addr) = serve(router);
+
+    sender.send(Ok(Bytes::from("partial"))).unwrap();
+    sender.send(Err(io::Error::other("sensor failed"))).unwrap();
+    let response = tokio::task::spawn_blocking(move || send(addr, b"GET / HTTP/1.1\r\n\r\n"))
+        .await
+        .unwrap();
+
+    // the missing last chunk tells the client that the body is incomplete
+    assert!(response.ends_with("7\r\npartial\r\n"));
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn known_lengths() {
+    // streams `first second` while declaring the length given in the path
+    let router = Router::new().route(
+        "/:length",
+        get(|Path(length): Path<u64>| async move {
+            let (sender, receiver) = unbounded_channel();
+            sender.send(Ok(Bytes::from("first "))).unwrap();
+            sender.send(Ok(Bytes::from("second"))).unwrap();
+            (
+                [(CONTENT_LENGTH, HeaderValue::from(length))],
+                StreamBody(Chunks(receiver)),
+            )
+        }),
+    );
+    let (mut http_server, addr) = serve(router);
+
+    // the connection stays open, even for HTTP/1.0 clients
+    let response = tokio::task::spawn_blocking(move || {
+        send(
+            addr,
+            b"GET /12 HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /12 HTTP/1.1\r\nConnection: close\r\n\r\n",
+        )
+    })
+    .await
+    .unwrap();
+    let responses: Vec<&str> = response.split("HTTP/1.1 200 OK\r\n").skip(1).collect();
+    assert_eq!(responses.len(), 2, "{response}");
+    for response in responses {
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("content-length: 12"));
+        assert!(!head.contains("transfer-encoding"));
+        assert_eq!(body, "first second");
+    }
+
+    // a body not matching its length closes the connection instead of answering the next request
+    for (length, body) in [("20", "first second"), ("8", "first se")] {
+        let request = format!("GET /{length} HTTP/1.1\r\n\r\nGET /12 HTTP/1.1\r\n\r\n");
+        let response = tokio::task::spawn_blocking(move || send(addr, request.as_bytes()))
+            .await
+            .unwrap();
+        assert_eq!(response.matches("HTTP/1.1").count(), 1, "{response}");
+        assert!(response.ends_with(&format!("\r\n\r\n{body}")), "{response}");
+    }
+
+    http_server.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn server_sent_events() {
+    let (sender, receiver) = unbounded_channel();
+    let receiver = Arc::new(Mutex::new(Some(receiver)));
+    let router = Router::new().route(
+        "/events",
+        get(|| async move { EventStream(Events(receiver.lock().unwrap().take().unwrap())) }),
+    );
+    let (mut http_server, addr) = serve(router);
+
+    let received = tokio::task::spawn_blocking(move || {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET /events HTTP/1.1\r\nAccept: text/event-stream\r\n\r\n")
+            .unwrap();
+
+        // the response stays open while the events get pushed one after another
+        let mut received = vec![];
+        sender.send(Event::default().retry(1000)).unwrap();
+        read_until(&mut client, &mut received, b"retry: 1000\n\n\r\n");
+        for reading in ["21.5", "21.7", "22.0"] {
+            sender
+                .send(Event::default().event("temperature").data(reading))
+                .unwrap();
+            read_until(
+                &mut client,
+                &mut received,
+                format!("data: {reading}\n\n\r\n").as_bytes(),
+            );
+        }
+        drop(sender);
+        read_until(&mut client, &mut received, b"0\r\n\r\n");
+        String::from_utf8(received).unwrap()
+    })
+    .await
+    .unwrap();
+
+    let (head, body) = received.split_once("\r\n\r\n").unwrap();
+    assert!(head.contains("content-type: text/event-stream"));
+    assert!(head.contains("cache-control: no-cache"));
+    assert!(head.contains("transfer-encoding: chunked"));
+    assert_eq!(
+        body,
+        "d\r\nretry: 1000\n\n\r\n\
+         1f\r\nevent: temperature\ndata: 21.5\n\n\r\n\
+         1f\r\nevent: temperature\ndata: 21.7\n\n\r\n\
+         1f\r\nevent: temperature\ndata: 22.0\n\n\r\n\
+         0\r\n\r\n"
+    );
+
+    http_server.shutdown().await;
+}
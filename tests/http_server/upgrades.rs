@@ -0,0 +1,36 @@
+use goohttp::axum::{
+    http::HeaderMap,
+    routing::get,
+    Router,
+};
+
+use crate::{
+    send,
+    serve,
+};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn h2c_upgrades_get_ignored() {
+    let router = Router::new().route(
+        "/",
+        get(|headers: HeaderMap| async move {
+            format!(
+                "upgrade: {}, settings: {}",
+                headers.contains_key("upgrade"),
+                headers.contains_key("http2-settings")
+            )
+        }),
+    );
+    let (mut http_server, addr) = serve(router);
+
+    let response = send(
+        addr,
+        b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade, HTTP2-Settings, close\r\nUpgrade: h2c\r\nHTTP2-Settings: AAMAAABkAARAAAAAAAIAAAAA\r\n\r\n",
+    );
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(!response.contains("upgrade: h2c"));
+    assert!(response.ends_with("\r\n\r\nupgrade: false, settings: false"));
+
+    http_server.shutdown().await;
+}
@@ -0,0 +1,335 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    time::Duration,
+};
+
+use goohttp::{
+    axum::{
+        http::{
+            header::{
+                CACHE_CONTROL,
+                CONTENT_TYPE,
+                HOST,
+                RETRY_AFTER,
+                VARY,
+            },
+            HeaderMap,
+            Method,
+            StatusCode,
+        },
+        middleware::{
+            from_fn,
+            Next,
+        },
+        response::{
+            IntoResponse,
+            Response,
+        },
+        routing::get,
+        Router,
+    },
+    middleware::{
+        content_type::ContentTypeGuessLayer,
+        dns_rebind::DnsRebindProtectionLayer,
+        favicon::FaviconLayer,
+        throttle::{
+            Priority,
+            PriorityThrottleConfig,
+            PriorityThrottleLayer,
+            Rate,
+        },
+        vary::VaryLayer,
+    },
+    middleware_stack,
+};
+use hyper::{
+    service::Service,
+    Body,
+    Request,
+};
+
+#[tokio::test]
+async fn vary() {
+    let mut router = Router::new()
+        .route("/", get(|| async { "index" }))
+        .route(
+            "/origin",
+            get(|| async { ([(VARY, "Origin, accept-encoding")], "origin").into_response() }),
+        )
+        .route(
+            "/everything",
+            get(|| async { ([(VARY, "*")], "everything").into_response() }),
+        )
+        .layer(VaryLayer(vec!["Accept-Encoding", "Accept-Language"]));
+
+    let cases = [
+        ("/", "Accept-Encoding, Accept-Language"),
+        ("/origin", "Origin, accept-encoding, Accept-Language"),
+        ("/everything", "*"),
+    ];
+    for (path, vary) in cases {
+        let response = router
+            .call(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let values: Vec<_> = response.headers().get_all(VARY).iter().collect();
+        assert_eq!(values, [vary], "wrong `Vary` header for `{path}`");
+    }
+}
+
+#[tokio::test]
+async fn favicon() {
+    /// Send a request with the given method and path to the given router.
+    async fn call(router: &mut Router, method: Method, path: &str) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        router.call(request).await.unwrap()
+    }
+
+    let index = Router::new().route("/", get(|| async { "index" }));
+    let mut with_icon = index
+        .clone()
+        .layer(FaviconLayer::icon(&b"icon"[..], "image/x-icon"));
+    let mut without_icon = index.layer(FaviconLayer::no_content());
+
+    for method in [Method::GET, Method::HEAD] {
+        let response = call(&mut with_icon, method, "/favicon.ico").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "image/x-icon");
+        assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=86400");
+    }
+    let response = call(&mut with_icon, Method::GET, "/favicon.ico").await;
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, "icon");
+
+    let response = call(&mut without_icon, Method::GET, "/favicon.ico").await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=86400");
+
+    // other requests still reach the router
+    let response = call(&mut with_icon, Method::GET, "/").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = call(&mut without_icon, Method::POST, "/favicon.ico").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn priority_throttle() {
+    let config = PriorityThrottleConfig::new(|request| match request.uri.path() {
+        "/health" => Priority::High,
+        "/logs" => Priority::Low,
+        _ => Priority::Normal,
+    })
+    .limit(
+        Priority::Normal,
+        Rate {
+            requests: 2,
+            period: Duration::from_secs(60),
+        },
+    )
+    .limit(
+        Priority::Low,
+        Rate {
+            requests: 0,
+            period: Duration::from_secs(90),
+        },
+    );
+    let mut router = Router::new()
+        .route("/", get(|| async { "index" }))
+        .route("/health", get(|| async { "healthy" }))
+        .route("/logs", get(|| async { "logs" }))
+        .layer(PriorityThrottleLayer::new(config));
+
+    let cases = [
+        ("/", StatusCode::OK, None),
+        ("/", StatusCode::OK, None),
+        // one token gets refilled every 30 seconds
+        ("/", StatusCode::TOO_MANY_REQUESTS, Some("30")),
+        ("/health", StatusCode::OK, None),
+        ("/logs", StatusCode::TOO_MANY_REQUESTS, Some("90")),
+    ];
+    for (path, status, retry_after) in cases {
+        let response = router
+            .call(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "wrong status for `{path}`");
+        let sent = response
+            .headers()
+            .get(RETRY_AFTER)
+            .map(|value| value.to_str().unwrap());
+        assert_eq!(sent, retry_after, "wrong `Retry-After` for `{path}`");
+    }
+    for _ in 0..20 {
+        let response = router
+            .call(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn middleware_stack() {
+    /// Create a middleware adding the given name to the `x-order` header of both the request and the response.
+    fn tag(
+        name: &'static str,
+    ) -> impl Fn(Request<Body>, Next<Body>) -> Pin<Box<dyn Future<Output = Response> + Send>>
+           + Clone
+           + Send
+           + Sync
+           + 'static {
+        move |mut request, next| {
+            Box::pin(async move {
+                request
+                    .headers_mut()
+                    .append("x-order", name.parse().unwrap());
+                let mut response = next.run(request).await;
+                response
+                    .headers_mut()
+                    .append("x-order", name.parse().unwrap());
+                response
+            })
+        }
+    }
+
+    let mut router = Router::new()
+        .route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                let order: Vec<_> = headers
+                    .get_all("x-order")
+                    .iter()
+                    .map(|name| name.to_str().unwrap())
+                    .collect();
+                order.join(",")
+            }),
+        )
+        .layer(middleware_stack!(
+            from_fn(tag("first")),
+            from_fn(tag("second")),
+            from_fn(tag("third")),
+        ));
+
+    let response = router
+        .call(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    // the first layer sees the request first and the response last
+    let order: Vec<_> = response.headers().get_all("x-order").iter().collect();
+    assert_eq!(order, ["third", "second", "first"]);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, "first,second,third");
+}
+
+#[tokio::test]
+async fn content_type_guess() {
+    /// Answer a request with a response without a `Content-Type`, whose body gets sent in the given chunks, and return the guessed
+    /// content type together with the body.
+    async fn guess(chunks: Vec<String>) -> (Option<String>, String) {
+        let mut router = Router::new()
+            .route(
+                "/",
+                get(move || async move {
+                    let (mut sender, body) = Body::channel();
+                    tokio::spawn(async move {
+                        for chunk in chunks {
+                            sender.send_data(chunk.into()).await.unwrap();
+                        }
+                    });
+                    Response::new(body)
+                }),
+            )
+            .layer(ContentTypeGuessLayer);
+        let response = router
+            .call(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(|content_type| content_type.to_str().unwrap().to_string());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    let cases: [(&[&str], Option<&str>); 7] = [
+        (&["{\"key\": ", "1}"], Some("application/json")),
+        (&["\n  [1, 2]"], Some("application/json")),
+        (
+            &["<!DOCTYPE html><p>hi</p>"],
+            Some("text/html; charset=utf-8"),
+        ),
+        (&["<", "html>", "</html>"], Some("text/html; charset=utf-8")),
+        (&["<?xml version=\"1.0\"?><a/>"], Some("application/xml")),
+        (&["\0binary"], Some("application/octet-stream")),
+        (&[], None),
+    ];
+    for (chunks, content_type) in cases {
+        let (guessed, body) = guess(chunks.iter().map(ToString::to_string).collect()).await;
+        assert_eq!(guessed.as_deref(), content_type, "{chunks:?}");
+        assert_eq!(body, chunks.concat());
+    }
+
+    // only the first 512 bytes are looked at, and the rest of the body still follows them
+    let (guessed, body) = guess(vec![" ".repeat(600), "{}".to_string()]).await;
+    assert_eq!(guessed.as_deref(), Some("application/octet-stream"));
+    assert_eq!(body.len(), 602);
+
+    // an explicit content type is kept
+    let mut router = Router::new()
+        .route("/", get(|| async { "{\"text\": true}" }))
+        .layer(ContentTypeGuessLayer);
+    let response = router
+        .call(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(
+        response.headers()[CONTENT_TYPE],
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[tokio::test]
+async fn dns_rebind_protection() {
+    let mut router =
+        Router::new()
+            .route("/", get(|| async { "index" }))
+            .layer(DnsRebindProtectionLayer {
+                allowed_hosts: vec!["Thermostat.local.".to_string()],
+            });
+    let mut status = |host: Option<&str>| {
+        let mut request = Request::get("/");
+        if let Some(host) = host {
+            request = request.header(HOST, host);
+        }
+        let response = router.call(request.body(Body::empty()).unwrap());
+        async move { response.await.unwrap().status() }
+    };
+
+    for host in [
+        None,
+        Some("thermostat.local"),
+        Some("THERMOSTAT.LOCAL.:8080"),
+        Some("localhost:3000"),
+        Some("192.168.4.1"),
+        Some("192.168.4.1:80"),
+        Some("[::1]:8080"),
+        Some("[fe80::1]"),
+    ] {
+        assert_eq!(status(host).await, StatusCode::OK, "{host:?}");
+    }
+    for host in [
+        Some("attacker.example"),
+        Some("thermostat.local.attacker.example"),
+        Some("localhost.attacker.example"),
+        Some("[attacker.example]"),
+        Some(""),
+    ] {
+        assert_eq!(status(host).await, StatusCode::FORBIDDEN, "{host:?}");
+    }
+}
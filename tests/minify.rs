@@ -0,0 +1,122 @@
+use goohttp::{
+    axum::{
+        routing::get,
+        Router,
+    },
+    middleware::minify::HtmlMinifyLayer,
+    response::{
+        HtmlResponse,
+        PlainText,
+    },
+};
+use hyper::{
+    service::Service,
+    Body,
+    Request,
+};
+
+/// A page indented like it would be by a template.
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+    <head>
+        <title>Settings</title>
+        <link rel="stylesheet" href="/style.css">
+    </head>
+    <body>
+        <nav>
+            <a href="/">Home</a>
+            <a href="/settings">Settings</a>
+        </nav>
+        <main>
+            <h1>Settings</h1>
+            <form method="post" action="/settings">
+                <label for="ssid">SSID</label>
+                <input id="ssid" name="ssid" type="text">
+                <label for="password">Password</label>
+                <input id="password" name="password" type="password">
+                <button type="submit">Save</button>
+            </form>
+        </main>
+    </body>
+</html>
+"#;
+
+/// Send the given HTML through a router with the [`HtmlMinifyLayer`] and get the body of the response.
+async fn minify(html: &str) -> String {
+    let html = html.to_string();
+    let mut router = Router::new()
+        .route(
+            "/",
+            get(move || {
+                let html = html.clone();
+                async move { HtmlResponse(html) }
+            }),
+        )
+        .layer(HtmlMinifyLayer);
+    let response = router
+        .call(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn whitespace_between_tags() {
+    assert_eq!(
+        minify("<ul>\n    <li>Hello World</li>\n    <li> spaced </li>\n</ul>\n").await,
+        "<ul><li>Hello World</li><li> spaced </li></ul>\n"
+    );
+}
+
+#[tokio::test]
+async fn whitespace_between_inline_tags() {
+    // the space between inline elements is displayed, while the one next to the paragraph is not
+    assert_eq!(
+        minify("<p>\n    <b>Hello</b>\n    <i>World</i><br>\n    <A href=\"/\">Home</A>\n</p>")
+            .await,
+        "<p><b>Hello</b> <i>World</i><br> <A href=\"/\">Home</A></p>"
+    );
+}
+
+#[tokio::test]
+async fn preserved_tags() {
+    let cases = [
+        "<div><pre>\n  indented\n    text\n</pre></div>",
+        "<div><code> <b>bold</b> <i>italic</i> </code></div>",
+        "<div><textarea name=\"notes\">\n  <keep>  </keep>\n</textarea></div>",
+        "<div><SCRIPT>\n  if (a <b) { run(); }\n  let html = '<p> </p>';\n</SCRIPT></div>",
+    ];
+    for html in cases {
+        let indented = html.replacen("<div>", "<div>\n    ", 1);
+        assert_eq!(minify(&indented).await, html);
+    }
+}
+
+#[tokio::test]
+async fn other_content_types_stay_unchanged() {
+    let mut router = Router::new()
+        .route(
+            "/",
+            get(|| async { PlainText("<p>\n  text\n</p>".to_string()) }),
+        )
+        .layer(HtmlMinifyLayer);
+    let response = router
+        .call(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(body, "<p>\n  text\n</p>");
+}
+
+#[tokio::test]
+async fn size_reduction() {
+    let minified = minify(PAGE).await;
+    assert!(
+        minified.len() * 10 < PAGE.len() * 7,
+        "`{}` bytes were minified to `{}` bytes",
+        PAGE.len(),
+        minified.len()
+    );
+    assert!(minified.starts_with("<!DOCTYPE html><html><head><title>Settings</title>"));
+}
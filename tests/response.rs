@@ -0,0 +1,229 @@
+use goohttp::{
+    axum::{
+        http::{
+            header::{
+                ACCEPT,
+                ACCEPT_RANGES,
+                CONTENT_RANGE,
+                CONTENT_TYPE,
+                RANGE,
+            },
+            HeaderMap,
+            HeaderValue,
+            StatusCode,
+        },
+        response::IntoResponse,
+        routing::get,
+        Router,
+    },
+    mime,
+    response::{
+        negotiate,
+        ContentDisposition,
+        HtmlResponse,
+        PlainText,
+        RangeRequest,
+        RangeResponse,
+        Xml,
+    },
+};
+use hyper::{
+    body::to_bytes,
+    service::Service,
+    Body,
+    Request,
+};
+
+#[test]
+fn content_disposition() {
+    assert_eq!(
+        ContentDisposition::attachment("report.pdf"),
+        "attachment; filename=\"report.pdf\""
+    );
+    assert_eq!(
+        ContentDisposition::attachment("say \"hi\".txt"),
+        "attachment; filename=\"say \\\"hi\\\".txt\""
+    );
+    assert_eq!(
+        ContentDisposition::attachment("Grüße 📄.pdf"),
+        "attachment; filename=\"Gr__e _.pdf\"; filename*=UTF-8''Gr%C3%BC%C3%9Fe%20%F0%9F%93%84.pdf"
+    );
+    assert_eq!(
+        ContentDisposition::inline("naïve.txt"),
+        "inline; filename=\"na_ve.txt\"; filename*=UTF-8''na%C3%AFve.txt"
+    );
+}
+
+#[tokio::test]
+async fn content_types() {
+    let responses = [
+        (
+            HtmlResponse("<p>Hello</p>".to_string()).into_response(),
+            "text/html; charset=utf-8",
+            "<p>Hello</p>",
+        ),
+        (
+            PlainText("Hello".to_string()).into_response(),
+            "text/plain; charset=utf-8",
+            "Hello",
+        ),
+        (
+            Xml("<hello/>".to_string()).into_response(),
+            "application/xml; charset=utf-8",
+            "<hello/>",
+        ),
+    ];
+
+    for (response, content_type, body) in responses {
+        assert_eq!(response.headers()[CONTENT_TYPE], content_type);
+        let response_body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(response_body, body);
+    }
+}
+
+#[test]
+fn content_negotiation() {
+    let available = [mime::APPLICATION_JSON, mime::TEXT_PLAIN];
+    let cases = [
+        (None, Some(mime::APPLICATION_JSON)),
+        (Some("application/json"), Some(mime::APPLICATION_JSON)),
+        (Some("text/plain"), Some(mime::TEXT_PLAIN)),
+        (Some("TEXT/PLAIN; charset=utf-8"), Some(mime::TEXT_PLAIN)),
+        (Some("*/*"), Some(mime::APPLICATION_JSON)),
+        (Some("text/*"), Some(mime::TEXT_PLAIN)),
+        // the quality decides, and ties go to the order of the route
+        (
+            Some("application/json;q=0.5, text/plain"),
+            Some(mime::TEXT_PLAIN),
+        ),
+        (
+            Some("text/plain, application/json"),
+            Some(mime::APPLICATION_JSON),
+        ),
+        (Some("text/html, */*;q=0.1"), Some(mime::APPLICATION_JSON)),
+        // the most specific range counts, even with a lower quality
+        (Some("*/*, application/json;q=0"), Some(mime::TEXT_PLAIN)),
+        (
+            Some("text/*;q=0.2, text/plain;q=0.9, */*;q=0.5"),
+            Some(mime::TEXT_PLAIN),
+        ),
+        // nothing acceptable
+        (Some("text/html"), None),
+        (Some("*/*;q=0"), None),
+        // malformed ranges get ignored
+        (
+            Some("text/plain;q=2, application/json"),
+            Some(mime::APPLICATION_JSON),
+        ),
+        (Some("garbage"), Some(mime::APPLICATION_JSON)),
+    ];
+    for (accept, expected) in cases {
+        let mut headers = HeaderMap::new();
+        if let Some(accept) = accept {
+            headers.insert(ACCEPT, HeaderValue::from_static(accept));
+        }
+        assert_eq!(
+            negotiate(&headers, &available),
+            expected,
+            "wrong media type for {accept:?}"
+        );
+    }
+
+    // ranges of several headers count together
+    let mut headers = HeaderMap::new();
+    headers.append(ACCEPT, HeaderValue::from_static("application/json;q=0.3"));
+    headers.append(ACCEPT, HeaderValue::from_static("text/plain;q=0.4"));
+    assert_eq!(negotiate(&headers, &available), Some(mime::TEXT_PLAIN));
+    assert_eq!(negotiate(&headers, &[]), None);
+}
+
+#[test]
+fn range_requests() {
+    let cases = [
+        (None, RangeRequest::Full),
+        (Some("bytes=0-99"), RangeRequest::Partial(0..100)),
+        (Some("bytes=100-"), RangeRequest::Partial(100..1000)),
+        (Some("Bytes = 900-5000"), RangeRequest::Partial(900..1000)),
+        (Some("bytes=-300"), RangeRequest::Partial(700..1000)),
+        (Some("bytes=-5000"), RangeRequest::Partial(0..1000)),
+        (Some("bytes=999-999"), RangeRequest::Partial(999..1000)),
+        (Some("bytes=1000-"), RangeRequest::Unsatisfiable),
+        (Some("bytes=1000-2000"), RangeRequest::Unsatisfiable),
+        (Some("bytes=-0"), RangeRequest::Unsatisfiable),
+        // malformed headers get ignored
+        (Some("bytes=50-10"), RangeRequest::Full),
+        (Some("bytes=a-b"), RangeRequest::Full),
+        (Some("bytes=+5-10"), RangeRequest::Full),
+        (Some("bytes=5"), RangeRequest::Full),
+        (Some("bytes"), RangeRequest::Full),
+        (Some("items=0-5"), RangeRequest::Full),
+        // several ranges are answered with the whole body
+        (Some("bytes=0-5, 10-15"), RangeRequest::Full),
+    ];
+    for (header, expected) in cases {
+        let header = header.map(HeaderValue::from_static);
+        assert_eq!(
+            RangeRequest::parse(header.as_ref(), 1000),
+            expected,
+            "wrong range for {header:?}"
+        );
+    }
+
+    let empty = HeaderValue::from_static("bytes=0-");
+    assert_eq!(
+        RangeRequest::parse(Some(&empty), 0),
+        RangeRequest::Unsatisfiable
+    );
+}
+
+#[tokio::test]
+async fn resumed_download() {
+    let mut router = Router::new().route(
+        "/firmware",
+        get(|headers: HeaderMap| async move {
+            let firmware: Vec<u8> = (0..=255).collect();
+            RangeResponse::new(&headers, firmware.len() as u64, move |range| {
+                firmware[range.start as usize..range.end as usize].to_vec()
+            })
+        }),
+    );
+
+    let cases = [
+        (None, StatusCode::OK, None, 0..256),
+        (
+            Some("bytes=200-"),
+            StatusCode::PARTIAL_CONTENT,
+            Some("bytes 200-255/256"),
+            200..256,
+        ),
+        (
+            Some("bytes=256-"),
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            Some("bytes */256"),
+            0..0,
+        ),
+        (Some("bytes=oops"), StatusCode::OK, None, 0..256),
+    ];
+    for (range, status, content_range, body) in cases {
+        let mut request = Request::get("/firmware");
+        if let Some(range) = range {
+            request = request.header(RANGE, range);
+        }
+        let response = router
+            .call(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), status, "wrong status for {range:?}");
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+        assert_eq!(
+            response
+                .headers()
+                .get(CONTENT_RANGE)
+                .map(|value| value.to_str().unwrap()),
+            content_range
+        );
+        let expected: Vec<u8> = body.map(|byte| byte as u8).collect();
+        assert_eq!(to_bytes(response.into_body()).await.unwrap(), expected);
+    }
+}
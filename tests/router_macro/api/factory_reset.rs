@@ -0,0 +1,5 @@
+use goohttp::axum::response::IntoResponse;
+
+pub async fn factory_reset() -> impl IntoResponse {
+    "reset".into_response()
+}
@@ -0,0 +1,52 @@
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+};
+
+use goohttp::axum::{
+    extract::Path,
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+static MCSERVERS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+pub async fn index() -> impl IntoResponse {
+    let mcservers = MCSERVERS.lock().unwrap();
+    mcservers.keys().cloned().collect::<Vec<_>>().join(",")
+}
+
+pub async fn show(Path(id): Path<String>) -> impl IntoResponse {
+    match MCSERVERS.lock().unwrap().get(&id) {
+        Some(mcserver) => mcserver.clone().into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+pub async fn create(body: String) -> impl IntoResponse {
+    let Some((id, mcserver)) = body.split_once('=') else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    MCSERVERS
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), mcserver.to_string());
+    StatusCode::CREATED.into_response()
+}
+
+pub async fn update(Path(id): Path<String>, body: String) -> impl IntoResponse {
+    match MCSERVERS.lock().unwrap().get_mut(&id) {
+        Some(mcserver) => {
+            *mcserver = body;
+            StatusCode::NO_CONTENT
+        }
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+pub async fn destroy(Path(id): Path<String>) -> impl IntoResponse {
+    match MCSERVERS.lock().unwrap().remove(&id) {
+        Some(_) => StatusCode::NO_CONTENT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
@@ -1,8 +1,44 @@
-use goohttp::router;
+use goohttp::{
+    axum::{
+        body::Body,
+        http::{
+            header::AUTHORIZATION,
+            Request,
+            StatusCode,
+        },
+    },
+    router,
+};
+use report::ReportHandler;
 
 router! {
+    #![strict]
     api {
+        /// Greet the caller.
+        /// Nobody gets left out.
         say_hello, get, ":caller";
-        say_hello_caller_sender, get, ":caller", ":sender"
+        say_hello_caller_sender, get, ":caller", ":sender";
+        factory_reset, post, guard = authenticated, guard = admin_only;
+        upload, post, limit = 16;
+        update_settings, post, accept = ["application/json", "application/cbor"];
+        report, get, init = || async { ReportHandler::new().await };
+        sensor, get, timeout = 200ms;
+        /// Manage the Minecraft servers.
+        resource mcservers
     }
-}
\ No newline at end of file
+}
+
+async fn authenticated(request: &Request<Body>) -> Result<(), StatusCode> {
+    if request.headers().contains_key(AUTHORIZATION) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn admin_only(request: &Request<Body>) -> Result<(), StatusCode> {
+    match request.headers().get(AUTHORIZATION) {
+        Some(authorization) if authorization == "admin" => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+
+use goohttp::axum::{
+    response::IntoResponse,
+    Extension,
+};
+
+/// How often a [`ReportHandler`] got created.
+static REPORT_HANDLERS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone)]
+pub struct ReportHandler {
+    title: String,
+}
+impl ReportHandler {
+    pub async fn new() -> Self {
+        let number = REPORT_HANDLERS.fetch_add(1, Ordering::SeqCst);
+        Self {
+            title: format!("report #{number}"),
+        }
+    }
+}
+
+pub async fn report(Extension(handler): Extension<ReportHandler>) -> impl IntoResponse {
+    handler.title
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+use goohttp::axum::{
+    http::Uri,
+    response::IntoResponse,
+};
+
+/// Read the sensor, which takes a second if the request asks for a `slow` reading.
+pub async fn sensor(uri: Uri) -> impl IntoResponse {
+    if uri.query() == Some("slow") {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    "21.5 °C"
+}
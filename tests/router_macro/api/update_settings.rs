@@ -0,0 +1,5 @@
+use goohttp::axum::response::IntoResponse;
+
+pub async fn update_settings(body: String) -> impl IntoResponse {
+    format!("updated settings to {body}").into_response()
+}
@@ -0,0 +1,8 @@
+use goohttp::axum::{
+    body::Bytes,
+    response::IntoResponse,
+};
+
+pub async fn upload(body: Bytes) -> impl IntoResponse {
+    format!("uploaded {} bytes", body.len()).into_response()
+}
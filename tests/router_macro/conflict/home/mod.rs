@@ -0,0 +1,7 @@
+use goohttp::router;
+
+router! {
+    home {
+        index, get
+    }
+}
@@ -0,0 +1,9 @@
+use goohttp::router;
+
+// Both the router and its root group declare `GET /`.
+router! {
+    conflicting {
+        index, get;
+        home (root)
+    }
+}
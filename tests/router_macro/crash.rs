@@ -0,0 +1,3 @@
+pub async fn crash() -> &'static str {
+    panic!("This route should be skipped by the smoke tests.")
+}
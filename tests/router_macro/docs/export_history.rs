@@ -0,0 +1,5 @@
+//! A route with a timeout of the `router` macro doc example.
+
+pub async fn export_history() -> &'static str {
+    "[]"
+}
@@ -0,0 +1,5 @@
+//! The route of the `HttpServer` doc example.
+
+pub async fn get_list() -> &'static str {
+    "[]"
+}
@@ -0,0 +1,5 @@
+//! A route with a timeout of the `router` macro doc example.
+
+pub async fn get_sensor() -> &'static str {
+    "21.5 °C"
+}
@@ -0,0 +1,7 @@
+//! The route with extensions of the `router` macro doc example.
+
+use goohttp::axum::extract::Path;
+
+pub async fn get_user(Path(id): Path<u32>) -> String {
+    format!("user #{id}")
+}
@@ -0,0 +1,11 @@
+//! The resource with only some routes of the `router` macro doc example.
+
+use goohttp::axum::extract::Path;
+
+pub async fn index() -> &'static str {
+    "boot"
+}
+
+pub async fn show(Path(id): Path<String>) -> String {
+    id
+}
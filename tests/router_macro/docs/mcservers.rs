@@ -0,0 +1,23 @@
+//! The resource of the `router` macro doc example.
+
+use goohttp::axum::extract::Path;
+
+pub async fn index() -> &'static str {
+    "lobby"
+}
+
+pub async fn show(Path(id): Path<String>) -> String {
+    id
+}
+
+pub async fn create(body: String) -> String {
+    body
+}
+
+pub async fn update(Path(id): Path<String>, body: String) -> String {
+    format!("{id}={body}")
+}
+
+pub async fn destroy(Path(id): Path<String>) -> String {
+    id
+}
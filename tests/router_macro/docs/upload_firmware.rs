@@ -0,0 +1,7 @@
+//! The route with a body limit of the `router` macro doc example.
+
+use goohttp::axum::body::Bytes;
+
+pub async fn upload_firmware(firmware: Bytes) -> String {
+    format!("received {} bytes", firmware.len())
+}
@@ -1,11 +1,22 @@
-use goohttp::router;
+use goohttp::{
+    router,
+    RouteInfo,
+};
 use hyper::{
     body::HttpBody,
+    header::{
+        AUTHORIZATION,
+        CONTENT_LENGTH,
+        CONTENT_TYPE,
+    },
     service::Service,
     Body,
     Request,
+    StatusCode,
 };
 
+// newer versions of clippy flag the `to_vec` calls of the original assertions
+#[allow(clippy::unnecessary_to_owned)]
 #[tokio::test]
 async fn main() {
     let mut website = website();
@@ -64,10 +75,437 @@ async fn main() {
     );
 }
 
+#[tokio::test]
+async fn head_requests() {
+    let mut website = website();
+
+    // routes declared with `get` also answer `HEAD` requests, with the headers of the `GET` response but without its body
+    let response = website
+        .call(Request::head("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_LENGTH], "5");
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(body.is_empty());
+}
+
+#[tokio::test]
+async fn urls() {
+    let mut website = website();
+
+    assert_eq!(urls::index, "/");
+    assert_eq!(urls::remaining(["some/route"]), "/some/route");
+
+    let say_hello_url = urls::api(&api::urls::say_hello(["My Client/1"]));
+    assert_eq!(say_hello_url, "/api/say_hello/My%20Client%2F1");
+
+    let say_hello_response = website
+        .call(Request::get(say_hello_url).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&say_hello_response).unwrap(),
+        "said hello from My Client/1"
+    );
+}
+
 router! {
+    #![with_tests]
+    #![generate_smoke_tests]
+    #![pub_handlers]
     website {
         index, get;
         remaining, get;
+        #[skip_smoke_test]
+        crash, get;
+        pages (root);
+        /// The API of the website, whose description is not given to its routes.
         api
     }
 }
+
+mod conflict;
+
+#[tokio::test]
+async fn pub_handlers() {
+    // re-exported by the `pub_handlers` flag, so the module path is not needed
+    let mut api = api();
+    let response = api
+        .call(Request::get("/say_hello/me").body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&response).unwrap(),
+        "said hello from me"
+    );
+
+    let mut pages = pages();
+    let response = pages
+        .call(Request::get("/about").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn root_groups() {
+    let mut website = website();
+    assert_eq!(urls::pages(pages::urls::about), "/about");
+
+    let about_response = website
+        .call(
+            Request::get(urls::pages(pages::urls::about))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(std::str::from_utf8(&about_response).unwrap(), "about");
+
+    // `/pages/about` is no route of the `pages` group anymore
+    let nested_response = website
+        .call(Request::get("/pages/about").body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&nested_response).unwrap(),
+        "called remaining with the route `pages/about`"
+    );
+}
+
+#[test]
+#[should_panic(expected = "Overlapping method route")]
+fn root_group_conflicts() {
+    let _ = conflict::conflicting();
+}
+
+#[tokio::test]
+async fn guards() {
+    let mut website = website();
+    let factory_reset_url = urls::api(api::urls::factory_reset);
+
+    let response = website
+        .call(
+            Request::post(&factory_reset_url)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = website
+        .call(
+            Request::post(&factory_reset_url)
+                .header(AUTHORIZATION, "guest")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let factory_reset_response = website
+        .call(
+            Request::post(&factory_reset_url)
+                .header(AUTHORIZATION, "admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&factory_reset_response).unwrap(),
+        "reset"
+    );
+}
+
+#[tokio::test]
+async fn limits() {
+    let mut website = website();
+    let upload_url = urls::api(api::urls::upload);
+
+    let upload_response = website
+        .call(
+            Request::post(&upload_url)
+                .body(Body::from(vec![0; 16]))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&upload_response).unwrap(),
+        "uploaded 16 bytes"
+    );
+
+    let response = website
+        .call(
+            Request::post(&upload_url)
+                .body(Body::from(vec![0; 17]))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn content_types() {
+    let mut website = website();
+    let update_settings_url = urls::api(api::urls::update_settings);
+
+    for content_type in ["text/plain", "application/jsonp"] {
+        let response = website
+            .call(
+                Request::post(&update_settings_url)
+                    .header(CONTENT_TYPE, content_type)
+                    .body(Body::from("{}"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+    let response = website
+        .call(
+            Request::post(&update_settings_url)
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let update_settings_response = website
+        .call(
+            Request::post(&update_settings_url)
+                .header(CONTENT_TYPE, "Application/JSON; charset=utf-8")
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&update_settings_response).unwrap(),
+        "updated settings to {}"
+    );
+}
+
+#[tokio::test]
+async fn timeouts() {
+    let mut website = website();
+    let sensor_url = urls::api(api::urls::sensor);
+
+    let sensor_response = website
+        .call(Request::get(&sensor_url).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .data()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(std::str::from_utf8(&sensor_response).unwrap(), "21.5 °C");
+
+    // the route sleeps for a second, but only has 200ms to answer
+    let started = std::time::Instant::now();
+    let response = website
+        .call(
+            Request::get(format!("{sensor_url}?slow"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(started.elapsed() < std::time::Duration::from_millis(900));
+}
+
+#[tokio::test]
+async fn initialization() {
+    let router = website();
+    let report_url = urls::api(api::urls::report);
+    let report = |mut website: goohttp::axum::Router| {
+        let report_url = report_url.clone();
+        async move {
+            let response = website
+                .call(Request::get(&report_url).body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .data()
+                .await
+                .unwrap()
+                .unwrap();
+            String::from_utf8(response.to_vec()).unwrap()
+        }
+    };
+
+    // requests arriving at the same time share the same value
+    let (first, second) = tokio::join!(report(router.clone()), report(router.clone()));
+    assert!(first.starts_with("report #"));
+    assert_eq!(first, second);
+    assert_eq!(report(router).await, first);
+
+    // a new router creates a new value
+    assert_ne!(report(website()).await, first);
+}
+
+/// A router combining other routers.
+mod combined {
+    use goohttp::{
+        axum::{
+            routing::get,
+            Router,
+        },
+        router,
+    };
+
+    router! {
+        combined_app {
+            use super::api::api;
+            use admin_router;
+        }
+    }
+
+    pub fn admin_router() -> Router {
+        Router::new().route("/admin", get(|| async { "admin" }))
+    }
+}
+
+#[tokio::test]
+async fn combined_routers() {
+    let mut combined_app = combined::combined_app();
+
+    for (path, body) in [("/admin", "admin"), ("/say_hello/me", "said hello from me")] {
+        let response = combined_app
+            .call(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .data()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::str::from_utf8(&response).unwrap(), body);
+    }
+    assert!(combined::router_routes().is_empty());
+}
+
+#[tokio::test]
+async fn resources() {
+    use api::urls::mcservers;
+
+    let mut website = website();
+    let mut send = |request: Request<Body>| {
+        let response = website.call(request);
+        async move {
+            let response = response.await.unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+    };
+
+    let (status, _) = send(
+        Request::post(urls::api(mcservers::create))
+            .body(Body::from("lobby=Lobby"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    send(
+        Request::post(urls::api(mcservers::create))
+            .body(Body::from("survival=Survival"))
+            .unwrap(),
+    )
+    .await;
+
+    let (_, mcserver_ids) = send(
+        Request::get(urls::api(mcservers::index))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(mcserver_ids, "lobby,survival");
+
+    let (status, _) = send(
+        Request::put(urls::api(&mcservers::update(["lobby"])))
+            .body(Body::from("Hub"))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, mcserver) = send(
+        Request::get(urls::api(&mcservers::show(["lobby"])))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(mcserver, "Hub");
+
+    let (status, _) = send(
+        Request::delete(urls::api(&mcservers::destroy(["lobby"])))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(
+        Request::get(urls::api(&mcservers::show(["lobby"])))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn route_table() {
+    let routes = router_routes();
+    let route = |method: &str, path: &str, smoke_test: bool| RouteInfo {
+        method: method.to_string(),
+        path: path.to_string(),
+        smoke_test,
+        description: None,
+    };
+
+    assert!(routes.contains(&route("GET", "/", true)));
+    assert!(routes.contains(&route("GET", "/crash", false)));
+    assert!(routes.contains(&route("GET", "/about", true)));
+    assert!(routes.contains(&RouteInfo {
+        description: Some("Greet the caller.\nNobody gets left out.".to_string()),
+        ..route("GET", "/api/say_hello/:caller", true)
+    }));
+    assert!(routes.contains(&RouteInfo {
+        description: Some("Manage the Minecraft servers.".to_string()),
+        ..route("DELETE", "/api/mcservers/:id", true)
+    }));
+    assert_eq!(routes.len(), 17);
+}
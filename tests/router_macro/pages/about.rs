@@ -0,0 +1,5 @@
+use goohttp::axum::response::IntoResponse;
+
+pub async fn about() -> impl IntoResponse {
+    "about".into_response()
+}
@@ -0,0 +1,8 @@
+use goohttp::router;
+
+router! {
+    pages {
+        about, get;
+        settings, get
+    }
+}
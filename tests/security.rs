@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+
+use goohttp::{
+    axum::{
+        http::header::CONTENT_SECURITY_POLICY,
+        response::IntoResponse,
+        routing::get,
+        Extension,
+        Router,
+    },
+    security::{
+        CspNonce,
+        CspNonceLayer,
+    },
+};
+use hyper::{
+    body::to_bytes,
+    service::Service,
+    Body,
+    Request,
+};
+
+/// Send a `GET` request to the given path and return the `Content-Security-Policy` header and the body of the response.
+async fn get_policy(router: &mut Router, path: &str) -> (Option<String>, String) {
+    let response = router
+        .call(Request::get(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let policy = response
+        .headers()
+        .get(CONTENT_SECURITY_POLICY)
+        .map(|policy| policy.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body()).await.unwrap();
+    (policy, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn nonces_are_unique() {
+    let mut router = Router::new()
+        .route(
+            "/",
+            get(|Extension(CspNonce(nonce)): Extension<CspNonce>| async move { nonce }),
+        )
+        .layer(CspNonceLayer::with_policy("default-src 'self'"));
+
+    let mut nonces = HashSet::new();
+    for _ in 0..100 {
+        let (policy, nonce) = get_policy(&mut router, "/").await;
+        // 16 random bytes encoded with base64
+        assert_eq!(nonce.len(), 24, "{nonce}");
+        assert_eq!(
+            policy.unwrap(),
+            format!(
+                "default-src 'self'; script-src 'self' 'nonce-{nonce}'; style-src 'self' 'nonce-{nonce}'"
+            )
+        );
+        assert!(nonces.insert(nonce), "a nonce was generated twice");
+    }
+}
+
+#[tokio::test]
+async fn policies_of_routes() {
+    let mut router = Router::new()
+        .route(
+            "/script",
+            get(
+                |Extension(CspNonce(nonce)): Extension<CspNonce>| async move {
+                    (
+                        [(CONTENT_SECURITY_POLICY, "script-src 'none'; img-src *")],
+                        nonce,
+                    )
+                        .into_response()
+                },
+            ),
+        )
+        .route(
+            "/unrestricted",
+            get(|| async { ([(CONTENT_SECURITY_POLICY, "img-src 'self'")], "").into_response() }),
+        )
+        .route("/none", get(|| async { "" }))
+        .layer(CspNonceLayer::new());
+
+    let (policy, nonce) = get_policy(&mut router, "/script").await;
+    assert_eq!(
+        policy.unwrap(),
+        format!("script-src 'nonce-{nonce}'; img-src *")
+    );
+
+    let (policy, _) = get_policy(&mut router, "/unrestricted").await;
+    assert_eq!(policy.unwrap(), "img-src 'self'");
+
+    let (policy, _) = get_policy(&mut router, "/none").await;
+    assert_eq!(policy, None);
+}
@@ -0,0 +1,124 @@
+use goohttp::{
+    axum::{
+        http::HeaderMap,
+        routing::get,
+        Extension,
+        Router,
+    },
+    trace::{
+        TraceContext,
+        TraceContextLayer,
+    },
+};
+use hyper::{
+    body::to_bytes,
+    service::Service,
+    Body,
+    Request,
+};
+
+/// Answer a request with the given headers and return the `traceparent` and `tracestate` headers the route would send to the next
+/// server, together with the id of the span of its caller.
+async fn propagate(headers: &[(&str, &str)]) -> (String, Option<String>, Option<String>) {
+    let mut router = Router::new()
+        .route(
+            "/",
+            get(|Extension(context): Extension<TraceContext>| async move {
+                // the extension and the context of the task are the same
+                assert_eq!(TraceContext::current(), Some(context.clone()));
+                let mut headers = HeaderMap::new();
+                context.inject(&mut headers);
+                format!(
+                    "{}|{}|{}",
+                    headers["traceparent"].to_str().unwrap(),
+                    headers
+                        .get("tracestate")
+                        .map_or("", |trace_state| trace_state.to_str().unwrap()),
+                    context.parent_span_id().unwrap_or_default()
+                )
+            }),
+        )
+        .layer(TraceContextLayer);
+
+    let mut request = Request::get("/");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = router
+        .call(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = String::from_utf8(to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap();
+    let mut parts = body.split('|').map(str::to_string);
+    let traceparent = parts.next().unwrap();
+    let trace_state = parts.next().filter(|trace_state| !trace_state.is_empty());
+    let parent_span_id = parts.next().filter(|span_id| !span_id.is_empty());
+    (traceparent, trace_state, parent_span_id)
+}
+
+#[tokio::test]
+async fn traces_get_continued() {
+    let (traceparent, trace_state, parent_span_id) = propagate(&[
+        (
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        ),
+        ("tracestate", "congo=t61rcWkgMzE"),
+        ("tracestate", "rojo=00f067aa0ba902b7"),
+    ])
+    .await;
+
+    let fields: Vec<&str> = traceparent.split('-').collect();
+    assert_eq!(fields[0], "00");
+    assert_eq!(fields[1], "0af7651916cd43dd8448eb211c80319c");
+    // the route gets a span of its own, which becomes the parent of the next request
+    assert_eq!(fields[2].len(), 16);
+    assert_ne!(fields[2], "b7ad6b7169203331");
+    assert_eq!(fields[3], "01");
+    assert_eq!(parent_span_id.as_deref(), Some("b7ad6b7169203331"));
+    assert_eq!(
+        trace_state.as_deref(),
+        Some("congo=t61rcWkgMzE,rojo=00f067aa0ba902b7")
+    );
+
+    // later versions may add fields
+    let (traceparent, ..) = propagate(&[(
+        "traceparent",
+        "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-extra",
+    )])
+    .await;
+    assert!(traceparent.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+    assert!(traceparent.ends_with("-00"));
+}
+
+#[tokio::test]
+async fn invalid_traces_get_replaced() {
+    let invalid = [
+        "",
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+        "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+        "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+        "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+        "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+        "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    ];
+    for traceparent in invalid {
+        let (propagated, trace_state, parent_span_id) = propagate(&[
+            ("traceparent", traceparent),
+            ("tracestate", "congo=t61rcWkgMzE"),
+        ])
+        .await;
+
+        // a new sampled trace gets started, and the state of the old one gets dropped
+        assert_eq!(propagated.len(), 55, "{traceparent:?}");
+        assert!(!propagated.contains("0af7651916cd43dd8448eb211c80319c"));
+        assert!(propagated.ends_with("-01"));
+        assert_eq!(trace_state, None);
+        assert_eq!(parent_span_id, None);
+    }
+}
+
+#[tokio::test]
+async fn no_context_outside_of_requests() {
+    assert_eq!(TraceContext::current(), None);
+}
@@ -0,0 +1,5 @@
+use goohttp::axum::response::IntoResponse;
+
+pub async fn say_hello_renamed() -> impl IntoResponse {
+    "said hello"
+}
@@ -0,0 +1,11 @@
+mod timeout_api {
+    use goohttp::router;
+
+    router! {
+        timeout_api {
+            sensor, get, timeout = 500us
+        }
+    }
+}
+
+fn main() {}
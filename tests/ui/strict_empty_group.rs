@@ -0,0 +1,8 @@
+use goohttp::router;
+
+router! {
+    #![strict]
+    api {}
+}
+
+fn main() {}
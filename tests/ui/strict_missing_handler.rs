@@ -0,0 +1,12 @@
+mod api {
+    use goohttp::router;
+
+    router! {
+        #![strict]
+        api {
+            say_hello, get
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+mod servers_api {
+    use goohttp::router;
+
+    router! {
+        #![strict]
+        servers_api {
+            resource servers only(index, show)
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,5 @@
+use goohttp::axum::response::IntoResponse;
+
+pub async fn sensor() -> impl IntoResponse {
+    "21.5 °C"
+}
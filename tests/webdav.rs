@@ -0,0 +1,207 @@
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use goohttp::{
+    axum::{
+        body::Body,
+        http::{
+            Request,
+            StatusCode,
+        },
+        Router,
+    },
+    webdav::WebDavHandler,
+};
+use hyper::{
+    body::HttpBody,
+    service::Service,
+};
+
+/// Create an empty directory for the given test.
+fn root(test: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("goohttp-webdav-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    root
+}
+
+async fn send(root: &Path, request: Request<Body>) -> (StatusCode, String) {
+    let response = Router::new()
+        .fallback(WebDavHandler {
+            root: root.to_owned(),
+        })
+        .call(request)
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn request(method: &str, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::from(body.to_owned()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn listing_directories() {
+    let root = root("listing");
+    fs::write(root.join("notes.txt"), "hello").unwrap();
+    fs::create_dir(root.join("my photos")).unwrap();
+
+    let (status, body) = send(&root, request("PROPFIND", "/", "")).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(body.contains("<D:href>/</D:href>"));
+    assert!(body.contains(
+        "<D:href>/my%20photos/</D:href><D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype>"
+    ));
+    assert!(body.contains("<D:href>/notes.txt</D:href>"));
+    assert!(body.contains("<D:getcontentlength>5</D:getcontentlength>"));
+
+    let mut depth_zero = request("PROPFIND", "/", "");
+    depth_zero
+        .headers_mut()
+        .insert("depth", "0".parse().unwrap());
+    let (_, body) = send(&root, depth_zero).await;
+    assert!(!body.contains("notes.txt"));
+
+    let (status, _) = send(&root, request("PROPFIND", "/missing", "")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&root, request("GET", "/%2E%2E/secret", "")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn uploading() {
+    let root = root("uploading");
+
+    let (status, _) = send(&root, request("PUT", "/notes.txt", "first")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(&root, request("PUT", "/notes.txt", "second")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, body) = send(&root, request("GET", "/notes.txt", "")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "second");
+
+    let (status, _) = send(&root, request("MKCOL", "/backup", "")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(&root, request("MKCOL", "/backup", "")).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+
+    let mut copy = request("COPY", "/notes.txt", "");
+    copy.headers_mut().insert(
+        "destination",
+        "http://device.local/backup/notes.txt".parse().unwrap(),
+    );
+    let (status, _) = send(&root, copy).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        fs::read_to_string(root.join("backup/notes.txt")).unwrap(),
+        "second"
+    );
+
+    let mut copy = request("COPY", "/notes.txt", "");
+    copy.headers_mut()
+        .insert("destination", "/backup/notes.txt".parse().unwrap());
+    copy.headers_mut().insert("overwrite", "F".parse().unwrap());
+    let (status, _) = send(&root, copy).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn downloading_large_files() {
+    let root = root("downloading");
+    let file: Vec<u8> = (0..100_000).map(|byte| byte as u8).collect();
+    fs::write(root.join("firmware.bin"), &file).unwrap();
+
+    let response = Router::new()
+        .fallback(WebDavHandler {
+            root: root.to_owned(),
+        })
+        .call(request("GET", "/firmware.bin", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-length"], "100000");
+
+    // the file gets read in small chunks instead of as a whole
+    let mut body = response.into_body();
+    let mut downloaded = vec![];
+    let mut chunks = 0;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        assert!(chunk.len() <= 4096);
+        downloaded.extend_from_slice(&chunk);
+        chunks += 1;
+    }
+    assert!(chunks > 1);
+    assert_eq!(downloaded, file);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn deleting_files() {
+    let root = root("deleting");
+    fs::write(root.join("notes.txt"), "hello").unwrap();
+    fs::create_dir(root.join("photos")).unwrap();
+    fs::write(root.join("photos/cat.jpg"), "meow").unwrap();
+
+    let (status, _) = send(&root, request("DELETE", "/notes.txt", "")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(!root.join("notes.txt").exists());
+    let (status, _) = send(&root, request("DELETE", "/notes.txt", "")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&root, request("DELETE", "/photos", "")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(!root.join("photos").exists());
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn protecting_the_root() {
+    let root = root("protecting");
+    fs::create_dir(root.join("photos")).unwrap();
+    fs::write(root.join("photos/cat.jpg"), "meow").unwrap();
+
+    let (status, _) = send(&root, request("DELETE", "/", "")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let mut copy = request("COPY", "/photos", "");
+    copy.headers_mut()
+        .insert("destination", "http://device.local/".parse().unwrap());
+    let (status, _) = send(&root, copy).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // a copy inside of the source would contain itself
+    for destination in ["/photos/backup", "/photos"] {
+        let mut copy = request("COPY", "/photos", "");
+        copy.headers_mut()
+            .insert("destination", destination.parse().unwrap());
+        let (status, _) = send(&root, copy).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    assert_eq!(
+        fs::read_to_string(root.join("photos/cat.jpg")).unwrap(),
+        "meow"
+    );
+    assert!(!root.join("photos/backup").exists());
+
+    fs::remove_dir_all(root).unwrap();
+}